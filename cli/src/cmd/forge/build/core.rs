@@ -13,7 +13,7 @@ use foundry_config::{
         value::{Dict, Map, Value},
         Figment, Metadata, Profile, Provider,
     },
-    Config,
+    Config, RenameProfileProvider,
 };
 use serde::Serialize;
 use std::path::PathBuf;
@@ -60,7 +60,14 @@ pub struct CoreBuildArgs {
     #[serde(skip)]
     pub deny_warnings: bool,
 
-    #[clap(help_heading = "Compiler options", help = "Do not auto-detect solc.", long)]
+    #[clap(
+        help_heading = "Compiler options",
+        help = "Do not auto-detect solc.",
+        long_help = "Do not auto-detect solc. With zk-build and zk-test, zksolc is never downloaded either and \
+        the build fails if it isn't installed yet. A security-hardening option for hermetic builds, which must not \
+        download unreviewed binaries.",
+        long
+    )]
     #[serde(skip)]
     pub no_auto_detect: bool,
 
@@ -74,7 +81,7 @@ pub struct CoreBuildArgs {
     #[clap(
         help_heading = "Compiler options",
         help = "Do not access the network.",
-        long_help = "Do not access the network. Missing solc versions will not be installed.",
+        long_help = "Do not access the network. Missing solc and zksolc versions will not be installed.",
         long
     )]
     #[serde(skip)]
@@ -153,12 +160,11 @@ impl CoreBuildArgs {
     pub fn get_remappings(&self) -> Vec<Remapping> {
         self.project_paths.get_remappings()
     }
-}
 
-// Loads project's figment and merges the build cli arguments into it
-impl<'a> From<&'a CoreBuildArgs> for Figment {
-    fn from(args: &'a CoreBuildArgs) -> Self {
-        let figment = if let Some(ref config_path) = args.project_paths.config_path {
+    /// Loads the project's figment with the sections of `profile` selected, see
+    /// `Config::figment_with_root_and_profile`, and merges the build cli arguments into it.
+    pub fn figment_with_profile(&self, profile: Profile) -> Figment {
+        let figment = if let Some(ref config_path) = self.project_paths.config_path {
             if !config_path.exists() {
                 panic!("error: config-path `{}` does not exist", config_path.display())
             }
@@ -166,18 +172,27 @@ impl<'a> From<&'a CoreBuildArgs> for Figment {
                 panic!("error: the config-path must be a path to a foundry.toml file")
             }
             let config_path = canonicalized(config_path);
-            Config::figment_with_root(config_path.parent().unwrap())
+            Config::figment_with_root_and_profile(config_path.parent().unwrap(), profile.clone())
         } else {
-            Config::figment_with_root(args.project_paths.project_root())
+            Config::figment_with_root_and_profile(self.project_paths.project_root(), profile.clone())
         };
 
         // remappings should stack
-        let mut remappings = args.project_paths.get_remappings();
+        let mut remappings = self.project_paths.get_remappings();
         remappings
             .extend(figment.extract_inner::<Vec<Remapping>>("remappings").unwrap_or_default());
         remappings.sort_by(|a, b| a.name.cmp(&b.name));
         remappings.dedup_by(|a, b| a.name.eq(&b.name));
-        figment.merge(("remappings", remappings)).merge(args)
+        figment
+            .merge(("remappings", remappings))
+            .merge(RenameProfileProvider::new(self, Config::selected_profile(), profile))
+    }
+}
+
+// Loads project's figment and merges the build cli arguments into it
+impl<'a> From<&'a CoreBuildArgs> for Figment {
+    fn from(args: &'a CoreBuildArgs) -> Self {
+        args.figment_with_profile(Config::selected_profile())
     }
 }
 
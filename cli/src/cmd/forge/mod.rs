@@ -63,7 +63,23 @@ pub mod tree;
 pub mod update;
 pub mod verify;
 pub mod watch;
+pub mod zk_abi;
+pub mod zk_artifacts;
 pub mod zk_build;
+pub mod zk_cache;
+pub mod zk_compiler;
 pub mod zk_create;
+pub mod zk_deploy;
+pub mod zk_doc;
+pub mod zk_flatten;
+pub mod zk_gas_report;
+pub mod zk_inspect;
+pub mod zk_remappings;
+pub mod zk_selectors;
+pub mod zk_snapshot;
+pub mod zk_storage_layout;
+pub mod zk_test;
+pub mod zk_update;
+pub mod zk_verify;
 pub mod zksolc;
 pub mod zksolc_manager;
@@ -273,7 +273,7 @@ impl FromStr for SnapshotEntry {
 }
 
 /// Reads a list of snapshot entries from a snapshot file
-fn read_snapshot(path: impl AsRef<Path>) -> eyre::Result<Vec<SnapshotEntry>> {
+pub(crate) fn read_snapshot(path: impl AsRef<Path>) -> eyre::Result<Vec<SnapshotEntry>> {
     let path = path.as_ref();
     let mut entries = Vec::new();
     for line in io::BufReader::new(
@@ -412,7 +412,7 @@ fn diff(tests: Vec<Test>, snaps: Vec<SnapshotEntry>) -> eyre::Result<()> {
     Ok(())
 }
 
-fn fmt_pct_change(change: f64) -> String {
+pub(crate) fn fmt_pct_change(change: f64) -> String {
     let change_pct = change * 100.0;
     match change.partial_cmp(&0.0).unwrap_or(Ordering::Equal) {
         Ordering::Less => Paint::green(format!("{change_pct:.3}%")).to_string(),
@@ -423,7 +423,7 @@ fn fmt_pct_change(change: f64) -> String {
     }
 }
 
-fn fmt_change(change: i128) -> String {
+pub(crate) fn fmt_change(change: i128) -> String {
     match change.cmp(&0) {
         Ordering::Less => Paint::green(format!("{change}")).to_string(),
         Ordering::Equal => {
@@ -436,7 +436,11 @@ fn fmt_change(change: i128) -> String {
 /// Returns true of the difference between the gas values exceeds the tolerance
 ///
 /// If `tolerance` is `None`, then this returns `true` if both gas values are equal
-fn within_tolerance(source_gas: u64, target_gas: u64, tolerance_pct: Option<u32>) -> bool {
+pub(crate) fn within_tolerance(
+    source_gas: u64,
+    target_gas: u64,
+    tolerance_pct: Option<u32>,
+) -> bool {
     if let Some(tolerance) = tolerance_pct {
         let (hi, lo) = if source_gas > target_gas {
             (source_gas, target_gas)
@@ -1,12 +1,15 @@
 //! Watch mode support
 
 use crate::{
-    cmd::forge::{build::BuildArgs, snapshot::SnapshotArgs, test::TestArgs},
+    cmd::forge::{
+        build::BuildArgs, snapshot::SnapshotArgs, test::TestArgs, zk_build::ZkBuildArgs,
+        zk_test::ZkTestArgs,
+    },
     utils::{self, FoundryPathExt},
 };
 use clap::{ArgAction, Parser};
 use foundry_config::Config;
-use std::{collections::HashSet, convert::Infallible, path::PathBuf, sync::Arc};
+use std::{collections::HashSet, convert::Infallible, path::PathBuf, sync::Arc, time::Duration};
 use tracing::trace;
 use watchexec::{
     action::{Action, Outcome, PreSpawn},
@@ -19,6 +22,12 @@ use watchexec::{
     Watchexec,
 };
 
+/// The default debounce delay of the zkSync commands.
+///
+/// Compiling with zksolc is slower than with solc, so change events are accumulated for longer
+/// to avoid restarting the compilation for every file that is saved.
+const ZK_WATCH_DELAY: Duration = Duration::from_millis(200);
+
 #[derive(Debug, Clone, Parser, Default)]
 #[clap(next_help_heading = "Watch options")]
 pub struct WatchArgs {
@@ -103,6 +112,58 @@ pub async fn watch_build(args: BuildArgs) -> eyre::Result<()> {
     Ok(())
 }
 
+/// Executes a [`Watchexec`] that listens for changes in the project's src dir and reruns `forge
+/// zk-build`
+///
+/// Unless `--watch-delay` is set, change events are debounced for [`ZK_WATCH_DELAY`].
+pub async fn watch_zk_build(args: ZkBuildArgs) -> eyre::Result<()> {
+    let (init, mut runtime) = args.watchexec_config()?;
+    if args.watch.watch_delay.is_none() {
+        runtime.action_throttle(ZK_WATCH_DELAY);
+    }
+    let cmd = cmd_args(args.watch.watch.as_ref().map(|paths| paths.len()).unwrap_or_default());
+
+    trace!("watch zk-build cmd={:?}", cmd);
+    runtime.command(watch_command(cmd.clone()));
+
+    let wx = Watchexec::new(init, runtime.clone())?;
+    on_action(args.watch, runtime, Arc::clone(&wx), cmd, (), |_| {});
+
+    // start executing the command immediately
+    wx.send_event(Event::default(), Priority::default()).await?;
+    wx.main().await??;
+
+    Ok(())
+}
+
+/// Executes a [`Watchexec`] that listens for changes in the project's src and test dirs and reruns
+/// `forge zk-test`
+///
+/// Unless `--watch-delay` is set, change events are debounced for [`ZK_WATCH_DELAY`].
+pub async fn watch_zk_test(args: ZkTestArgs) -> eyre::Result<()> {
+    let (init, mut runtime) = args.build.watch.watchexec_config(|| {
+        let config = Config::from(&args.build);
+        vec![config.src, config.test]
+    })?;
+    if args.build.watch.watch_delay.is_none() {
+        runtime.action_throttle(ZK_WATCH_DELAY);
+    }
+    let cmd =
+        cmd_args(args.build.watch.watch.as_ref().map(|paths| paths.len()).unwrap_or_default());
+
+    trace!("watch zk-test cmd={:?}", cmd);
+    runtime.command(watch_command(cmd.clone()));
+
+    let wx = Watchexec::new(init, runtime.clone())?;
+    on_action(args.build.watch, runtime, Arc::clone(&wx), cmd, (), |_| {});
+
+    // start executing the command immediately
+    wx.send_event(Event::default(), Priority::default()).await?;
+    wx.main().await??;
+
+    Ok(())
+}
+
 /// Executes a [`Watchexec`] that listens for changes in the project's src dir and reruns `forge
 /// snapshot`
 pub async fn watch_snapshot(args: SnapshotArgs) -> eyre::Result<()> {
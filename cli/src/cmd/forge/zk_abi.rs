@@ -0,0 +1,221 @@
+//! The `zk_abi` module provides the `forge zk-abi` command, which prints the ABI of a contract
+//! compiled with `zk-build`.
+//!
+//! The ABI is read from the Foundry artifact of the contract in the `zksync` directory of the
+//! `out` path, and printed as JSON, compact by default or indented with `--pretty`, for
+//! `cast abi-decode` and frontend frameworks. `--functions`, `--events` and `--errors` only print
+//! the entries of the given types, and `--human` prints them as human readable signatures, like
+//! `function transfer(address,uint256) returns (bool)`.
+use super::zk_artifacts::read_artifact;
+use crate::cmd::{Cmd, LoadConfig};
+use clap::{Parser, ValueHint};
+use foundry_config::impl_figment_convert_basic;
+use serde_json::Value;
+use std::path::PathBuf;
+
+/// CLI arguments for `forge zk-abi`.
+#[derive(Debug, Clone, Parser)]
+pub struct ZkAbiArgs {
+    /// The name of the contract, optionally in the form `<path>:<contractname>`.
+    #[clap(value_name = "CONTRACT")]
+    pub contract: String,
+
+    /// Indent the JSON output.
+    #[clap(long, conflicts_with = "human")]
+    pub pretty: bool,
+
+    /// Only print the functions of the ABI.
+    #[clap(long)]
+    pub functions: bool,
+
+    /// Only print the events of the ABI.
+    #[clap(long)]
+    pub events: bool,
+
+    /// Only print the errors of the ABI.
+    #[clap(long)]
+    pub errors: bool,
+
+    /// Print human readable signatures, one per line, instead of JSON.
+    #[clap(long)]
+    pub human: bool,
+
+    #[clap(
+        help = "The project's root path. By default, this is the root directory of the current Git repository or the current working directory if it is not part of a Git repository",
+        long,
+        value_hint = ValueHint::DirPath,
+        value_name = "PATH"
+    )]
+    root: Option<PathBuf>,
+}
+impl_figment_convert_basic!(ZkAbiArgs);
+
+impl Cmd for ZkAbiArgs {
+    type Output = ();
+
+    /// Prints the ABI of the contract.
+    ///
+    /// # Errors
+    /// An error is returned if the contract was not compiled with `zk-build`, or if its artifact
+    /// has no ABI.
+    fn run(self) -> eyre::Result<Self::Output> {
+        let config = self.try_load_config_emit_warnings()?;
+        let out_dir = config.project_paths().artifacts.join("zksync");
+        let artifact = read_artifact(&out_dir, &self.contract)?;
+        let abi = match artifact["abi"].as_array() {
+            Some(abi) => abi,
+            None => eyre::bail!("The artifact of {} has no ABI", self.contract),
+        };
+
+        let entries = self.filter(abi);
+        if self.human {
+            for entry in entries {
+                println!("{}", human_readable(entry));
+            }
+        } else if self.pretty {
+            println!("{}", serde_json::to_string_pretty(&entries)?);
+        } else {
+            println!("{}", serde_json::to_string(&entries)?);
+        }
+        Ok(())
+    }
+}
+
+impl ZkAbiArgs {
+    /// This function returns the entries of `abi` selected by `--functions`, `--events` and
+    /// `--errors`, or all of them if none is passed.
+    fn filter<'a>(&self, abi: &'a [Value]) -> Vec<&'a Value> {
+        if !self.functions && !self.events && !self.errors {
+            return abi.iter().collect();
+        }
+        abi.iter()
+            .filter(|entry| match entry["type"].as_str() {
+                Some("function") => self.functions,
+                Some("event") => self.events,
+                Some("error") => self.errors,
+                _ => false,
+            })
+            .collect()
+    }
+}
+
+/// This function formats the ABI `entry` as a human readable signature, like
+/// `function transfer(address,uint256) returns (bool)` or
+/// `event Transfer(address indexed,address indexed,uint256)`.
+fn human_readable(entry: &Value) -> String {
+    let kind = entry["type"].as_str().unwrap_or("function");
+    let inputs = format_params(&entry["inputs"], kind == "event");
+    let signature = match kind {
+        "constructor" | "fallback" | "receive" => format!("{kind}({inputs})"),
+        _ => format!("{kind} {}({inputs})", entry["name"].as_str().unwrap_or_default()),
+    };
+
+    let mut modifiers = Vec::new();
+    match entry["stateMutability"].as_str() {
+        Some("view") => modifiers.push("view".to_string()),
+        Some("pure") => modifiers.push("pure".to_string()),
+        Some("payable") => modifiers.push("payable".to_string()),
+        _ => {}
+    }
+    if entry["anonymous"].as_bool() == Some(true) {
+        modifiers.push("anonymous".to_string());
+    }
+    if entry["outputs"].as_array().map_or(false, |outputs| !outputs.is_empty()) {
+        modifiers.push(format!("returns ({})", format_params(&entry["outputs"], false)));
+    }
+    std::iter::once(signature).chain(modifiers).collect::<Vec<_>>().join(" ")
+}
+
+/// This function formats the types of the ABI `params`, separated by commas, marking the
+/// `indexed` parameters of events.
+fn format_params(params: &Value, event: bool) -> String {
+    params
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|param| {
+            let kind = format_type(param);
+            if event && param["indexed"].as_bool() == Some(true) {
+                format!("{kind} indexed")
+            } else {
+                kind
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// This function formats the type of the ABI `param`, expanding tuples to the types of their
+/// components, like `(address,uint256)[]`.
+fn format_type(param: &Value) -> String {
+    let kind = param["type"].as_str().unwrap_or_default();
+    match kind.strip_prefix("tuple") {
+        Some(suffix) => format!("({}){suffix}", format_params(&param["components"], false)),
+        None => kind.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn abi_entries_are_filtered_and_formatted() {
+        let abi = json!([
+            { "type": "constructor", "inputs": [{ "name": "owner", "type": "address" }] },
+            {
+                "type": "function",
+                "name": "transfer",
+                "stateMutability": "nonpayable",
+                "inputs": [
+                    { "name": "to", "type": "address" },
+                    { "name": "amount", "type": "uint256" }
+                ],
+                "outputs": [{ "name": "", "type": "bool" }]
+            },
+            {
+                "type": "function",
+                "name": "orders",
+                "stateMutability": "view",
+                "inputs": [],
+                "outputs": [{
+                    "name": "",
+                    "type": "tuple[]",
+                    "components": [
+                        { "name": "maker", "type": "address" },
+                        { "name": "amount", "type": "uint256" }
+                    ]
+                }]
+            },
+            {
+                "type": "event",
+                "name": "Transfer",
+                "anonymous": false,
+                "inputs": [
+                    { "name": "from", "type": "address", "indexed": true },
+                    { "name": "value", "type": "uint256", "indexed": false }
+                ]
+            },
+            { "type": "error", "name": "Unauthorized", "inputs": [] }
+        ]);
+        let abi = abi.as_array().unwrap();
+
+        let args = ZkAbiArgs::parse_from(["zk-abi", "Token", "--events", "--errors"]);
+        let entries = args.filter(abi);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(ZkAbiArgs::parse_from(["zk-abi", "Token"]).filter(abi).len(), 5);
+
+        let signatures: Vec<String> = abi.iter().map(human_readable).collect();
+        assert_eq!(
+            signatures,
+            vec![
+                "constructor(address)",
+                "function transfer(address,uint256) returns (bool)",
+                "function orders() view returns ((address,uint256)[])",
+                "event Transfer(address indexed,uint256)",
+                "error Unauthorized()",
+            ]
+        );
+    }
+}
@@ -0,0 +1,202 @@
+//! The `zk_artifacts` module looks up the artifacts of the contracts compiled with `forge
+//! zk-build`, for the commands which work with a compiled contract.
+//!
+//! Contracts are looked up either in the zksolc output of their source in the `zkout` directory,
+//! see `find_contract`, or in their Foundry artifact in the `zksync` directory of the `out` path,
+//! see `read_artifact`. In both cases, a contract is identified by its name or, if its name is
+//! defined in several sources, by an identifier in the form `<path>:<contractname>`.
+use super::zksolc::read_contract_output;
+use eyre::Context;
+use serde_json::Value;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// A contract found in the zksolc artifacts.
+pub(crate) struct ZkArtifactContract {
+    /// The source file the contract is defined in, as it appears in the compiler output.
+    pub file: String,
+    /// The name of the contract.
+    pub name: String,
+    /// The compiler output the contract was found in.
+    pub output: Value,
+    /// The compiled contract.
+    pub contract: Value,
+}
+
+impl ZkArtifactContract {
+    /// Returns the contract identifier in the form `<path>:<contractname>`.
+    pub fn id(&self) -> String {
+        format!("{}:{}", self.file, self.name)
+    }
+}
+
+/// This function finds the zksolc artifact of a contract in the `artifacts` directory.
+///
+/// `contract_name` is either the name of a contract or an identifier in the form
+/// `<path>:<contractname>`. A bare name must match a contract defined in exactly one source.
+pub(crate) fn find_contract(
+    artifacts: &Path,
+    contract_name: &str,
+) -> eyre::Result<ZkArtifactContract> {
+    if let Some((path, name)) = contract_name.rsplit_once(':') {
+        let output = read_contract_output(artifacts, Path::new(path))
+            .map_err(|err| eyre::eyre!("{}", err))?;
+        let contract = output[path][name].clone();
+        if contract.is_null() {
+            eyre::bail!("Could not find {} - did you run zk-build?", contract_name);
+        }
+        return Ok(ZkArtifactContract {
+            file: path.to_string(),
+            name: name.to_string(),
+            output,
+            contract,
+        });
+    }
+
+    let entries = fs::read_dir(artifacts).wrap_err(format!(
+        "Unable to read the artifacts directory {} - did you run zk-build?",
+        artifacts.display()
+    ))?;
+
+    let mut found = Vec::new();
+    for entry in entries {
+        let source = entry?.path();
+        if !source.join("artifacts.json").is_file() {
+            continue;
+        }
+        let output =
+            read_contract_output(artifacts, &source).map_err(|err| eyre::eyre!("{}", err))?;
+        let files = match output.as_object() {
+            Some(files) => files,
+            None => continue,
+        };
+
+        // the output of a source also contains the contracts of its imports, which have their own
+        // artifacts
+        let matches: Vec<_> = files
+            .iter()
+            .filter(|(file, _)| Path::new(file).file_name() == source.file_name())
+            .filter(|(_, contracts)| !contracts[contract_name].is_null())
+            .map(|(file, _)| file.clone())
+            .collect();
+        for file in matches {
+            let contract = output[&file][contract_name].clone();
+            found.push(ZkArtifactContract {
+                file,
+                name: contract_name.to_string(),
+                output: output.clone(),
+                contract,
+            });
+        }
+    }
+
+    match found.len() {
+        0 => eyre::bail!("Could not find {} - did you run zk-build?", contract_name),
+        1 => Ok(found.remove(0)),
+        _ => {
+            let files: Vec<_> = found.into_iter().map(|found| found.file).collect();
+            eyre::bail!(
+                "{} is defined in multiple sources ({}), use `<path>:{}` instead",
+                contract_name,
+                files.join(", "),
+                contract_name
+            )
+        }
+    }
+}
+
+/// This function reads the Foundry artifact of `contract` in `out_dir`, see `artifact_path`.
+///
+/// # Errors
+/// An error is returned if the contract was not compiled with `zk-build`, if its name is defined
+/// in several sources, or if its artifact is not valid JSON.
+pub(crate) fn read_artifact(out_dir: &Path, contract: &str) -> eyre::Result<Value> {
+    let path = artifact_path(out_dir, contract)?;
+    let artifact = fs::read_to_string(&path).wrap_err(format!(
+        "Unable to read the artifact of {} at {} - did you run zk-build?",
+        contract,
+        path.display()
+    ))?;
+    serde_json::from_str(&artifact)
+        .wrap_err(format!("Unable to parse JSON from {}", path.display()))
+}
+
+/// This function returns the path of the Foundry artifact of `contract` in `out_dir`.
+///
+/// Contracts whose name is defined in several sources are nested in a directory named after their
+/// source, which is used if `contract` is in the form `<path>:<contractname>`. Like
+/// `find_contract`, a bare name must match a contract defined in exactly one source.
+fn artifact_path(out_dir: &Path, contract: &str) -> eyre::Result<PathBuf> {
+    let (source, name) = match contract.rsplit_once(':') {
+        Some((source, name)) => (Path::new(source).file_name(), name),
+        None => (None, contract),
+    };
+    let filename = format!("{name}.json");
+    if let Some(source) = source {
+        let path = out_dir.join(source).join(&filename);
+        return Ok(if path.exists() { path } else { out_dir.join(filename) })
+    }
+    if out_dir.join(&filename).exists() {
+        return Ok(out_dir.join(filename))
+    }
+
+    let mut sources: Vec<_> = fs::read_dir(out_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|source| source.join(&filename).is_file())
+        .collect();
+    sources.sort();
+    match sources.len() {
+        0 => Ok(out_dir.join(filename)),
+        1 => Ok(sources.remove(0).join(filename)),
+        _ => {
+            let files: Vec<_> =
+                sources.iter().filter_map(|source| source.file_name()?.to_str()).collect();
+            eyre::bail!(
+                "{} is defined in multiple sources ({}), use `<path>:{}` instead",
+                contract,
+                files.join(", "),
+                contract
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn artifacts_are_found_by_source_and_by_unique_name() {
+        let out_dir = tempfile::tempdir().unwrap();
+        let out_dir = out_dir.path();
+        for dir in ["A.sol", "B.sol"] {
+            fs::create_dir_all(out_dir.join(dir)).unwrap();
+            fs::write(out_dir.join(dir).join("Shared.json"), "{}").unwrap();
+        }
+        fs::create_dir_all(out_dir.join("C.sol")).unwrap();
+        fs::write(out_dir.join("C.sol").join("Nested.json"), "{}").unwrap();
+        fs::write(out_dir.join("Unique.json"), "{}").unwrap();
+
+        assert_eq!(artifact_path(out_dir, "Unique").unwrap(), out_dir.join("Unique.json"));
+        assert_eq!(
+            artifact_path(out_dir, "src/B.sol:Shared").unwrap(),
+            out_dir.join("B.sol").join("Shared.json")
+        );
+        assert_eq!(
+            artifact_path(out_dir, "Nested").unwrap(),
+            out_dir.join("C.sol").join("Nested.json")
+        );
+
+        let err = read_artifact(out_dir, "Shared").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Shared is defined in multiple sources (A.sol, B.sol), use `<path>:Shared` instead"
+        );
+        let err = read_artifact(out_dir, "Missing").unwrap_err();
+        assert!(err.to_string().contains("did you run zk-build?"));
+    }
+}
@@ -24,62 +24,202 @@
 /// contracts. It is designed to provide a seamless experience for developers, providing an
 /// easy-to-use interface for contract compilation while taking care of the underlying complexities.
 use super::build::CoreBuildArgs;
-use super::zksolc::{ZkSolc, ZkSolcOpts};
+use super::watch::WatchArgs;
+use super::zk_selectors::{selectors, ZkSelector};
+use super::zksolc::{
+    ZkCompilationOutput, ZkCompiledContract, ZkSolc, ZkSolcDiagnostic, ZkSolcOptsBuilder,
+    ZkSourceOutcome, DEFAULT_COMPILER_TIMEOUT,
+};
 use super::zksolc_manager::{
-    ZkSolcManager, ZkSolcManagerBuilder, ZkSolcManagerOpts, DEFAULT_ZKSOLC_VERSION,
+    ZkSolcManager, ZkSolcManagerBuilder, ZkSolcManagerOpts, ZkSolcVersionSelection,
 };
 use crate::cmd::{Cmd, LoadConfig};
-use clap::Parser;
-use ethers::prelude::Project;
+use clap::{builder::RangedU64ValueParser, Parser, ValueHint};
+use comfy_table::{presets::ASCII_MARKDOWN, Attribute, Cell, Color, Table};
+use ethers::{abi::Abi, prelude::Project};
 use foundry_config::{
     figment::{
         self,
         error::Kind::InvalidType,
+        providers::Env,
         value::{Dict, Map, Value},
-        Metadata, Profile, Provider,
+        Figment, Metadata, Profile, Provider,
     },
     Config,
 };
 use serde::Serialize;
-use std::fmt::Debug;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Debug,
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use watchexec::config::{InitConfig, RuntimeConfig};
+
+// Loads the project's figment with the `--profile` selected and merges the build cli arguments
+// into it
+impl<'a> From<&'a ZkBuildArgs> for Figment {
+    fn from(args: &'a ZkBuildArgs) -> Self {
+        args.args.figment_with_profile(args.selected_profile()).merge(args)
+    }
+}
+
+impl<'a> From<&'a ZkBuildArgs> for Config {
+    fn from(args: &'a ZkBuildArgs) -> Self {
+        let figment: Figment = args.into();
+        Config::from_provider(figment).sanitized()
+    }
+}
 
-foundry_config::merge_impl_figment_convert!(ZkBuildArgs, args);
+/// The maximum size of the bytecode of a contract on zkSync, in bytes.
+const ZKSYNC_CONTRACT_SIZE_LIMIT: usize = 64 * 1024;
 
-/// The `ZkBuildArgs` struct encapsulates the parameters required for the zkSync contract compilation process.
+/// The `ZkBuildArgs` struct encapsulates the parameters required for the zkSync contract
+/// compilation process.
 ///
 /// This includes:
-/// * `use_zksolc`: The version of the Solidity compiler (solc) to be used for compilation, or the path to a local solc.
-///   The values can be in the format `x.y.z`, `solc:x.y.z`, or `path/to/solc`. It is used to specify the compiler
-///   version or location, which is crucial for the contract building process.
+/// * `use_zksolc`: The version of the Solidity compiler (solc) to be used for compilation, or the
+///   path to a local solc. The values can be in the format `x.y.z`, `solc:x.y.z`, or
+///   `path/to/solc`. It is used to specify the compiler version or location, which is crucial for
+///   the contract building process. If not set, `use_zksolc` of the `[zksync]` section of the
+///   config is used, and `DEFAULT_ZKSOLC_VERSION` if that is not set either. With `latest`, the
+///   newest stable release is used, see `ZkSolcManager::get_latest_version`.
+///
+/// * `compiler_path`: The path to a local zksolc binary, for air-gapped environments and custom
+///   builds of the compiler. It bypasses the download of zksolc, so `use_zksolc` is ignored when it
+///   is set.
+///
+/// * `is_system`: A boolean flag indicating whether to enable the system contract compilation mode.
+///   In this mode, zkEVM extensions are enabled, for example, calls to addresses `0xFFFF` and below
+///   are substituted by special zkEVM instructions. This option is used when we want to compile
+///   system contracts.
+///
+/// * `force_evmla`: A boolean flag indicating whether to forcibly switch to the EVM legacy assembly
+///   pipeline. This is useful for older revisions of `solc` 0.8, where Yul was considered highly
+///   experimental and contained more bugs than today. This flag allows us to use the EVM legacy
+///   assembly pipeline, which can be beneficial in certain situations. It cannot be combined with
+///   `--via-ir` of the core build arguments, which selects the Yul IR pipeline instead.
+///
+/// * `optimizer_mode`: The zksolc optimization mode, serialised into `settings.optimizer.mode` of
+///   the JSON input. If not set, `--optimize` selects mode `3`.
+///
+/// * `optimize_for_size`: A boolean flag serialised into
+///   `settings.optimizer.fallbackToOptimizingForSize`.
+///
+/// * `metadata_hash`: The hash of the metadata appended to the bytecode, serialised into
+///   `settings.metadata.bytecodeHash`. With `none` the bytecode only depends on the code, which is
+///   essential for predicting `CREATE2` addresses of deterministic deployments across chains.
+///
+/// * `jobs`: The number of sources compiled in parallel, each with its own zksolc process. Defaults
+///   to the number of logical CPUs.
+///
+/// * `compiler_timeout`: The number of seconds a zksolc process may take to compile a source before
+///   it is killed and the source is reported as failed. Defaults to `DEFAULT_COMPILER_TIMEOUT`.
+///
+/// * `debug_output_dir`: The directory zksolc writes its intermediate artifacts (Yul, LLVM IR,
+///   EraVM assembly) to, for inspecting what the compiler produces. Sources are always compiled
+///   again when it is set.
+///
+/// * `include_paths`: Additional directories searched when resolving imports, for example the
+///   contracts of other packages of a monorepo. They are passed to zksolc as `--include-path`,
+///   together with the sources, the libraries and the remapping roots of the project.
+///
+/// * `allow_paths`: Additional directories imports may be read from, for example the targets of
+///   symlinked libraries installed with `forge install`. They are passed to zksolc as
+///   `--allow-paths`, together with the root, the sources and the libraries of the project.
+///
+/// * `no_node_modules`: A boolean flag disabling the `node_modules` directory of the project root
+///   as an include path. By default it is searched when it exists, so that the
+///   `@openzeppelin/contracts/...` imports of projects migrating from Hardhat resolve without
+///   remappings.
+///
+/// * `suppress_warnings`: A boolean flag silencing the warnings of zksolc, for projects migrating
+///   with many warnings they plan to address later. Only their number is printed at the end of the
+///   compilation, errors are always printed.
+///
+/// * `json`: A boolean flag indicating whether to print the compilation result as a JSON object
+///   with the fields `status`, `contracts`, `errors` and `warnings` instead of human readable
+///   output, for CI pipelines and IDEs.
+///
+/// * `sizes`: A boolean flag indicating whether to print the bytecode size of every compiled
+///   contract, in the format of `forge build --sizes`, together with the percentage of the zkSync
+///   contract size limit it uses.
+///
+/// * `ast`: A boolean flag indicating whether to write the AST of every source to the `zksync/ast`
+///   directory of the `out` path, for static analysis tools. The files are named after the path of
+///   the source relative to `src`.
+///
+/// * `storage_layout`: A boolean flag indicating whether to request the storage layout of every
+///   contract from zksolc and include it in its artifact, to check upgrades for storage collisions.
+///   `forge zk-storage-layout` prints it.
+///
+/// * `hardhat_artifacts`: A boolean flag indicating whether to also write the artifact of every
+///   compiled contract in the layout of Hardhat, to `artifacts/<path>/<ContractName>.json` in the
+///   project root, for Hardhat deploy scripts and ethers.js tests.
+///
+/// * `compilation_db`: A boolean flag indicating whether to write the invocation of zksolc for
+///   every source to `zk_compile_commands.json` in the project root, in the format of
+///   `compile_commands.json`, for IDEs and language servers.
+///
+/// * `doc`: A boolean flag indicating whether to request the NatSpec `devdoc` and `userdoc` of
+///   every contract from zksolc and include them in its artifact, for documentation generators.
+///   `forge zk-doc` renders them.
+///
+/// * `hashes`: A boolean flag indicating whether to print the function selectors, event topics and
+///   error selectors of every compiled contract.
 ///
-/// * `is_system`: A boolean flag indicating whether to enable the system contract compilation mode. In this mode,
-///   zkEVM extensions are enabled, for example, calls to addresses `0xFFFF` and below are substituted by special
-///   zkEVM instructions. This option is used when we want to compile system contracts.
+/// * `args`: Core build arguments encapsulated in the `CoreBuildArgs` struct. These include
+///   additional parameters required for building the contract, such as optimization level, output
+///   directory etc.
 ///
-/// * `force_evmla`: A boolean flag indicating whether to forcibly switch to the EVM legacy assembly pipeline. This is
-///   useful for older revisions of `solc` 0.8, where Yul was considered highly experimental and contained more bugs
-///   than today. This flag allows us to use the EVM legacy assembly pipeline, which can be beneficial in certain situations.
+/// * `watch`: Watch mode arguments. With `--watch`, the contracts are compiled again whenever a
+///   source changes, see `watch::watch_zk_build`.
 ///
-/// * `args`: Core build arguments encapsulated in the `CoreBuildArgs` struct. These include additional parameters
-///   required for building the contract, such as optimization level, output directory etc.
+/// `use_zksolc`, `is_system` and `force_evmla` can also be set in the `[zksync]` section of
+/// `foundry.toml`, or with the `FOUNDRY_ZKSYNC_USE_ZKSOLC`, `FOUNDRY_ZKSYNC_IS_SYSTEM` and
+/// `FOUNDRY_ZKSYNC_FORCE_EVMLA` environment variables, which are merged into the `[zksync]` section
+/// by figment, so that every command reading it sees them. The environment takes precedence over
+/// `foundry.toml`, and values passed on the command line take precedence over both.
 ///
-/// This struct is used as input to the `ZkSolc` compiler, which will use these arguments to configure the compilation process.
-/// It implements the `Cmd` trait, which triggers the compilation process when the `run` function is called. The struct also
-/// implements the `Provider` trait, allowing it to be converted into a form that can be merged into the application's configuration object.
+/// Every other zk-build option can also be set with a `FOUNDRY_ZKSYNC_` environment variable named
+/// after its flag, like `FOUNDRY_ZKSYNC_INCLUDE_PATH=lib/a,lib/b`, for CI pipelines in which config
+/// files are inconvenient. Lists are comma-separated. Flags passed on the command line take
+/// precedence over the environment. The options shared with `forge build` keep their own variables.
+///
+/// This struct is used as input to the `ZkSolc` compiler, which will use these arguments to
+/// configure the compilation process. It implements the `Cmd` trait, which triggers the compilation
+/// process when the `run` function is called. The struct also implements the `Provider` trait,
+/// allowing it to be converted into a form that can be merged into the application's configuration
+/// object.
 #[derive(Debug, Clone, Parser, Serialize, Default)]
 #[clap(next_help_heading = "ZkBuild options", about = None)]
 pub struct ZkBuildArgs {
-    /// Specify the solc version, or a path to a local solc, to build with.
+    /// Specify the zksolc version to build with.
     ///
-    /// Valid values are in the format `x.y.z`, `solc:x.y.z` or `path/to/solc`.
+    /// Valid values are in the format `v1.3.x` or `1.3.x`, or `latest` for the newest stable
+    /// release. Takes precedence over `use_zksolc` in the `[zksync]` section of the config,
+    /// defaults to `DEFAULT_ZKSOLC_VERSION`.
     #[clap(
         help_heading = "ZkSync Compiler options",
         value_name = "ZK_SOLC_VERSION",
-        long = "use-zksolc",
-        default_value = DEFAULT_ZKSOLC_VERSION
+        long = "use-zksolc"
+    )]
+    #[serde(skip)]
+    pub use_zksolc: Option<ZkSolcVersionSelection>,
+
+    /// The path to a local zksolc binary to build with.
+    ///
+    /// No zksolc is downloaded and `--use-zksolc` is ignored when it is set.
+    #[clap(
+        help_heading = "ZkSync Compiler options",
+        long = "compiler-path",
+        env = "FOUNDRY_ZKSYNC_COMPILER_PATH",
+        value_hint = ValueHint::FilePath,
+        value_name = "PATH"
     )]
     #[serde(skip)]
-    pub use_zksolc: String,
+    pub compiler_path: Option<PathBuf>,
 
     /// A flag indicating whether to enable the system contract compilation mode.
     #[clap(
@@ -89,6 +229,7 @@ pub struct ZkBuildArgs {
         long = "is-system",
         value_name = "SYSTEM_MODE"
     )]
+    #[serde(skip)]
     pub is_system: bool,
 
     /// A flag indicating whether to forcibly switch to the EVM legacy assembly pipeline.
@@ -97,113 +238,728 @@ pub struct ZkBuildArgs {
         help = "Forcibly switch to the EVM legacy assembly pipeline. It is useful for older revisions of `solc` 0.8, where
         Yul was considered highly experimental and contained more bugs than today",
         long = "force-evmla",
-        value_name = "FORCE_EVMLA"
+        value_name = "FORCE_EVMLA",
+        conflicts_with = "via_ir"
     )]
+    #[serde(skip)]
     pub force_evmla: bool,
 
+    /// The zksolc optimization mode.
+    #[clap(
+        help_heading = "ZkSync Compiler options",
+        help = "Set the zksolc optimization mode: `0`, `1`, `2`, `3`, `s` (size) or `z` (aggressive size). \
+        Enables the optimizer. `--optimize` alone uses mode `3`.",
+        long = "optimizer-mode",
+        env = "FOUNDRY_ZKSYNC_OPTIMIZER_MODE",
+        value_name = "MODE",
+        value_parser = parse_optimizer_mode
+    )]
+    #[serde(skip)]
+    pub optimizer_mode: Option<char>,
+
+    /// A flag indicating whether to fall back to optimizing for size if a contract is too large.
+    #[clap(
+        help_heading = "ZkSync Compiler options",
+        help = "Fall back to optimizing for size if the bytecode of a contract is too large.",
+        long = "optimize-for-size",
+        env = "FOUNDRY_ZKSYNC_OPTIMIZE_FOR_SIZE"
+    )]
+    #[serde(skip)]
+    pub optimize_for_size: bool,
+
+    /// The hash of the metadata appended to the bytecode.
+    #[clap(
+        help_heading = "ZkSync Compiler options",
+        help = "Set the hash of the metadata appended to the bytecode. Use `none` for bytecode \
+        that doesn't change with the metadata, which is essential for deterministic deployments \
+        across chains.",
+        long = "metadata-hash",
+        env = "FOUNDRY_ZKSYNC_METADATA_HASH",
+        value_name = "HASH",
+        value_parser = ["none", "ipfs", "bzzr1"]
+    )]
+    #[serde(skip)]
+    pub metadata_hash: Option<String>,
+
+    /// The number of zksolc processes to run in parallel.
+    #[clap(
+        help_heading = "ZkSync Compiler options",
+        help = "Number of sources to compile in parallel. Defaults to the number of logical CPUs.",
+        long = "jobs",
+        env = "FOUNDRY_ZKSYNC_JOBS",
+        short = 'j',
+        value_name = "JOBS",
+        value_parser = RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    #[serde(skip)]
+    pub jobs: Option<usize>,
+
+    /// The number of seconds a zksolc process may take to compile a source.
+    #[clap(
+        help_heading = "ZkSync Compiler options",
+        help = "Kill zksolc if it takes longer than the given number of seconds to compile a source. \
+        Defaults to 300.",
+        long = "compiler-timeout",
+        env = "FOUNDRY_ZKSYNC_COMPILER_TIMEOUT",
+        value_name = "SECS",
+        value_parser = RangedU64ValueParser::<u64>::new().range(1..)
+    )]
+    #[serde(skip)]
+    pub compiler_timeout: Option<u64>,
+
+    /// The directory to write the intermediate artifacts of zksolc to.
+    #[clap(
+        help_heading = "ZkSync Compiler options",
+        help = "Write the intermediate artifacts of zksolc, like Yul, LLVM IR and EraVM assembly, to \
+        the given directory. The directory is created if it does not exist.",
+        long = "debug-output-dir",
+        env = "FOUNDRY_ZKSYNC_DEBUG_OUTPUT_DIR",
+        value_name = "PATH"
+    )]
+    #[serde(skip)]
+    pub debug_output_dir: Option<PathBuf>,
+
+    /// Additional directories to search when resolving imports.
+    #[clap(
+        help_heading = "ZkSync Compiler options",
+        help = "Add a directory to search when resolving imports, like contracts outside of the \
+        project root. Accepts a comma-separated list and can be passed multiple times.",
+        long = "include-path",
+        env = "FOUNDRY_ZKSYNC_INCLUDE_PATH",
+        value_name = "PATH",
+        value_delimiter = ','
+    )]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub include_paths: Vec<PathBuf>,
+
+    /// Additional directories imports may be read from.
+    #[clap(
+        help_heading = "ZkSync Compiler options",
+        help = "Allow imports from the given directories, like the targets of symlinked \
+        libraries. Accepts a comma-separated list and can be passed multiple times.",
+        long = "allow-paths",
+        env = "FOUNDRY_ZKSYNC_ALLOW_PATHS",
+        value_name = "PATH",
+        value_delimiter = ','
+    )]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub allow_paths: Vec<PathBuf>,
+
+    /// A flag indicating whether to not search `node_modules` when resolving imports.
+    #[clap(
+        help_heading = "ZkSync Compiler options",
+        help = "Do not search the `node_modules` directory of the project root when resolving \
+        imports. By default it is searched if it exists, like Hardhat does.",
+        long = "no-node-modules",
+        env = "FOUNDRY_ZKSYNC_NO_NODE_MODULES"
+    )]
+    #[serde(skip)]
+    pub no_node_modules: bool,
+
+    /// A flag indicating whether to not print the warnings of zksolc.
+    #[clap(
+        help_heading = "ZkSync Compiler options",
+        help = "Do not print the warnings of zksolc, only their number. Errors are always printed.",
+        long = "suppress-warnings",
+        env = "FOUNDRY_ZKSYNC_SUPPRESS_WARNINGS"
+    )]
+    #[serde(skip)]
+    pub suppress_warnings: bool,
+
+    /// A flag indicating whether to print the compilation result as JSON.
+    #[clap(
+        help_heading = "ZkSync Compiler options",
+        help = "Print the compilation result as a JSON object instead of human readable output.",
+        long = "json",
+        env = "FOUNDRY_ZKSYNC_JSON"
+    )]
+    #[serde(skip)]
+    pub json: bool,
+
+    /// A flag indicating whether to print the bytecode sizes of the compiled contracts.
+    #[clap(help = "Print compiled contract sizes.", long = "sizes", env = "FOUNDRY_ZKSYNC_SIZES")]
+    #[serde(skip)]
+    pub sizes: bool,
+
+    /// A flag indicating whether to print the selectors of the compiled contracts.
+    #[clap(
+        help = "Print the function selectors, event topics and error selectors of the compiled contracts.",
+        long = "hashes",
+        env = "FOUNDRY_ZKSYNC_HASHES"
+    )]
+    #[serde(skip)]
+    pub hashes: bool,
+
+    /// A flag indicating whether to also write the artifacts in the layout of Hardhat.
+    #[clap(
+        help_heading = "ZkSync Compiler options",
+        help = "Also write the artifacts of the compiled contracts in the layout of Hardhat, to \
+        `artifacts/<path>/<ContractName>.json` in the project root.",
+        long = "hardhat-artifacts",
+        env = "FOUNDRY_ZKSYNC_HARDHAT_ARTIFACTS"
+    )]
+    #[serde(skip)]
+    pub hardhat_artifacts: bool,
+
+    /// A flag indicating whether to write the compilation database.
+    #[clap(
+        help_heading = "ZkSync Compiler options",
+        help = "Write the invocation of zksolc for every source to `zk_compile_commands.json` in \
+        the project root, for IDEs and language servers.",
+        long = "compilation-db",
+        env = "FOUNDRY_ZKSYNC_COMPILATION_DB"
+    )]
+    #[serde(skip)]
+    pub compilation_db: bool,
+
+    /// A flag indicating whether to include the NatSpec documentation in the artifacts.
+    #[clap(
+        help_heading = "ZkSync Compiler options",
+        help = "Include the NatSpec devdoc and userdoc of every contract in its artifact, see `forge zk-doc`.",
+        long = "doc",
+        env = "FOUNDRY_ZKSYNC_DOC"
+    )]
+    #[serde(skip)]
+    pub doc: bool,
+
+    /// A flag indicating whether to write the AST of every source.
+    #[clap(
+        help_heading = "ZkSync Compiler options",
+        help = "Write the AST of every source to the `zksync/ast` directory of the out path.",
+        long = "ast",
+        env = "FOUNDRY_ZKSYNC_AST"
+    )]
+    #[serde(skip)]
+    pub ast: bool,
+
+    /// A flag indicating whether to include the storage layout in the artifacts.
+    #[clap(
+        help_heading = "ZkSync Compiler options",
+        help = "Include the storage layout of every contract in its artifact, see `forge zk-storage-layout`.",
+        long = "storage-layout",
+        env = "FOUNDRY_ZKSYNC_STORAGE_LAYOUT"
+    )]
+    #[serde(skip)]
+    pub storage_layout: bool,
+
+    /// The profile of `foundry.toml` to build with.
+    #[clap(
+        help = "The profile of the config to build with, like `release` for the `[profile.release]` \
+        and `[profile.release.zksync]` sections. Defaults to `FOUNDRY_PROFILE`, or the `default` \
+        profile if that is not set either.",
+        long = "profile",
+        value_name = "PROFILE"
+    )]
+    #[serde(skip)]
+    pub profile: Option<String>,
+
     /// Core build arguments encapsulated in the `CoreBuildArgs` struct.
     #[clap(flatten)]
     #[serde(flatten)]
     pub args: CoreBuildArgs,
+
+    /// Watch mode arguments, to compile again whenever a source changes.
+    #[clap(flatten)]
+    #[serde(skip)]
+    pub watch: WatchArgs,
 }
 
 impl Cmd for ZkBuildArgs {
-    type Output = ();
+    type Output = ZkCompilationOutput;
 
-    /// Executes the zkSync contract compilation process based on the parameters encapsulated in the `ZkBuildArgs` instance.
+    /// Executes the zkSync contract compilation process based on the parameters encapsulated in the
+    /// `ZkBuildArgs` instance.
     ///
     /// This method performs the following steps:
-    /// 1. Tries to load the application's configuration, emitting warnings if any issues are encountered.
-    /// 2. Modifies the project's artifact path to be the "zkout" directory in the project's root directory.
-    /// 3. Creates a `ZkSolcManager` instance based on the specified zkSync Solidity compiler (`use_zksolc` field in `ZkBuildArgs`).
-    /// 4. Checks if the setup compilers directory is properly set up. If not, it raises an error and halts execution.
-    /// 5. If the zkSync Solidity compiler does not exist in the compilers directory, it triggers its download.
-    /// 6. Initiates the contract compilation process using the `ZkSolc` compiler. This process is configured with the
-    ///    `is_system` and `force_evmla` parameters from the `ZkBuildArgs` instance, and the path to the zkSync Solidity compiler.
-    /// 7. If the compilation process fails, it raises an error and halts execution.
+    /// 1. Tries to load the application's configuration with the `--profile` selected, emitting
+    ///    warnings if any issues are encountered.
+    /// 2. Modifies the project's artifact path to be the "zkout" directory in the project's root
+    ///    directory.
+    /// 3. Uses the zksolc binary of `--compiler-path` if it is passed. Otherwise, creates a
+    ///    `ZkSolcManager` instance based on the specified zkSync Solidity compiler (`use_zksolc` of
+    ///    the `[zksync]` section of the config, which the `use_zksolc` field in `ZkBuildArgs`
+    ///    overrides) and goes through steps 4 and 5.
+    /// 4. Checks if the setup compilers directory is properly set up. If not, it raises an error
+    ///    and halts execution.
+    /// 5. If the zkSync Solidity compiler does not exist in the compilers directory, it triggers
+    ///    its download.
+    /// 6. Initiates the contract compilation process using the `ZkSolc` compiler. This process is
+    ///    configured with the `is_system` and `force_evmla` settings of the `[zksync]` section of
+    ///    the config, and the path to the zkSync Solidity compiler.
+    /// 7. Every source in the project is compiled, even if some of them fail. Once all sources have
+    ///    been processed, a summary table with the outcome of each source is printed, or a
+    ///    `ZkBuildReport` if `--json` is passed.
+    /// 8. If `--sizes` is passed, a table with the bytecode size of every compiled contract is
+    ///    printed. Contracts exceeding the zkSync contract size limit are highlighted and make the
+    ///    build fail, also with `--json`.
+    /// 9. If `--hashes` is passed, a table with the selectors of every compiled contract is
+    ///    printed.
     ///
-    /// The method returns `Ok(())` if the entire process completes successfully, or an error if any step in the process fails.
-    /// The purpose of this function is to consolidate all steps involved in the zkSync contract compilation process in a single method,
-    /// allowing for easy invocation of the process with a single function call.
-    fn run(self) -> eyre::Result<()> {
+    /// The method returns the outcome of every compiled source if all of them compiled
+    /// successfully, or an error if any step in the process fails, if at least one source failed to
+    /// compile or if a contract is too large. The purpose of this function is to consolidate all
+    /// steps involved in the zkSync contract compilation process in a single method, allowing for
+    /// easy invocation of the process with a single function call.
+    fn run(self) -> eyre::Result<Self::Output> {
         let config = self.try_load_config_emit_warnings()?;
         let mut project = config.project()?;
 
+        //cache zksolc outputs in the regular out path
+        let cache_dir = project.paths.artifacts.join("zksync").join("cache");
+
         //set zk out path
         let zk_out_path = project.paths.root.join("zkout");
         project.paths.artifacts = zk_out_path;
+        let root = project.paths.root.clone();
+
+        let compiler_path = self.zksolc_path(&config)?;
+
+        if !self.json {
+            println!("Compiling smart contracts...");
+        }
+        let output = self.compile_smart_contracts(&config, compiler_path, project, cache_dir)?;
+        if self.json {
+            println!("{}", serde_json::to_string(&ZkBuildReport::new(&output))?);
+        } else {
+            print_summary(&output.sources, &root);
+            let suppressed = output.warnings().count();
+            if self.suppress_warnings && suppressed > 0 {
+                println!(
+                    "{suppressed} warnings suppressed (use without --suppress-warnings to see them)"
+                );
+            }
+        }
+
+        let failed = output.failed().count();
+        if failed > 0 {
+            eyre::bail!(
+                "{} of {} sources failed to compile with zksolc",
+                failed,
+                output.sources.len()
+            );
+        }
 
-        let zksolc_manager = self.setup_zksolc_manager()?;
+        // the size limit is enforced whatever the output format, so that CI runs with `--json` fail
+        if config.sizes {
+            let size_report = ZkSizeReport::new(&output);
+            if !self.json {
+                println!("{size_report}");
+            }
+            if size_report.exceeds_size_limit() {
+                eyre::bail!(
+                    "Some contracts exceed the zkSync contract size limit of {} bytes",
+                    ZKSYNC_CONTRACT_SIZE_LIMIT
+                );
+            }
+        }
+
+        if self.hashes && !self.json {
+            print!("{}", ZkHashesReport::new(&output));
+        }
 
-        println!("Compiling smart contracts...");
-        self.compile_smart_contracts(zksolc_manager, project)
+        if !self.json {
+            println!("Compiled Successfully");
+        }
+        Ok(output)
     }
 }
 
 impl ZkBuildArgs {
+    /// Returns whether `ZkBuildArgs` was configured with `--watch`
+    pub fn is_watch(&self) -> bool {
+        self.watch.watch.is_some()
+    }
+
+    /// Returns the profile of the config to build with, the `--profile` if one is passed or the
+    /// profile selected via `FOUNDRY_PROFILE` otherwise.
+    pub fn selected_profile(&self) -> Profile {
+        self.profile.as_deref().map(Profile::new).unwrap_or_else(Config::selected_profile)
+    }
+
+    /// Returns the [`watchexec::InitConfig`] and [`watchexec::RuntimeConfig`] necessary to
+    /// bootstrap a new [`watchexe::Watchexec`] loop.
+    pub(crate) fn watchexec_config(&self) -> eyre::Result<(InitConfig, RuntimeConfig)> {
+        // use the path arguments or if none where provided the `src` dir
+        self.watch.watchexec_config(|| {
+            let config = Config::from(self);
+            vec![config.src]
+        })
+    }
+
     /// The `setup_zksolc_manager` function creates and prepares an instance of `ZkSolcManager`.
     ///
     /// It follows these steps:
-    /// 1. Instantiate `ZkSolcManagerOpts` and `ZkSolcManagerBuilder` with the specified zkSync Solidity compiler.
-    /// 2. Create a `ZkSolcManager` using the builder.
-    /// 3. Check if the setup compilers directory is properly set up. If not, it raises an error.
-    /// 4. If the zkSync Solidity compiler does not exist in the compilers directory, it triggers its download.
+    /// 1. Instantiate `ZkSolcManagerOpts` and `ZkSolcManagerBuilder` with the zkSync Solidity compiler of the
+    ///    `[zksync]` section of `config`, or `DEFAULT_ZKSOLC_VERSION` if none is set. `latest` is resolved to the
+    ///    newest stable release before the builder is constructed.
+    /// 2. Create a `ZkSolcManager` using the builder, in offline mode if `--offline` is passed.
+    /// 3. Set up the compilers directory and download the zkSync Solidity compiler if it does not exist in it, with
+    ///    `ZkSolcManager::ensure_version`. In offline mode, or if `--no-auto-detect` is passed, a missing compiler
+    ///    raises an error instead.
     ///
     /// The function returns the `ZkSolcManager` if all steps are successful, or an error if any step fails.
-    fn setup_zksolc_manager(&self) -> eyre::Result<ZkSolcManager> {
-        let zksolc_manager_opts = ZkSolcManagerOpts::new(self.use_zksolc.clone());
-        let zksolc_manager_builder = ZkSolcManagerBuilder::new(zksolc_manager_opts);
+    pub(crate) fn setup_zksolc_manager(&self, config: &Config) -> eyre::Result<ZkSolcManager> {
+        let use_zksolc = match &config.zksync.use_zksolc {
+            Some(version) => version.parse::<ZkSolcVersionSelection>().map_err(|err| {
+                eyre::eyre!("Invalid use_zksolc in the [zksync] section of the config: {}", err)
+            })?,
+            None => ZkSolcVersionSelection::default(),
+        };
+        let use_zksolc = use_zksolc
+            .resolve()
+            .map_err(|err| eyre::eyre!("Failed to resolve the latest zksolc version: {}", err))?;
+        let zksolc_manager_opts = ZkSolcManagerOpts::new(use_zksolc);
+        let mut zksolc_manager_builder = ZkSolcManagerBuilder::new(zksolc_manager_opts);
+        if config.offline {
+            zksolc_manager_builder = zksolc_manager_builder.offline();
+        }
         let zksolc_manager = zksolc_manager_builder
             .build()
             .map_err(|e| eyre::eyre!("Error building zksolc_manager: {}", e))?;
 
-        if let Err(err) = zksolc_manager.check_setup_compilers_dir() {
-            eyre::bail!("Failed to setup compilers directory: {}", err);
-        }
-
         if !zksolc_manager.exists() {
-            println!(
-                "Downloading zksolc compiler from {:?}",
-                zksolc_manager.get_full_download_url().unwrap().to_string()
-            );
-            zksolc_manager
-                .download()
-                .map_err(|err| eyre::eyre!("Failed to download the file: {}", err))?;
+            // never download an unreviewed binary in the middle of a hermetic build
+            if self.args.no_auto_detect {
+                eyre::bail!(
+                    "zksolc {} not found; set --compiler-path or run without --no-auto-detect",
+                    zksolc_manager.get_version()
+                );
+            }
+            if !self.json && !zksolc_manager.is_offline() {
+                let url = zksolc_manager
+                    .get_full_download_url()
+                    .map_err(|err| eyre::eyre!("Failed to build the download URL: {}", err))?;
+                println!("Downloading zksolc compiler from {:?}", url.to_string());
+            }
         }
+        zksolc_manager.ensure_version().map_err(|err| eyre::eyre!("{}", err))?;
 
         Ok(zksolc_manager)
     }
 
+    /// Returns the path of the zksolc binary to build with.
+    ///
+    /// This is the absolute `--compiler-path` if it is set, after checking that it is an executable
+    /// file. Otherwise, the zksolc of `setup_zksolc_manager` is used, which is downloaded if needed.
+    pub(crate) fn zksolc_path(&self, config: &Config) -> eyre::Result<PathBuf> {
+        match &self.compiler_path {
+            Some(path) => local_compiler_path(path),
+            None => Ok(self.setup_zksolc_manager(config)?.get_full_compiler_path()),
+        }
+    }
+
     /// The `compile_smart_contracts` function initiates the contract compilation process.
     ///
     /// It follows these steps:
-    /// 1. Create an instance of `ZkSolcOpts` with the appropriate options.
+    /// 1. Build the `ZkSolcOpts` with a `ZkSolcOptsBuilder`, which rejects `via_ir` together with `force_evmla`, with the
+    ///    zksolc at `compiler_path`, the `[zksync]` settings and the
+    ///    `extra_output` selection of `config` and the appropriate options, suppressing
+    ///    the warnings in `ignored_error_codes` of the project like solc does, and caching the compiler outputs in
+    ///    `cache_dir` unless `--force` is passed.
+    ///    The imports are resolved in the include paths returned by `include_paths` and may be read from the
+    ///    directories returned by `allow_paths`.
     /// 2. Instantiate `ZkSolc` with the created options and the project.
-    /// 3. Initiate the contract compilation process.
+    /// 3. Initiate the contract compilation process, which compiles every source in the project. Besides the outputs
+    ///    in `zkout`, the artifacts of the compiled contracts are written to the `zksync` directory of the `out` path in
+    ///    the layout of Foundry artifacts.
     ///
-    /// The function returns `Ok(())` if the compilation process completes successfully, or an error if it fails.
-    fn compile_smart_contracts(
+    /// The function returns the outcome of each compiled source, or an error if the compilation process could not be
+    /// started at all. Failures of individual sources are reported in their outcome rather than as an error.
+    pub(crate) fn compile_smart_contracts(
         &self,
-        zksolc_manager: ZkSolcManager,
+        config: &Config,
+        compiler_path: PathBuf,
         project: Project,
-    ) -> eyre::Result<()> {
-        let zksolc_opts = ZkSolcOpts {
-            compiler_path: zksolc_manager.get_full_compiler_path(),
-            is_system: self.is_system,
-            force_evmla: self.force_evmla,
+        cache_dir: PathBuf,
+    ) -> eyre::Result<ZkCompilationOutput> {
+        let zksolc_opts = ZkSolcOptsBuilder::default()
+            .with_compiler_path(compiler_path)
+            .with_is_system(config.zksync.is_system)
+            .with_force_evmla(config.zksync.force_evmla)
+            .with_via_ir(config.via_ir)
+            .with_optimizer_mode(self.optimizer_mode.or(self.args.compiler.optimize.then_some('3')))
+            .with_optimize_for_size(self.optimize_for_size)
+            .with_metadata_hash(self.metadata_hash.clone())
+            .with_cache_dir(Some(cache_dir))
+            .with_force(self.args.force)
+            .with_silent(self.json)
+            .with_jobs(self.jobs)
+            .with_debug_output_dir(self.debug_output_dir.clone())
+            .with_ignored_error_codes(project.ignored_error_codes.clone())
+            .with_suppress_warnings(self.suppress_warnings)
+            .with_extra_output(config.extra_output.iter().map(ToString::to_string).collect())
+            .with_extra_output_files(
+                config.extra_output_files.iter().map(ToString::to_string).collect(),
+            )
+            .with_out_dir(Some(config.project_paths().artifacts.join("zksync")))
+            .with_hardhat_artifacts_dir(
+                self.hardhat_artifacts.then(|| project.paths.root.join("artifacts")),
+            )
+            .with_compilation_db(
+                self.compilation_db.then(|| project.paths.root.join("zk_compile_commands.json")),
+            )
+            .with_ast(self.ast)
+            .with_storage_layout(self.storage_layout)
+            .with_natspec(self.doc)
+            .with_base_path(Some(config.__root.0.clone()))
+            .with_include_paths(include_paths(config, &project, !self.no_node_modules))
+            .with_allow_paths(allow_paths(config, &project))
+            .build()
+            .map_err(|err| eyre::eyre!("{}", err))?;
+
+        let mut zksolc = ZkSolc::new(zksolc_opts, project);
+        let timeout = self.compiler_timeout.map_or(DEFAULT_COMPILER_TIMEOUT, Duration::from_secs);
+
+        zksolc
+            .compile_with_timeout(timeout)
+            .map_err(|err| eyre::eyre!("Failed to compile smart contracts with zksolc: {}", err))
+    }
+}
+
+/// Returns the absolute path of the zksolc binary passed with `--compiler-path`, resolved from the
+/// current directory if it is relative.
+///
+/// An error is returned if the path is not a file or, on Unix, if it is not executable, rather
+/// than failing later when the first source is compiled.
+fn local_compiler_path(path: &Path) -> eyre::Result<PathBuf> {
+    let path =
+        if path.is_absolute() { path.to_path_buf() } else { std::env::current_dir()?.join(path) };
+    let metadata = fs::metadata(&path)
+        .map_err(|err| eyre::eyre!("zksolc not found at {}: {}", path.display(), err))?;
+    if !metadata.is_file() {
+        eyre::bail!("The zksolc path {} is not a file", path.display());
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o111 == 0 {
+            eyre::bail!("The zksolc binary at {} is not executable", path.display());
+        }
+    }
+    Ok(path)
+}
+
+/// Returns the directories zksolc searches when resolving imports.
+///
+/// These are the sources and libraries of the project and the roots of its remappings, if they
+/// exist, followed by the `node_modules` directory of the project root if `node_modules` is set
+/// and it exists, and by the `include_paths` of the config, which `--include-path` overrides.
+fn include_paths(config: &Config, project: &Project, node_modules: bool) -> Vec<PathBuf> {
+    let paths = &project.paths;
+    let mut include_paths: Vec<PathBuf> = std::iter::once(paths.sources.clone())
+        .chain(paths.libraries.iter().cloned())
+        .chain(paths.remappings.iter().map(|remapping| paths.root.join(&remapping.path)))
+        .chain(node_modules.then(|| paths.root.join("node_modules")))
+        .filter(|path| path.is_dir())
+        .collect();
+    include_paths.extend(config.include_paths.iter().cloned());
+
+    let mut seen = HashSet::new();
+    include_paths.retain(|path| seen.insert(path.clone()));
+    include_paths
+}
+
+/// Returns the directories zksolc may read imports from.
+///
+/// These are the root, the sources and the libraries of the project, followed by the `allow_paths`
+/// of the config, which `--allow-paths` overrides.
+fn allow_paths(config: &Config, project: &Project) -> Vec<PathBuf> {
+    let paths = &project.paths;
+    let mut allow_paths: Vec<PathBuf> = [paths.root.clone(), paths.sources.clone()]
+        .into_iter()
+        .chain(paths.libraries.iter().cloned())
+        .chain(config.allow_paths.iter().cloned())
+        .collect();
+
+    let mut seen = HashSet::new();
+    allow_paths.retain(|path| seen.insert(path.clone()));
+    allow_paths
+}
+
+/// Parses a zksolc optimization mode, which is one of `0`, `1`, `2`, `3`, `s` or `z`.
+fn parse_optimizer_mode(mode: &str) -> Result<char, String> {
+    match mode {
+        "0" | "1" | "2" | "3" | "s" | "z" => Ok(mode.chars().next().unwrap()),
+        _ => Err(format!("invalid optimizer mode `{mode}`, expected one of 0, 1, 2, 3, s or z")),
+    }
+}
+
+/// The compilation result printed by `zk-build --json`.
+///
+/// The errors and warnings of all sources are aggregated. A source that failed without reporting any
+/// error diagnostic, for example because zksolc could not be run, is reported as an error without a
+/// source location.
+#[derive(Debug, Serialize)]
+struct ZkBuildReport<'a> {
+    status: &'static str,
+    contracts: Vec<&'a ZkCompiledContract>,
+    errors: Vec<ZkSolcDiagnostic>,
+    warnings: Vec<&'a ZkSolcDiagnostic>,
+}
+
+impl<'a> ZkBuildReport<'a> {
+    fn new(output: &'a ZkCompilationOutput) -> Self {
+        let mut report = Self {
+            status: "success",
+            contracts: Vec::new(),
+            errors: Vec::new(),
+            warnings: Vec::new(),
         };
 
-        let zksolc = ZkSolc::new(zksolc_opts, project);
+        for outcome in &output.sources {
+            report.contracts.extend(&outcome.contracts);
+            report.warnings.extend(outcome.diagnostics.iter().filter(|d| d.is_warning()));
+            report.errors.extend(outcome.diagnostics.iter().filter(|d| !d.is_warning()).cloned());
 
-        match zksolc.compile() {
-            Ok(_) => {
-                println!("Compiled Successfully");
-                Ok(())
+            let has_errors = outcome.diagnostics.iter().any(|d| !d.is_warning());
+            if let (Some(error), false) = (&outcome.error, has_errors) {
+                report.errors.push(ZkSolcDiagnostic {
+                    severity: "error".to_string(),
+                    message: format!("{}: {}", outcome.source.display(), error),
+                    source_file: None,
+                    start: -1,
+                    end: -1,
+                });
             }
-            Err(err) => {
-                eyre::bail!("Failed to compile smart contracts with zksolc: {}", err);
+            if !outcome.is_success() {
+                report.status = "failed";
             }
         }
+
+        report
+    }
+}
+
+/// Prints a table with the outcome of every source compiled with zksolc.
+///
+/// Sources are displayed relative to the project `root`. Failed sources are highlighted in red
+/// together with the reason of the failure.
+fn print_summary(outcomes: &[ZkSourceOutcome], root: &Path) {
+    let mut table = Table::new();
+    table.load_preset(ASCII_MARKDOWN);
+    table.set_header(vec![
+        Cell::new("Source").add_attribute(Attribute::Bold).fg(Color::Blue),
+        Cell::new("Status").add_attribute(Attribute::Bold).fg(Color::Blue),
+        Cell::new("Error").add_attribute(Attribute::Bold).fg(Color::Blue),
+    ]);
+
+    for outcome in outcomes {
+        let source = outcome.source.strip_prefix(root).unwrap_or(&outcome.source);
+        let (status, color) =
+            if outcome.is_success() { ("compiled", Color::Green) } else { ("failed", Color::Red) };
+
+        table.add_row(vec![
+            Cell::new(source.display()).fg(color),
+            Cell::new(status).fg(color),
+            Cell::new(outcome.error.as_ref().map(ToString::to_string).unwrap_or_default())
+                .fg(color),
+        ]);
+    }
+
+    println!("{table}");
+}
+
+/// The bytecode sizes of the contracts compiled with zksolc, printed by `zk-build --sizes`.
+///
+/// Contracts are keyed by name. Tests and scripts are excluded, as they are never deployed.
+struct ZkSizeReport {
+    contracts: BTreeMap<String, usize>,
+}
+
+impl ZkSizeReport {
+    fn new(output: &ZkCompilationOutput) -> Self {
+        let contracts = output
+            .contracts()
+            .filter(|contract| !contract.is_dev_contract())
+            .map(|contract| (contract.contract_name().to_string(), contract.bytecode_size()))
+            .filter(|(_, size)| *size > 0)
+            .collect();
+        Self { contracts }
+    }
+
+    /// Returns true if any contract exceeds the zkSync contract size limit.
+    fn exceeds_size_limit(&self) -> bool {
+        self.contracts.values().any(|size| *size > ZKSYNC_CONTRACT_SIZE_LIMIT)
+    }
+}
+
+impl std::fmt::Display for ZkSizeReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        let mut table = Table::new();
+        table.load_preset(ASCII_MARKDOWN);
+        table.set_header(vec![
+            Cell::new("Contract").add_attribute(Attribute::Bold).fg(Color::Blue),
+            Cell::new("Size (kB)").add_attribute(Attribute::Bold).fg(Color::Blue),
+            Cell::new("Margin (kB)").add_attribute(Attribute::Bold).fg(Color::Blue),
+            Cell::new("Limit (%)").add_attribute(Attribute::Bold).fg(Color::Blue),
+        ]);
+
+        for (name, size) in &self.contracts {
+            let margin = ZKSYNC_CONTRACT_SIZE_LIMIT as isize - *size as isize;
+            let percentage = *size as f64 * 100.0 / ZKSYNC_CONTRACT_SIZE_LIMIT as f64;
+            let color = match size {
+                size if *size > ZKSYNC_CONTRACT_SIZE_LIMIT => Color::Red,
+                size if *size * 4 >= ZKSYNC_CONTRACT_SIZE_LIMIT * 3 => Color::Yellow,
+                _ => Color::Reset,
+            };
+
+            table.add_row(vec![
+                Cell::new(name).fg(color),
+                Cell::new(*size as f64 / 1000.0).fg(color),
+                Cell::new(margin as f64 / 1000.0).fg(color),
+                Cell::new(format!("{percentage:.2}")).fg(color),
+            ]);
+        }
+
+        writeln!(f, "{table}")?;
+        Ok(())
+    }
+}
+
+/// The selectors of the contracts compiled with zksolc, printed by `zk-build --hashes`.
+///
+/// Every contract is mapped to the selectors of its functions, errors and events, computed from
+/// its ABI like `forge zk-selectors` does.
+struct ZkHashesReport {
+    contracts: BTreeMap<String, Vec<ZkSelector>>,
+}
+
+impl ZkHashesReport {
+    fn new(output: &ZkCompilationOutput) -> Self {
+        let mut contracts = BTreeMap::new();
+        for contract in output.contracts() {
+            if let Ok(abi) = serde_json::from_value::<Abi>(contract.abi.clone()) {
+                let selectors = selectors(&abi);
+                if !selectors.is_empty() {
+                    contracts.insert(contract.name.clone(), selectors);
+                }
+            }
+        }
+        Self { contracts }
+    }
+}
+
+impl std::fmt::Display for ZkHashesReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        for (name, selectors) in &self.contracts {
+            let mut table = Table::new();
+            table.load_preset(ASCII_MARKDOWN);
+            table.set_header(vec![
+                Cell::new("Type").add_attribute(Attribute::Bold).fg(Color::Blue),
+                Cell::new("Signature").add_attribute(Attribute::Bold).fg(Color::Blue),
+                Cell::new("Selector").add_attribute(Attribute::Bold).fg(Color::Blue),
+            ]);
+            for selector in selectors {
+                table.add_row(vec![
+                    selector.kind,
+                    selector.signature.as_str(),
+                    selector.selector.as_str(),
+                ]);
+            }
+            writeln!(f, "{name}")?;
+            writeln!(f, "{table}")?;
+        }
+        Ok(())
     }
 }
 
@@ -216,16 +972,32 @@ impl Provider for ZkBuildArgs {
     fn data(&self) -> Result<Map<Profile, Dict>, figment::Error> {
         let value = Value::serialize(self)?;
         let error = InvalidType(value.to_actual(), "map".into());
-        let dict = value.into_dict().ok_or(error)?;
+        let mut dict = value.into_dict().ok_or(error)?;
 
-        // if self.names {
-        //     dict.insert("names".to_string(), true.into());
-        // }
+        // the `[zksync]` section is overridden by the `FOUNDRY_ZKSYNC_` environment variables,
+        // which are overridden by the zksync settings passed on the command line
+        let mut zksync = Env::prefixed("FOUNDRY_ZKSYNC_")
+            .only(&["use_zksolc", "is_system", "force_evmla"])
+            .data()?
+            .remove(&Profile::Default)
+            .unwrap_or_default();
+        if let Some(use_zksolc) = &self.use_zksolc {
+            zksync.insert("use_zksolc".to_string(), use_zksolc.to_string().into());
+        }
+        if self.is_system {
+            zksync.insert("is_system".to_string(), true.into());
+        }
+        if self.force_evmla {
+            zksync.insert("force_evmla".to_string(), true.into());
+        }
+        if !zksync.is_empty() {
+            dict.insert("zksync".to_string(), zksync.into());
+        }
 
-        // if self.sizes {
-        //     dict.insert("sizes".to_string(), true.into());
-        // }
+        if self.sizes {
+            dict.insert("sizes".to_string(), true.into());
+        }
 
-        Ok(Map::from([(Config::selected_profile(), dict)]))
+        Ok(Map::from([(self.selected_profile(), dict)]))
     }
 }
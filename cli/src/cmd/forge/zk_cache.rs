@@ -0,0 +1,161 @@
+//! The `zk_cache` module provides the `forge zk-cache` command, which manages the compilation
+//! artifacts and caches of `zk-build`, like `forge cache` does for the Foundry cache.
+//!
+//! `forge zk-cache clean` removes the `zksync` directory of the `out` path, which holds the Foundry
+//! artifacts and the cached zksolc outputs, the `zkout` directory and the
+//! `zk_compile_commands.json` file of the project root, so that the next `zk-build` compiles every
+//! source from scratch. With `--all`, the artifacts and the cache of `forge build` are removed as
+//! well, like `forge clean` does.
+use crate::cmd::{Cmd, LoadConfig};
+use clap::{Parser, Subcommand, ValueHint};
+use foundry_config::impl_figment_convert_basic;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// CLI arguments for `forge zk-cache`.
+#[derive(Debug, Parser)]
+pub struct ZkCacheArgs {
+    #[clap(subcommand)]
+    pub sub: ZkCacheSubcommands,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ZkCacheSubcommands {
+    #[clap(about = "Removes the zksync compilation artifacts and caches of the project.")]
+    Clean(ZkCacheCleanArgs),
+}
+
+/// CLI arguments for `forge zk-cache clean`.
+#[derive(Debug, Clone, Parser)]
+pub struct ZkCacheCleanArgs {
+    /// Print what would be removed without removing anything.
+    #[clap(long)]
+    pub dry_run: bool,
+
+    /// Also remove the artifacts and the cache of `forge build`, like `forge clean`.
+    #[clap(long)]
+    pub all: bool,
+
+    #[clap(
+        help = "The project's root path. By default, this is the root directory of the current Git repository or the current working directory if it is not part of a Git repository",
+        long,
+        value_hint = ValueHint::DirPath,
+        value_name = "PATH"
+    )]
+    root: Option<PathBuf>,
+}
+impl_figment_convert_basic!(ZkCacheCleanArgs);
+
+impl Cmd for ZkCacheCleanArgs {
+    type Output = ();
+
+    /// Removes the zksync compilation artifacts and caches, printing every removed path and the
+    /// total number of bytes freed.
+    ///
+    /// # Errors
+    /// An error is returned if the configuration cannot be loaded, or if a path cannot be removed.
+    fn run(self) -> eyre::Result<Self::Output> {
+        let config = self.try_load_config_emit_warnings()?;
+        let paths = config.project_paths();
+        let mut targets = vec![
+            paths.artifacts.join("zksync"),
+            paths.root.join("zkout"),
+            paths.root.join("zk_compile_commands.json"),
+        ];
+        if self.all {
+            targets.extend([paths.artifacts.clone(), paths.cache.clone()]);
+        }
+        let targets = existing_targets(targets);
+        if targets.is_empty() {
+            println!("Nothing to clean");
+            return Ok(());
+        }
+
+        let mut freed = 0;
+        for target in &targets {
+            let size = disk_usage(target);
+            freed += size;
+            if self.dry_run {
+                println!("Would remove {} ({} bytes)", target.display(), size);
+                continue;
+            }
+            if target.is_dir() {
+                fs::remove_dir_all(target)?;
+            } else {
+                fs::remove_file(target)?;
+            }
+            println!("Removed {} ({} bytes)", target.display(), size);
+        }
+
+        if self.dry_run {
+            println!("{freed} bytes would be freed");
+        } else {
+            println!("{freed} bytes freed");
+        }
+        Ok(())
+    }
+}
+
+/// This function returns the `targets` which exist, without those inside another target, which
+/// are removed together with it.
+fn existing_targets(targets: Vec<PathBuf>) -> Vec<PathBuf> {
+    let existing: Vec<PathBuf> = targets.into_iter().filter(|target| target.exists()).collect();
+    existing
+        .iter()
+        .filter(|target| {
+            !existing.iter().any(|other| other != *target && target.starts_with(other))
+        })
+        .cloned()
+        .collect()
+}
+
+/// This function returns the number of bytes used by the file at `path`, or by all the files in
+/// the directory at `path`. Symbolic links are not followed.
+fn disk_usage(path: &Path) -> u64 {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(_) => return 0,
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    fs::read_dir(path)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| disk_usage(&entry.path()))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_targets_are_removed_with_their_parent() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out");
+        fs::create_dir_all(out.join("zksync/cache")).unwrap();
+        fs::write(out.join("zksync/Counter.json"), [0u8; 10]).unwrap();
+        fs::write(out.join("zksync/cache/abc.json"), [0u8; 5]).unwrap();
+        fs::write(out.join("Counter.json"), [0u8; 3]).unwrap();
+        fs::write(dir.path().join("zk_compile_commands.json"), [0u8; 2]).unwrap();
+
+        let zk_targets = vec![
+            out.join("zksync"),
+            dir.path().join("zkout"),
+            dir.path().join("zk_compile_commands.json"),
+        ];
+        let targets = existing_targets(zk_targets.clone());
+        assert_eq!(targets, vec![out.join("zksync"), dir.path().join("zk_compile_commands.json")]);
+        assert_eq!(targets.iter().map(|target| disk_usage(target)).sum::<u64>(), 17);
+
+        let mut all_targets = zk_targets;
+        all_targets.push(out.clone());
+        let targets = existing_targets(all_targets);
+        assert_eq!(targets, vec![dir.path().join("zk_compile_commands.json"), out.clone()]);
+        assert_eq!(disk_usage(&out), 18);
+    }
+}
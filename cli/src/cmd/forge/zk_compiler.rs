@@ -0,0 +1,178 @@
+//! The `zk_compiler` module provides the `forge zk-compiler` command, which manages the versions of
+//! the `zksolc` compiler used to build zkSync contracts.
+//!
+//! This module consists of the following key structures:
+//!
+//! * `ZkCompilerArgs`: The CLI arguments of `forge zk-compiler`, which dispatch to one of the
+//!   `ZkCompilerSubcommands`.
+//!
+//! * `ZkCompilerVersionsArgs`: The CLI arguments of `forge zk-compiler versions`, which lists all the
+//!   `zksolc` versions published upstream.
+//!
+//! * `ZkCompilerUseArgs`: The CLI arguments of `forge zk-compiler use`, which installs a `zksolc` version
+//!   and selects it for the project in `foundry.toml`.
+//!
+//! * `ZkCompilerCurrentArgs`: The CLI arguments of `forge zk-compiler current`, which prints the `zksolc`
+//!   version used by the project.
+//!
+//! * `ZkCompilerListArgs`: The CLI arguments of `forge zk-compiler list`, which lists the installed `zksolc`
+//!   versions.
+use super::zksolc_manager::{
+    ZkSolcManager, ZkSolcManagerBuilder, ZkSolcManagerOpts, ZkSolcVersion, DEFAULT_ZKSOLC_VERSION,
+};
+use crate::cmd::{Cmd, LoadConfig};
+use clap::{Parser, Subcommand, ValueHint};
+use foundry_config::impl_figment_convert_basic;
+use std::path::PathBuf;
+
+/// CLI arguments for `forge zk-compiler`.
+#[derive(Debug, Parser)]
+pub struct ZkCompilerArgs {
+    #[clap(subcommand)]
+    pub sub: ZkCompilerSubcommands,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ZkCompilerSubcommands {
+    #[clap(about = "Lists all the zksolc versions published upstream.")]
+    Versions(ZkCompilerVersionsArgs),
+
+    #[clap(about = "Installs a zksolc version and sets it as the version of the project.")]
+    Use(ZkCompilerUseArgs),
+
+    #[clap(about = "Prints the zksolc version of the project.")]
+    Current(ZkCompilerCurrentArgs),
+
+    #[clap(about = "Lists the installed zksolc versions.")]
+    List(ZkCompilerListArgs),
+}
+
+/// CLI arguments for `forge zk-compiler versions`.
+#[derive(Debug, Parser)]
+pub struct ZkCompilerVersionsArgs {}
+
+impl Cmd for ZkCompilerVersionsArgs {
+    type Output = ();
+
+    /// Prints every published `zksolc` version, one per line and in ascending order.
+    ///
+    /// If the upstream releases cannot be queried, the versions cached by the last successful query
+    /// are printed instead.
+    fn run(self) -> eyre::Result<Self::Output> {
+        let versions = ZkSolcManager::available_versions()
+            .map_err(|err| eyre::eyre!("Failed to list zksolc versions: {}", err))?;
+
+        for version in versions {
+            println!("v{version}");
+        }
+
+        Ok(())
+    }
+}
+
+/// CLI arguments for `forge zk-compiler use`.
+#[derive(Debug, Clone, Parser)]
+pub struct ZkCompilerUseArgs {
+    /// The zksolc version to use, in the format `v1.3.x` or `1.3.x`.
+    #[clap(value_name = "VERSION")]
+    pub version: ZkSolcVersion,
+
+    #[clap(
+        help = "The project's root path. By default, this is the root directory of the current Git repository or the current working directory if it is not part of a Git repository",
+        long,
+        value_hint = ValueHint::DirPath,
+        value_name = "PATH"
+    )]
+    root: Option<PathBuf>,
+}
+impl_figment_convert_basic!(ZkCompilerUseArgs);
+
+impl Cmd for ZkCompilerUseArgs {
+    type Output = ();
+
+    /// Installs the `zksolc` version and sets it as `use_zksolc` in the `[zksync]` section of
+    /// `foundry.toml`.
+    ///
+    /// # Procedure
+    /// 1. Downloads the version if it is not installed yet, verifying its checksum if it is known.
+    /// 2. Sets the version in `foundry.toml`, keeping the rest of the file untouched.
+    ///
+    /// # Errors
+    /// An error is returned if the version is not supported, if it cannot be downloaded or if the
+    /// project has no `foundry.toml`.
+    fn run(self) -> eyre::Result<Self::Output> {
+        let version = self.version.clone();
+        let config = self.try_load_config_emit_warnings()?;
+        if !config.get_config_path().exists() {
+            eyre::bail!(
+                "No foundry.toml found in {}, run `forge init` first",
+                config.__root.0.display()
+            );
+        }
+
+        let zksolc_manager = ZkSolcManagerBuilder::new(ZkSolcManagerOpts::new(version.clone()))
+            .build()
+            .map_err(|err| eyre::eyre!("Error building zksolc_manager: {}", err))?;
+        if !zksolc_manager.exists() {
+            println!(
+                "Downloading zksolc compiler to {}",
+                zksolc_manager.get_full_compiler_path().display()
+            );
+        }
+        zksolc_manager.ensure_version().map_err(|err| eyre::eyre!("{}", err))?;
+
+        config.update_zksolc_version(&version.to_string())?;
+        println!("Using zksolc {version}");
+
+        Ok(())
+    }
+}
+
+/// CLI arguments for `forge zk-compiler current`.
+#[derive(Debug, Clone, Parser)]
+pub struct ZkCompilerCurrentArgs {
+    #[clap(
+        help = "The project's root path. By default, this is the root directory of the current Git repository or the current working directory if it is not part of a Git repository",
+        long,
+        value_hint = ValueHint::DirPath,
+        value_name = "PATH"
+    )]
+    root: Option<PathBuf>,
+}
+impl_figment_convert_basic!(ZkCompilerCurrentArgs);
+
+impl Cmd for ZkCompilerCurrentArgs {
+    type Output = ();
+
+    /// Prints the `zksolc` version set in the `[zksync]` section of the config, or the default
+    /// version if none is set.
+    fn run(self) -> eyre::Result<Self::Output> {
+        let config = self.try_load_config_emit_warnings()?;
+        match config.zksync.use_zksolc {
+            Some(version) => println!("{version}"),
+            None => println!("{DEFAULT_ZKSOLC_VERSION} (default)"),
+        }
+
+        Ok(())
+    }
+}
+
+/// CLI arguments for `forge zk-compiler list`.
+#[derive(Debug, Clone, Parser)]
+pub struct ZkCompilerListArgs {}
+
+impl Cmd for ZkCompilerListArgs {
+    type Output = ();
+
+    /// Prints every installed `zksolc` version, one per line and in ascending order.
+    fn run(self) -> eyre::Result<Self::Output> {
+        let versions = ZkSolcManager::installed_versions()
+            .map_err(|err| eyre::eyre!("Failed to list installed zksolc versions: {}", err))?;
+
+        for version in versions {
+            println!("v{version}");
+        }
+
+        Ok(())
+    }
+}
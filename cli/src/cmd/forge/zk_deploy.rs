@@ -0,0 +1,784 @@
+//! The `zk_deploy` module provides the `forge zk-deploy-artifact` command, which deploys a contract
+//! that was previously compiled with `forge zk-build` to a zkSync network.
+//!
+//! Unlike `forge zk-create`, which takes the constructor arguments as human readable values, this
+//! command is meant to be scripted: the contract is looked up by name in the `zkout` artifacts and
+//! the constructor arguments are given already ABI-encoded. Complex arguments can be read from
+//! a file with `--constructor-args-path`, either ABI-encoded or as a JSON array `[[arg1, ...]]`,
+//! which is encoded with the constructor of the contract.
+//!
+//! The deployment process involves:
+//! 1. Loading the configuration and resolving the RPC URL, falling back to `eth_rpc_url`.
+//! 2. Finding the artifact of the contract in the `zkout` directory.
+//! 3. Decoding the ABI-encoded constructor arguments with the constructor of the contract.
+//! 4. Sending a zkSync `CREATE` transaction through the `ContractDeployer` system contract. The
+//!    contract is identified by the hash of its zkEVM bytecode, which is sent together with its
+//!    factory dependencies.
+//! 5. Waiting for `--confirmations` blocks, up to `--timeout`, and printing the address of the
+//!    deployed contract.
+//!
+//! With `--salt`, the contract is deployed with `CREATE2` instead, at an address which only
+//! depends on the deployer, the salt, the bytecode hash and the constructor arguments. The
+//! address is computed with the zkSync formula and printed before the transaction is sent, and
+//! `--no-broadcast` only prints it.
+//!
+//! With `--paymaster-address`, the fees of the deployment are paid by a paymaster contract, which
+//! is given the `--paymaster-input` of the `General` or `ApprovalBased` flow, so the deployer
+//! doesn't need a balance to cover them.
+//!
+//! With `--ledger`, the transaction is signed by a Ledger device instead of a private key, at the
+//! `--hd-path` derivation path or the first Ledger Live account. The device signs the EIP-712
+//! hashes of the zkSync transaction, so blind signing must be enabled in its Ethereum app.
+use super::{
+    build::CoreBuildArgs,
+    zk_artifacts::{find_contract, ZkArtifactContract},
+    zksolc::{get_bytecode, get_factory_dependencies},
+};
+use crate::{
+    cmd::{
+        cast::zk_utils::zk_utils::{get_private_key, get_rpc_url},
+        LoadConfig,
+    },
+    opts::EthereumOpts,
+};
+use clap::{Parser, ValueHint};
+use ethers::{
+    abi::{self, Abi, ParamType, Token},
+    signers::{HDPath as LedgerHDPath, Ledger, Signer as _},
+    types::{
+        transaction::eip712::{EIP712Domain, Eip712},
+        H256, U256,
+    },
+    utils::{id, keccak256},
+};
+use eyre::Context;
+use forge::decode::decode_revert;
+use foundry_common::abi::parse_tokens;
+use serde_json::Value;
+use std::{
+    borrow::Cow,
+    convert::Infallible,
+    fs,
+    path::PathBuf,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+use zksync_web3_rs::{
+    eip712::{Eip712Meta, Eip712Transaction, Eip712TransactionRequest, PaymasterParams},
+    providers::{Http, Middleware, Provider, RpcError},
+    signers::{LocalWallet, Signer},
+    types::{
+        transaction::eip712::Eip712 as ZkEip712, Address, BlockId, Signature, TransactionReceipt,
+        TransactionRequest, TxHash,
+    },
+    zks_provider::ZKSProvider,
+    zks_utils::{CONTRACT_DEPLOYER_ADDR, EIP712_TX_TYPE},
+    ZKSWallet,
+};
+
+/// The prefix of the preimage of `CREATE2` addresses on zkSync.
+const CREATE2_PREFIX: &str = "zksyncCreate2";
+
+/// The interval before the receipt of a deployment is polled for the first time.
+const INITIAL_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The maximum interval between two polls of the receipt of a deployment.
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The EIP-712 type of zkSync transactions.
+const ZK_TRANSACTION_TYPE: &str = concat!(
+    "Transaction(uint256 txType,uint256 from,uint256 to,uint256 gasLimit,",
+    "uint256 gasPerPubdataByteLimit,uint256 maxFeePerGas,uint256 maxPriorityFeePerGas,",
+    "uint256 paymaster,uint256 nonce,uint256 value,bytes data,bytes32[] factoryDeps,",
+    "bytes paymasterInput)"
+);
+
+/// The signatures of the paymaster flows, whose selector starts the input of a paymaster.
+const PAYMASTER_FLOWS: [&str; 2] = ["general(bytes)", "approvalBased(address,uint256,bytes)"];
+
+/// CLI arguments for `forge zk-deploy-artifact`.
+#[derive(Debug, Clone, Parser)]
+#[clap(next_help_heading = "ZkDeploy options", about = None)]
+pub struct ZkDeployArgs {
+    /// The name of the contract to deploy, optionally in the form `<path>:<contractname>`.
+    #[clap(long = "contract-name", value_name = "CONTRACT")]
+    pub contract_name: String,
+
+    /// The ABI-encoded constructor arguments, as a hex string.
+    #[clap(
+        long = "constructor-args",
+        value_name = "HEX",
+        conflicts_with = "constructor_args_path"
+    )]
+    pub constructor_args: Option<String>,
+
+    /// The path to a file containing the constructor arguments.
+    ///
+    /// The file contains either the ABI-encoded arguments as a hex string, or a JSON array
+    /// `[[arg1, arg2, ...]]` with the arguments of the constructor, which are ABI-encoded.
+    #[clap(
+        long,
+        value_hint = ValueHint::FilePath,
+        value_name = "FILE",
+        conflicts_with = "constructor_args"
+    )]
+    pub constructor_args_path: Option<PathBuf>,
+
+    /// Deploy the contract with `CREATE2` and the given 32 bytes salt, at a deterministic address.
+    #[clap(long, value_name = "BYTES32")]
+    pub salt: Option<H256>,
+
+    /// Only print the address the contract would be deployed to with `--salt`, without deploying
+    /// it.
+    #[clap(long, requires = "salt")]
+    pub no_broadcast: bool,
+
+    /// The address of the paymaster contract paying the fees of the deployment.
+    #[clap(long, value_name = "ADDRESS", requires = "paymaster_input")]
+    pub paymaster_address: Option<Address>,
+
+    /// The ABI-encoded input of the paymaster, as a hex string.
+    ///
+    /// The input must be a call to `general(bytes)` or to
+    /// `approvalBased(address,uint256,bytes)`, the flows of the `IPaymasterFlow` interface.
+    #[clap(long, value_name = "HEX", requires = "paymaster_address")]
+    pub paymaster_input: Option<String>,
+
+    /// The number of L2 blocks to wait for after the block including the deployment, included.
+    #[clap(long, default_value = "1", value_name = "N")]
+    pub confirmations: u64,
+
+    /// How long to wait for the deployment to be confirmed, in seconds.
+    #[clap(long, default_value = "120", value_name = "SECONDS")]
+    pub timeout: u64,
+
+    /// Core build arguments, used to locate the project and its configuration.
+    #[clap(flatten)]
+    pub opts: CoreBuildArgs,
+
+    /// Ethereum-specific options, such as the RPC URL and the private key.
+    #[clap(flatten)]
+    pub eth: EthereumOpts,
+}
+
+impl ZkDeployArgs {
+    /// Executes the command to deploy a contract.
+    ///
+    /// # Procedure
+    /// 1. Resolves the private key and the RPC URL. If `--rpc-url` is not provided, the
+    ///    `eth_rpc_url` of the configuration is used.
+    /// 2. Finds the zksolc artifact of the contract in the "zkout" directory.
+    /// 3. Encodes the constructor arguments, if any.
+    /// 4. Deploys the contract, together with its factory dependencies. Contracts deployed with
+    ///    constructor arguments are deployed with a call to the ContractDeployer, which takes the
+    ///    encoded arguments as they are.
+    /// 5. Prints the deployer and the transaction hash, then waits for the transaction to be
+    ///    confirmed and prints the address of the deployed contract.
+    ///
+    /// # Errors
+    /// An error is returned if the contract cannot be found, if the constructor arguments do not
+    /// match the constructor of the contract, or if the deployment fails. In the latter case the
+    /// error contains the revert reason, see `revert_reason`. If the deployment is not confirmed
+    /// within `--timeout`, the error contains the transaction hash to follow it up.
+    pub async fn run(self) -> eyre::Result<()> {
+        let config = self.opts.try_load_config_emit_warnings()?;
+        let rpc_url = match &self.eth.rpc_url {
+            Some(rpc_url) => Some(rpc_url.clone()),
+            None => config.get_rpc_url().transpose()?.map(Cow::into_owned),
+        };
+        let rpc_url = get_rpc_url(&rpc_url)?;
+
+        let mut project = config.project()?;
+        project.paths.artifacts = project.paths.root.join("zkout");
+
+        let ZkArtifactContract { output, contract, .. } =
+            find_contract(&project.paths.artifacts, &self.contract_name)?;
+        let abi: Abi = serde_json::from_value(contract["abi"].clone())
+            .wrap_err(format!("Failed to parse the ABI of {}", self.contract_name))?;
+        let bytecode = get_bytecode(&contract).map_err(|err| {
+            eyre::eyre!("Failed to find the bytecode of {}: {}", self.contract_name, err)
+        })?;
+        let factory_deps = get_factory_dependencies(&output, &contract).map_err(|err| {
+            eyre::eyre!("Failed to find the factory deps of {}: {}", self.contract_name, err)
+        })?;
+        let input = self.get_constructor_input(&abi)?;
+
+        let provider = Provider::try_from(rpc_url)?;
+        let chain = match self.eth.chain {
+            Some(chain) => u64::from(chain),
+            None => provider.get_chainid().await?.as_u64(),
+        };
+        let signer = self.get_signer(chain).await?;
+        let deployer = signer.address();
+
+        let paymaster = self.get_paymaster_params()?;
+        let tx_hash = match signer {
+            ZkDeploySigner::Local(wallet)
+                if self.salt.is_none() && paymaster.is_none() && input.is_empty() =>
+            {
+                println!("Deployer: {deployer:?}");
+                let zk_wallet = ZKSWallet::new(wallet, None, Some(provider.clone()), None)?;
+                let factory_deps = if factory_deps.is_empty() { None } else { Some(factory_deps) };
+                zk_wallet
+                    .deploy(
+                        serde_json::from_value(contract["abi"].clone())?,
+                        bytecode,
+                        vec![],
+                        factory_deps,
+                    )
+                    .await
+                    .map_err(|err| eyre::eyre!("Failed to deploy {}: {}", self.contract_name, err))?
+                    .transaction_hash
+            }
+            signer => {
+                let bytecode_hash = get_bytecode_hash(&contract).map_err(|err| {
+                    eyre::eyre!(
+                        "Failed to find the bytecode hash of {}: {}",
+                        self.contract_name,
+                        err
+                    )
+                })?;
+                println!("Deployer: {deployer:?}");
+
+                let (signature, salt) = match self.salt {
+                    Some(salt) => {
+                        let address = create2_address(deployer, salt, bytecode_hash, &input);
+                        println!("Predicted address: {address:?}");
+                        if self.no_broadcast {
+                            return Ok(());
+                        }
+                        ("create2(bytes32,bytes32,bytes)", salt)
+                    }
+                    None => ("create(bytes32,bytes32,bytes)", H256::zero()),
+                };
+                let mut calldata = id(signature).to_vec();
+                calldata.extend(abi::encode(&[
+                    Token::FixedBytes(salt.as_bytes().to_vec()),
+                    Token::FixedBytes(bytecode_hash.as_bytes().to_vec()),
+                    Token::Bytes(input),
+                ]));
+                let factory_deps = [vec![bytecode], factory_deps].concat();
+                send_to_deployer(&provider, &signer, calldata, factory_deps, paymaster)
+                    .await
+                    .map_err(|err| {
+                        eyre::eyre!("Failed to deploy {}: {}", self.contract_name, err)
+                    })?
+            }
+        };
+
+        println!("Transaction hash: {tx_hash:?}");
+        let timeout = Duration::from_secs(self.timeout);
+        let rcpt = wait_for_confirmations(&provider, tx_hash, self.confirmations, timeout).await?;
+        if rcpt.status != Some(1u64.into()) {
+            match revert_reason(&provider, &rcpt, &abi).await {
+                Some(reason) => eyre::bail!(
+                    "Failed to deploy {}: transaction {:?} reverted: {}",
+                    self.contract_name,
+                    tx_hash,
+                    reason
+                ),
+                None => eyre::bail!(
+                    "Failed to deploy {}: transaction {:?} reverted",
+                    self.contract_name,
+                    tx_hash
+                ),
+            }
+        }
+
+        let deployed_address =
+            rcpt.contract_address.ok_or(eyre::eyre!("Error retrieving deployed address"))?;
+        println!("Deployed to: {deployed_address:?}");
+
+        Ok(())
+    }
+
+    /// This function returns the ABI-encoded constructor arguments the contract is deployed with.
+    ///
+    /// # Returns
+    /// The encoded arguments, see `get_encoded_constructor_args`, or an empty vector if none are
+    /// provided.
+    ///
+    /// # Errors
+    /// An error is returned if arguments are provided for a contract without a constructor.
+    fn get_constructor_input(&self, abi: &Abi) -> eyre::Result<Vec<u8>> {
+        if abi.constructor.is_none() {
+            if self.constructor_args.is_some() || self.constructor_args_path.is_some() {
+                eyre::bail!("{} has no constructor", self.contract_name);
+            }
+            return Ok(vec![]);
+        }
+        self.get_encoded_constructor_args(abi)
+    }
+
+    /// This function returns the signer of the deployment for the chain `chain`.
+    ///
+    /// # Errors
+    /// An error is returned if no private key is provided without `--ledger`, or if the Ledger
+    /// device is not connected, locked, or its Ethereum app is not open.
+    async fn get_signer(&self, chain: u64) -> eyre::Result<ZkDeploySigner> {
+        if !self.eth.wallet.ledger {
+            let private_key = get_private_key(&self.eth.wallet.private_key)?;
+            let wallet = LocalWallet::from_str(&format!("{private_key:?}"))?.with_chain_id(chain);
+            return Ok(ZkDeploySigner::Local(wallet));
+        }
+
+        let derivation = match &self.eth.wallet.hd_path {
+            Some(hd_path) => LedgerHDPath::Other(hd_path.clone()),
+            None => LedgerHDPath::LedgerLive(0),
+        };
+        let ledger = Ledger::new(derivation, chain).await.map_err(|err| {
+            eyre::eyre!(
+                "Ledger device not available ({}). Connect it, unlock it and open the Ethereum app",
+                err
+            )
+        })?;
+        Ok(ZkDeploySigner::Ledger(ledger))
+    }
+
+    /// This function returns the paymaster of the deployment, if any.
+    ///
+    /// # Errors
+    /// An error is returned if the paymaster input is not hex encoded, or if it doesn't call one of
+    /// the paymaster flows.
+    fn get_paymaster_params(&self) -> eyre::Result<Option<PaymasterParams>> {
+        let (paymaster, input) = match (self.paymaster_address, &self.paymaster_input) {
+            (Some(paymaster), Some(input)) => (paymaster, input),
+            _ => return Ok(None),
+        };
+
+        let input = hex::decode(input.trim_start_matches("0x"))
+            .wrap_err("The paymaster input must be hex encoded")?;
+        if !PAYMASTER_FLOWS.iter().any(|flow| input.starts_with(&id(flow))) {
+            eyre::bail!(
+                "The paymaster input must call one of the paymaster flows: {}",
+                PAYMASTER_FLOWS.join(", ")
+            );
+        }
+
+        Ok(Some(PaymasterParams { paymaster, paymaster_input: input }))
+    }
+
+    /// This function returns the ABI-encoded constructor arguments as bytes.
+    ///
+    /// The arguments are read from `--constructor-args`, or from the `--constructor-args-path`
+    /// file. A file containing a JSON array is encoded with the inputs of the constructor in
+    /// `abi`.
+    ///
+    /// # Returns
+    /// The ABI-encoded constructor arguments, or an empty vector if they are not provided.
+    ///
+    /// # Errors
+    /// An error is returned if the file cannot be read, if the arguments are not hex encoded, or
+    /// if the JSON arguments don't match the constructor.
+    fn get_encoded_constructor_args(&self, abi: &Abi) -> eyre::Result<Vec<u8>> {
+        let path = match &self.constructor_args_path {
+            Some(path) => path,
+            None => {
+                let encoded = self.constructor_args.as_deref().unwrap_or_default();
+                return hex::decode(encoded.trim_start_matches("0x"))
+                    .wrap_err("Constructor arguments must be hex encoded");
+            }
+        };
+
+        let content = fs::read_to_string(path).wrap_err(format!(
+            "Failed to read the constructor arguments file {}",
+            path.display()
+        ))?;
+        let content = content.trim();
+        if !content.starts_with('[') {
+            return hex::decode(content.trim_start_matches("0x")).wrap_err(format!(
+                "The constructor arguments file {} must be hex encoded or a JSON array",
+                path.display()
+            ));
+        }
+
+        let params: Vec<_> = match &abi.constructor {
+            Some(constructor) => {
+                constructor.inputs.iter().map(|input| input.kind.clone()).collect()
+            }
+            None => vec![],
+        };
+        let args: Vec<Vec<Value>> = serde_json::from_str(content).wrap_err(format!(
+            "The constructor arguments file {} must contain a JSON array `[[arg1, arg2, ...]]`",
+            path.display()
+        ))?;
+        let args = match args.as_slice() {
+            [args] => args,
+            _ => eyre::bail!(
+                "The constructor arguments file {} must contain a single array of arguments",
+                path.display()
+            ),
+        };
+        let tokens = encode_json_args(&params, args).wrap_err(format!(
+            "Failed to encode the constructor arguments of {}",
+            self.contract_name
+        ))?;
+        Ok(abi::encode(&tokens))
+    }
+}
+
+/// This function converts the JSON constructor arguments `args` to tokens of the types `params`.
+///
+/// Arrays are given as JSON arrays, and tuples as JSON arrays of their components. Every other
+/// value is given as a string, a number or a boolean, and parsed like a `forge create` argument.
+///
+/// # Errors
+/// An error is returned if the number of arguments doesn't match, or if an argument cannot be
+/// parsed as its type.
+fn encode_json_args(params: &[ParamType], args: &[Value]) -> eyre::Result<Vec<Token>> {
+    if params.len() != args.len() {
+        eyre::bail!("Expected {} arguments, found {}", params.len(), args.len());
+    }
+    params.iter().zip(args).map(|(param, arg)| encode_json_arg(param, arg)).collect()
+}
+
+/// This function converts the JSON constructor argument `arg` to a token of the type `param`.
+fn encode_json_arg(param: &ParamType, arg: &Value) -> eyre::Result<Token> {
+    let token = match (param, arg) {
+        (ParamType::Array(kind), Value::Array(items)) => Token::Array(
+            items.iter().map(|item| encode_json_arg(kind, item)).collect::<eyre::Result<_>>()?,
+        ),
+        (ParamType::FixedArray(kind, len), Value::Array(items)) => {
+            if items.len() != *len {
+                eyre::bail!("Expected {} items for {}, found {}", len, param, items.len());
+            }
+            Token::FixedArray(
+                items
+                    .iter()
+                    .map(|item| encode_json_arg(kind, item))
+                    .collect::<eyre::Result<_>>()?,
+            )
+        }
+        (ParamType::Tuple(kinds), Value::Array(items)) => {
+            Token::Tuple(encode_json_args(kinds, items)?)
+        }
+        (_, Value::Array(_) | Value::Object(_) | Value::Null) => {
+            eyre::bail!("Unexpected value `{}` for {}", arg, param)
+        }
+        (_, Value::String(value)) => parse_tokens([(param, value.as_str())], true)?.remove(0),
+        (_, value) => parse_tokens([(param, value.to_string().as_str())], true)?.remove(0),
+    };
+    Ok(token)
+}
+
+/// This function computes the address of a contract deployed with `CREATE2` on zkSync.
+///
+/// Unlike the EVM, whose `CREATE2` addresses hash the init code, zkSync hashes the hash of the
+/// zkEVM bytecode and the hash of the constructor arguments separately:
+/// `keccak256(keccak256("zksyncCreate2") ++ sender ++ salt ++ bytecodeHash ++ keccak256(input))`,
+/// where the sender is padded to 32 bytes and the last 20 bytes of the hash are the address.
+fn create2_address(sender: Address, salt: H256, bytecode_hash: H256, input: &[u8]) -> Address {
+    let mut preimage = keccak256(CREATE2_PREFIX).to_vec();
+    preimage.extend([0u8; 12]);
+    preimage.extend(sender.as_bytes());
+    preimage.extend(salt.as_bytes());
+    preimage.extend(bytecode_hash.as_bytes());
+    preimage.extend(keccak256(input));
+    Address::from_slice(&keccak256(preimage)[12..])
+}
+
+/// This function returns the hash of the zkEVM bytecode of a compiled contract, as reported by
+/// zksolc.
+fn get_bytecode_hash(contract: &Value) -> eyre::Result<H256> {
+    let hash = contract["hash"].as_str().ok_or(eyre::eyre!("The artifact has no bytecode hash"))?;
+    let hash = hex::decode(hash.trim_start_matches("0x"))?;
+    if hash.len() != 32 {
+        eyre::bail!("The bytecode hash must be 32 bytes long, got {}", hash.len());
+    }
+    Ok(H256::from_slice(&hash))
+}
+
+/// The signer of the transactions of `forge zk-deploy-artifact`.
+enum ZkDeploySigner {
+    /// A private key.
+    Local(LocalWallet),
+    /// A Ledger device.
+    Ledger(Ledger),
+}
+
+impl ZkDeploySigner {
+    /// Returns the address of the signer.
+    fn address(&self) -> Address {
+        match self {
+            ZkDeploySigner::Local(wallet) => wallet.address(),
+            ZkDeploySigner::Ledger(ledger) => Address::from(ledger.address().0),
+        }
+    }
+
+    /// Returns the id of the chain the signer signs transactions for.
+    fn chain_id(&self) -> u64 {
+        match self {
+            ZkDeploySigner::Local(wallet) => wallet.chain_id(),
+            ZkDeploySigner::Ledger(ledger) => ledger.chain_id(),
+        }
+    }
+
+    /// This function signs the EIP-712 hash of the zkSync transaction `tx`.
+    ///
+    /// # Errors
+    /// With a Ledger, an error is returned if the transaction is rejected on the device, or if its
+    /// Ethereum app doesn't support signing EIP-712 hashes.
+    async fn sign(&self, tx: &Eip712Transaction) -> eyre::Result<Signature> {
+        match self {
+            ZkDeploySigner::Local(wallet) => Ok(wallet.sign_typed_data(tx).await?),
+            ZkDeploySigner::Ledger(ledger) => {
+                println!("Confirm the transaction on the Ledger device...");
+                let signature =
+                    ledger.sign_typed_struct(&ZkLedgerPayload::new(tx)?).await.map_err(|err| {
+                        eyre::eyre!(
+                            "The Ledger device didn't sign the transaction ({}). Confirm it on \
+                             the device, with blind signing enabled in the Ethereum app settings",
+                            err
+                        )
+                    })?;
+                Ok(Signature::try_from(signature.to_vec().as_slice())?)
+            }
+        }
+    }
+}
+
+/// The EIP-712 hashes of a zkSync transaction, which the Ledger device signs.
+///
+/// `ethers` signs typed data with a Ledger from the domain separator and the struct hash of the
+/// payload, which are computed by `zksync_web3_rs` for the zkSync transaction.
+struct ZkLedgerPayload {
+    domain: EIP712Domain,
+    struct_hash: [u8; 32],
+}
+
+impl ZkLedgerPayload {
+    /// Computes the EIP-712 hashes of `tx`.
+    fn new(tx: &Eip712Transaction) -> eyre::Result<Self> {
+        let domain = ZkEip712::domain(tx).map_err(|err| eyre::eyre!("{}", err))?;
+        let struct_hash = ZkEip712::struct_hash(tx).map_err(|err| eyre::eyre!("{}", err))?;
+        Ok(Self {
+            domain: EIP712Domain {
+                name: domain.name,
+                version: domain.version,
+                chain_id: domain.chain_id.map(|chain_id| U256(chain_id.0)),
+                verifying_contract: domain
+                    .verifying_contract
+                    .map(|address| ethers::types::Address::from(address.0)),
+                salt: domain.salt,
+            },
+            struct_hash,
+        })
+    }
+}
+
+impl Eip712 for ZkLedgerPayload {
+    type Error = Infallible;
+
+    fn domain(&self) -> Result<EIP712Domain, Self::Error> {
+        Ok(self.domain.clone())
+    }
+
+    fn type_hash() -> Result<[u8; 32], Self::Error> {
+        Ok(keccak256(ZK_TRANSACTION_TYPE))
+    }
+
+    fn struct_hash(&self) -> Result<[u8; 32], Self::Error> {
+        Ok(self.struct_hash)
+    }
+}
+
+/// This function sends a zkSync EIP-712 transaction calling the `ContractDeployer` system
+/// contract with `calldata`, publishing `factory_deps` with it.
+///
+/// If a `paymaster` is given, the fees of the transaction are paid by it.
+///
+/// # Returns
+/// The hash of the transaction.
+async fn send_to_deployer(
+    provider: &Provider<Http>,
+    signer: &ZkDeploySigner,
+    calldata: Vec<u8>,
+    factory_deps: Vec<Vec<u8>>,
+    paymaster: Option<PaymasterParams>,
+) -> eyre::Result<TxHash> {
+    let gas_price = provider.get_gas_price().await?;
+    let mut meta = Eip712Meta::new().factory_deps(factory_deps);
+    if let Some(paymaster) = paymaster {
+        meta = meta.paymaster_params(paymaster);
+    }
+    let mut request = Eip712TransactionRequest::new()
+        .r#type(EIP712_TX_TYPE)
+        .from(signer.address())
+        .to(Address::from_str(CONTRACT_DEPLOYER_ADDR)?)
+        .chain_id(signer.chain_id())
+        .nonce(provider.get_transaction_count(signer.address(), None).await?)
+        .gas_price(gas_price)
+        .max_fee_per_gas(gas_price)
+        .data(calldata)
+        .custom_data(meta.clone());
+
+    let fee = provider.estimate_fee(request.clone()).await.map_err(|err| eyre::eyre!("{}", err))?;
+    request = request
+        .max_priority_fee_per_gas(fee.max_priority_fee_per_gas)
+        .max_fee_per_gas(fee.max_fee_per_gas)
+        .gas_limit(fee.gas_limit);
+
+    let signable: Eip712Transaction =
+        request.clone().try_into().map_err(|err| eyre::eyre!("{}", err))?;
+    let signature = signer.sign(&signable).await?;
+    request = request.custom_data(meta.custom_signature(signature.to_vec()));
+    let encoded = request.rlp_signed(signature).map_err(|err| eyre::eyre!("{}", err))?;
+
+    let pending =
+        provider.send_raw_transaction([&[EIP712_TX_TYPE], &*encoded].concat().into()).await?;
+    Ok(pending.tx_hash())
+}
+
+/// This function returns the reason the deployment of `rcpt` reverted.
+///
+/// The receipt doesn't contain the revert data, so the transaction is replayed with `eth_call` on
+/// the state of the block before the one it was included in. The revert data is decoded with the
+/// `abi` of the deployed contract, so that both revert strings and the custom errors of its
+/// constructor are displayed in a human readable form.
+///
+/// # Returns
+/// The decoded revert reason, or `None` if the transaction cannot be replayed or if the replay
+/// doesn't revert.
+async fn revert_reason(
+    provider: &Provider<Http>,
+    rcpt: &TransactionReceipt,
+    abi: &Abi,
+) -> Option<String> {
+    let tx = provider.get_transaction(rcpt.transaction_hash).await.ok()??;
+    let mut request =
+        TransactionRequest::new().from(tx.from).data(tx.input).value(tx.value).gas(tx.gas);
+    if let Some(to) = tx.to {
+        request = request.to(to);
+    }
+    let block = rcpt.block_number.map(|block| BlockId::from(block.as_u64().saturating_sub(1)));
+
+    let err = provider.call(&request.into(), block).await.err()?;
+    match err.as_error_response().and_then(|err| err.as_revert_data()) {
+        Some(data) => Some(
+            decode_revert(&data, Some(abi), None)
+                .unwrap_or_else(|_| format!("custom error 0x{}", hex::encode(&data))),
+        ),
+        None => Some(err.to_string()),
+    }
+}
+
+/// This function waits for the transaction `tx_hash` to be included in a block, followed by
+/// `confirmations - 1` other blocks.
+///
+/// The receipt is polled with an exponential backoff, from `INITIAL_POLL_INTERVAL` up to
+/// `MAX_POLL_INTERVAL`.
+///
+/// # Errors
+/// An error with the transaction hash is returned if the transaction is not confirmed within
+/// `timeout`.
+async fn wait_for_confirmations(
+    provider: &Provider<Http>,
+    tx_hash: TxHash,
+    confirmations: u64,
+    timeout: Duration,
+) -> eyre::Result<TransactionReceipt> {
+    let start = Instant::now();
+    let mut interval = INITIAL_POLL_INTERVAL;
+    loop {
+        if let Some(rcpt) = provider.get_transaction_receipt(tx_hash).await? {
+            if let Some(block) = rcpt.block_number {
+                let latest = provider.get_block_number().await?;
+                if latest.saturating_sub(block).as_u64() + 1 >= confirmations {
+                    return Ok(rcpt);
+                }
+            }
+        }
+
+        let elapsed = start.elapsed();
+        if elapsed >= timeout {
+            eyre::bail!(
+                "Transaction {:?} was not confirmed within {}s, check its status on the explorer",
+                tx_hash,
+                timeout.as_secs()
+            );
+        }
+        tokio::time::sleep(interval.min(timeout - elapsed)).await;
+        interval = next_poll_interval(interval);
+    }
+}
+
+/// This function returns the interval before the next poll, doubling the current one up to
+/// `MAX_POLL_INTERVAL`.
+fn next_poll_interval(interval: Duration) -> Duration {
+    (interval * 2).min(MAX_POLL_INTERVAL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn receipt_polling_backs_off_exponentially_up_to_30_seconds() {
+        let mut interval = INITIAL_POLL_INTERVAL;
+        let mut intervals = Vec::new();
+        for _ in 0..9 {
+            intervals.push(interval.as_millis());
+            interval = next_poll_interval(interval);
+        }
+        assert_eq!(intervals, vec![500, 1000, 2000, 4000, 8000, 16000, 30000, 30000, 30000]);
+    }
+
+    #[test]
+    fn json_constructor_args_are_encoded_with_nested_types() {
+        let params = [
+            ParamType::Uint(256),
+            ParamType::Array(Box::new(ParamType::Tuple(vec![
+                ParamType::Address,
+                ParamType::String,
+            ]))),
+        ];
+        let args: Vec<Value> = serde_json::from_str(
+            r#"[42, [["0x0000000000000000000000000000000000000001", "a, b"]]]"#,
+        )
+        .unwrap();
+
+        let tokens = encode_json_args(&params, &args).unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Uint(42.into()),
+                Token::Array(vec![Token::Tuple(vec![
+                    Token::Address(ethers::types::Address::from_low_u64_be(1)),
+                    Token::String("a, b".to_string()),
+                ])]),
+            ]
+        );
+        assert!(encode_json_args(&params, &args[..1]).is_err());
+    }
+
+    #[test]
+    fn json_constructor_args_are_deployed_as_encoded() {
+        let abi: Abi = serde_json::from_str(
+            r#"[{
+                "type": "constructor",
+                "stateMutability": "nonpayable",
+                "inputs": [{ "name": "names", "type": "string[]" }]
+            }]"#,
+        )
+        .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("args.json");
+        fs::write(&path, r#"[[["a, b"]]]"#).unwrap();
+        let args = ZkDeployArgs::parse_from([
+            "zk-deploy-artifact",
+            "--contract-name",
+            "Greeter",
+            "--constructor-args-path",
+            path.to_str().unwrap(),
+        ]);
+
+        let input = args.get_constructor_input(&abi).unwrap();
+        assert_eq!(
+            abi::decode(&[ParamType::Array(Box::new(ParamType::String))], &input).unwrap(),
+            vec![Token::Array(vec![Token::String("a, b".to_string())])]
+        );
+
+        let abi: Abi = serde_json::from_str("[]").unwrap();
+        assert!(args.get_constructor_input(&abi).is_err());
+    }
+}
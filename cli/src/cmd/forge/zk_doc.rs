@@ -0,0 +1,318 @@
+//! The `zk_doc` module provides the `forge zk-doc` command, which renders the NatSpec
+//! documentation of a contract compiled with `zk-build --doc`.
+//!
+//! The `devdoc` and `userdoc` of the contract are read from its Foundry artifact in the `zksync`
+//! directory of the `out` path. The title, notice and details of the contract are followed by the
+//! functions, events and errors it documents, with their parameters and return values, like the
+//! pages of `forge doc`. The documentation is printed as Markdown, or written as an HTML page to
+//! the `--out` directory.
+use super::zk_artifacts::read_artifact;
+use crate::cmd::{Cmd, LoadConfig};
+use clap::{Parser, ValueHint};
+use foundry_config::impl_figment_convert_basic;
+use serde_json::Value;
+use std::{fmt::Write, fs, path::PathBuf};
+
+/// CLI arguments for `forge zk-doc`.
+#[derive(Debug, Clone, Parser)]
+pub struct ZkDocArgs {
+    /// The name of the contract, optionally in the form `<path>:<contractname>`.
+    #[clap(value_name = "CONTRACT")]
+    pub contract: String,
+
+    /// The directory to write the documentation to, as `<ContractName>.html`. By default, it is
+    /// printed to stdout as Markdown.
+    #[clap(long, value_hint = ValueHint::DirPath, value_name = "PATH")]
+    pub out: Option<PathBuf>,
+
+    #[clap(
+        help = "The project's root path. By default, this is the root directory of the current Git repository or the current working directory if it is not part of a Git repository",
+        long,
+        value_hint = ValueHint::DirPath,
+        value_name = "PATH"
+    )]
+    root: Option<PathBuf>,
+}
+impl_figment_convert_basic!(ZkDocArgs);
+
+impl Cmd for ZkDocArgs {
+    type Output = ();
+
+    /// Renders the documentation of the contract.
+    ///
+    /// # Errors
+    /// An error is returned if the contract was not compiled with `zk-build`, or if it was compiled
+    /// without `--doc`.
+    fn run(self) -> eyre::Result<Self::Output> {
+        let config = self.try_load_config_emit_warnings()?;
+        let out_dir = config.project_paths().artifacts.join("zksync");
+        let artifact = read_artifact(&out_dir, &self.contract)?;
+        if artifact["devdoc"].is_null() && artifact["userdoc"].is_null() {
+            eyre::bail!(
+                "The artifact of {} has no NatSpec documentation, build it with zk-build --doc",
+                self.contract
+            );
+        }
+
+        let name = self.contract.rsplit(':').next().unwrap_or(&self.contract);
+        let doc = ZkContractDoc::new(name, &artifact["devdoc"], &artifact["userdoc"]);
+        match &self.out {
+            Some(out) => {
+                fs::create_dir_all(out)?;
+                let path = out.join(format!("{name}.html"));
+                fs::write(&path, doc.to_html())?;
+                println!("Documentation written at {}", path.display());
+            }
+            None => print!("{}", doc.to_markdown()),
+        }
+        Ok(())
+    }
+}
+
+/// The NatSpec documentation of a contract, merged from its `devdoc` and `userdoc`.
+#[derive(Debug, Default, PartialEq)]
+struct ZkContractDoc {
+    /// The name of the contract.
+    name: String,
+    /// The `@title` of the contract.
+    title: Option<String>,
+    /// The `@author` of the contract.
+    author: Option<String>,
+    /// The `@notice` of the contract, for its users.
+    notice: Option<String>,
+    /// The `@dev` details of the contract, for developers.
+    details: Option<String>,
+    /// The documented functions, events and errors, under the heading of their kind.
+    sections: Vec<(&'static str, Vec<ZkItemDoc>)>,
+}
+
+/// The NatSpec documentation of a function, an event or an error.
+#[derive(Debug, Default, PartialEq)]
+struct ZkItemDoc {
+    /// The signature of the documented item.
+    signature: String,
+    /// The `@notice` of the item.
+    notice: Option<String>,
+    /// The `@dev` details of the item.
+    details: Option<String>,
+    /// The `@param` descriptions, by parameter name.
+    params: Vec<(String, String)>,
+    /// The `@return` descriptions, by return value name.
+    returns: Vec<(String, String)>,
+}
+
+impl ZkContractDoc {
+    /// Merges the `devdoc` and `userdoc` of the contract named `name`.
+    fn new(name: &str, devdoc: &Value, userdoc: &Value) -> Self {
+        let sections = [("Functions", "methods"), ("Events", "events"), ("Errors", "errors")]
+            .into_iter()
+            .map(|(heading, key)| (heading, item_docs(&devdoc[key], &userdoc[key])))
+            .filter(|(_, items)| !items.is_empty())
+            .collect();
+        Self {
+            name: name.to_string(),
+            title: text(&devdoc["title"]),
+            author: text(&devdoc["author"]),
+            notice: text(&userdoc["notice"]),
+            details: text(&devdoc["details"]),
+            sections,
+        }
+    }
+
+    /// Renders the documentation as Markdown.
+    fn to_markdown(&self) -> String {
+        let mut md = format!("# {}\n", self.name);
+        if let Some(title) = &self.title {
+            let _ = write!(md, "\n**{title}**\n");
+        }
+        for paragraph in [&self.notice, &self.details].into_iter().flatten() {
+            let _ = write!(md, "\n{paragraph}\n");
+        }
+        if let Some(author) = &self.author {
+            let _ = write!(md, "\n*Author: {author}*\n");
+        }
+
+        for (heading, items) in &self.sections {
+            let _ = write!(md, "\n## {heading}\n");
+            for item in items {
+                let _ = write!(md, "\n### `{}`\n", item.signature);
+                for paragraph in [&item.notice, &item.details].into_iter().flatten() {
+                    let _ = write!(md, "\n{paragraph}\n");
+                }
+                for (header, rows) in [("Parameter", &item.params), ("Return", &item.returns)] {
+                    if rows.is_empty() {
+                        continue;
+                    }
+                    let _ = write!(md, "\n| {header} | Description |\n| --- | --- |\n");
+                    for (name, description) in rows {
+                        let _ = writeln!(md, "| `{name}` | {description} |");
+                    }
+                }
+            }
+        }
+        md
+    }
+
+    /// Renders the documentation as a standalone HTML page.
+    fn to_html(&self) -> String {
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n\
+             </head>\n<body>\n<h1>{0}</h1>\n",
+            escape_html(&self.name)
+        );
+        if let Some(title) = &self.title {
+            let _ = writeln!(html, "<p><strong>{}</strong></p>", escape_html(title));
+        }
+        for paragraph in [&self.notice, &self.details].into_iter().flatten() {
+            let _ = writeln!(html, "<p>{}</p>", escape_html(paragraph));
+        }
+        if let Some(author) = &self.author {
+            let _ = writeln!(html, "<p><em>Author: {}</em></p>", escape_html(author));
+        }
+
+        for (heading, items) in &self.sections {
+            let _ = writeln!(html, "<h2>{heading}</h2>");
+            for item in items {
+                let _ = writeln!(html, "<h3><code>{}</code></h3>", escape_html(&item.signature));
+                for paragraph in [&item.notice, &item.details].into_iter().flatten() {
+                    let _ = writeln!(html, "<p>{}</p>", escape_html(paragraph));
+                }
+                for (header, rows) in [("Parameter", &item.params), ("Return", &item.returns)] {
+                    if rows.is_empty() {
+                        continue;
+                    }
+                    let _ =
+                        writeln!(html, "<table>\n<tr><th>{header}</th><th>Description</th></tr>");
+                    for (name, description) in rows {
+                        let _ = writeln!(
+                            html,
+                            "<tr><td><code>{}</code></td><td>{}</td></tr>",
+                            escape_html(name),
+                            escape_html(description)
+                        );
+                    }
+                    html.push_str("</table>\n");
+                }
+            }
+        }
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+/// This function merges the `devdoc` and `userdoc` entries of the functions, events or errors of a
+/// contract, sorted by signature.
+///
+/// The entries of errors are arrays, as several errors may share a signature, so their first
+/// entry is used.
+fn item_docs(devdoc: &Value, userdoc: &Value) -> Vec<ZkItemDoc> {
+    let mut signatures: Vec<&String> = devdoc
+        .as_object()
+        .into_iter()
+        .chain(userdoc.as_object())
+        .flat_map(|items| items.keys())
+        .collect();
+    signatures.sort();
+    signatures.dedup();
+
+    let entry = |doc: &Value, signature: &str| match &doc[signature] {
+        Value::Array(entries) => entries.first().cloned().unwrap_or_default(),
+        entry => entry.clone(),
+    };
+    signatures
+        .into_iter()
+        .map(|signature| {
+            let dev = entry(devdoc, signature);
+            let user = entry(userdoc, signature);
+            ZkItemDoc {
+                signature: signature.clone(),
+                notice: text(&user["notice"]),
+                details: text(&dev["details"]),
+                params: descriptions(&dev["params"]),
+                returns: descriptions(&dev["returns"]),
+            }
+        })
+        .collect()
+}
+
+/// This function returns the descriptions of the object `docs`, by parameter or return value name.
+fn descriptions(docs: &Value) -> Vec<(String, String)> {
+    docs.as_object()
+        .into_iter()
+        .flatten()
+        .filter_map(|(name, description)| text(description).map(|text| (name.clone(), text)))
+        .collect()
+}
+
+/// This function returns the trimmed string `value`, or `None` if it is not a non-empty string.
+fn text(value: &Value) -> Option<String> {
+    value.as_str().map(str::trim).filter(|text| !text.is_empty()).map(str::to_string)
+}
+
+/// This function escapes the characters of `text` which have a meaning in HTML.
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn devdoc_and_userdoc_are_rendered_together() {
+        let devdoc = json!({
+            "title": "A counter",
+            "author": "Matter Labs",
+            "methods": {
+                "add(uint256)": {
+                    "details": "Reverts on overflow.",
+                    "params": { "amount": "The amount to add" },
+                    "returns": { "_0": "The new count" }
+                }
+            },
+            "errors": { "Overflow()": [{ "details": "The count overflowed." }] }
+        });
+        let userdoc = json!({
+            "notice": "Counts things",
+            "methods": { "add(uint256)": { "notice": "Adds to the count" } }
+        });
+
+        let doc = ZkContractDoc::new("Counter", &devdoc, &userdoc);
+        assert_eq!(
+            doc.to_markdown(),
+            "\
+# Counter
+
+**A counter**
+
+Counts things
+
+*Author: Matter Labs*
+
+## Functions
+
+### `add(uint256)`
+
+Adds to the count
+
+Reverts on overflow.
+
+| Parameter | Description |
+| --- | --- |
+| `amount` | The amount to add |
+
+| Return | Description |
+| --- | --- |
+| `_0` | The new count |
+
+## Errors
+
+### `Overflow()`
+
+The count overflowed.
+"
+        );
+        assert!(doc.to_html().contains("<h3><code>add(uint256)</code></h3>"));
+    }
+}
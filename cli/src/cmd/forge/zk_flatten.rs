@@ -0,0 +1,190 @@
+//! The `zk_flatten` module provides the `forge zk-flatten` command, which flattens a source file
+//! and all of its imports into a single file, for the block explorers that only verify single
+//! file sources.
+//!
+//! The imports are resolved with the remappings of the project, like `forge flatten` does. The
+//! `SPDX-License-Identifier` comments and the `pragma` directives of the imported files are then
+//! removed, only keeping the first occurrence of each, so the file can be compiled on its own.
+//!
+//! With `--verify`, the flattened file is compiled with zksolc, in a temporary project with the
+//! settings of the current one, and the command fails if it doesn't compile.
+use super::{build::CoreBuildArgs, zk_build::ZkBuildArgs};
+use crate::cmd::{Cmd, LoadConfig};
+use clap::{Parser, ValueHint};
+use eyre::Context;
+use foundry_config::Config;
+use std::{collections::HashSet, fs, path::PathBuf};
+
+/// The comment declaring the license of a source file.
+const SPDX_LICENSE_IDENTIFIER: &str = "// SPDX-License-Identifier:";
+
+/// CLI arguments for `forge zk-flatten`.
+#[derive(Debug, Clone, Parser)]
+#[clap(next_help_heading = "ZkFlatten options", about = None)]
+pub struct ZkFlattenArgs {
+    /// The path to the contract to flatten.
+    #[clap(value_hint = ValueHint::FilePath, value_name = "TARGET_PATH")]
+    pub target_path: PathBuf,
+
+    /// The path to output the flattened contract. By default, it is printed to stdout.
+    #[clap(long, short, value_hint = ValueHint::FilePath, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+
+    /// Compile the flattened contract with zksolc, failing if it doesn't compile.
+    #[clap(long)]
+    pub verify: bool,
+
+    /// Core build arguments, used to locate the project and its configuration.
+    #[clap(flatten)]
+    pub opts: CoreBuildArgs,
+}
+
+impl Cmd for ZkFlattenArgs {
+    type Output = ();
+
+    /// Flattens the target file and writes it to `--output`, or prints it.
+    ///
+    /// # Errors
+    /// An error is returned if an import cannot be resolved or, with `--verify`, if the flattened
+    /// file fails to compile with zksolc.
+    fn run(self) -> eyre::Result<Self::Output> {
+        let config = self.opts.try_load_config_emit_warnings()?;
+        let target_path = dunce::canonicalize(&self.target_path)
+            .wrap_err(format!("Failed to find {}", self.target_path.display()))?;
+        let flattened = config
+            .project_paths()
+            .flatten(&target_path)
+            .map_err(|err| eyre::eyre!("Failed to flatten the file: {}", err))?;
+        let flattened = strip_duplicate_directives(&flattened);
+
+        if self.verify {
+            self.verify_flattened(&config, &flattened)?;
+        }
+
+        match &self.output {
+            Some(output) => {
+                if let Some(parent) = output.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(output, flattened)?;
+                println!("Flattened file written at {}", output.display());
+            }
+            None => println!("{flattened}"),
+        }
+        Ok(())
+    }
+}
+
+impl ZkFlattenArgs {
+    /// This function compiles the `flattened` source with zksolc.
+    ///
+    /// The source is written to the `src` directory of a temporary project, which has the settings
+    /// of `config` and writes its artifacts in the temporary directory, so that the outputs of the
+    /// current project are left untouched.
+    ///
+    /// # Errors
+    /// An error is returned if zksolc cannot be set up, or if the source fails to compile.
+    fn verify_flattened(&self, config: &Config, flattened: &str) -> eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file_name = self.target_path.file_name().unwrap_or_else(|| "Flattened.sol".as_ref());
+        let mut verify_config = config.clone();
+        verify_config.src = dir.path().join("src");
+        verify_config.out = dir.path().join("out");
+        verify_config.cache_path = dir.path().join("cache");
+        fs::create_dir_all(&verify_config.src)?;
+        fs::write(verify_config.src.join(file_name), flattened)?;
+
+        let build = ZkBuildArgs { args: self.opts.clone(), ..Default::default() };
+        let compiler_path = build.zksolc_path(&verify_config)?;
+        let mut project = verify_config.project()?;
+        project.paths.artifacts = dir.path().join("zkout");
+        println!("Compiling the flattened file with zksolc...");
+        let output = build.compile_smart_contracts(
+            &verify_config,
+            compiler_path,
+            project,
+            dir.path().join("zksync").join("cache"),
+        )?;
+
+        if let Some(outcome) = output.failed().next() {
+            match &outcome.error {
+                Some(err) => {
+                    eyre::bail!("The flattened file failed to compile with zksolc: {}", err)
+                }
+                None => eyre::bail!("The flattened file failed to compile with zksolc"),
+            }
+        }
+        println!("The flattened file compiles with zksolc");
+        Ok(())
+    }
+}
+
+/// This function removes the `SPDX-License-Identifier` comments and the `pragma` directives of
+/// `source` which are already declared earlier in it.
+///
+/// A file has a single license, so every license comment after the first one is removed. The
+/// `pragma` directives are compared by name, or by name and feature for `pragma experimental`, so
+/// only the first `pragma solidity` is kept even if the imported files require other versions.
+///
+/// # Returns
+/// The source without the duplicate directives.
+fn strip_duplicate_directives(source: &str) -> String {
+    let mut license = false;
+    let mut pragmas = HashSet::new();
+    let mut lines = Vec::new();
+    for line in source.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with(SPDX_LICENSE_IDENTIFIER) {
+            if license {
+                continue;
+            }
+            license = true;
+        } else if trimmed.starts_with("pragma ") {
+            let words: Vec<_> = trimmed.trim_end_matches(';').split_whitespace().collect();
+            let len = if words.get(1) == Some(&"experimental") { 3 } else { 2 };
+            if !pragmas.insert(words[..len.min(words.len())].join(" ")) {
+                continue;
+            }
+        }
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_first_license_and_pragmas_are_kept() {
+        let source = "\
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.0;
+pragma abicoder v2;
+
+contract A {}
+
+// SPDX-License-Identifier: UNLICENSED
+pragma solidity >=0.8.4;
+pragma experimental ABIEncoderV2;
+pragma experimental SMTChecker;
+pragma abicoder v2;
+
+contract B is A {}";
+
+        assert_eq!(
+            strip_duplicate_directives(source),
+            "\
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.0;
+pragma abicoder v2;
+
+contract A {}
+
+pragma experimental ABIEncoderV2;
+pragma experimental SMTChecker;
+
+contract B is A {}"
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! The `zk_gas_report` module provides the gas report printed by `forge zk-test --gas-report`.
+//!
+//! The gas used by every call of a test to the contracts deployed by the test contract is read
+//! from the `callTracer` trace of the test, as returned by `debug_traceCall` on the zkSync node,
+//! and aggregated per contract and function. The amounts are in ergs, the unit of computation of
+//! the zkSync VM, so they are not comparable to the EVM gas reported by `forge test --gas-report`.
+//!
+//! This module consists of the following key structures:
+//!
+//! * `ZkCallGas`: The ergs used by a single call to a function.
+//!
+//! * `ZkGasReport`: The min, average, median and max ergs of every function of the reported
+//!   contracts, printed as one table per contract.
+use comfy_table::{presets::ASCII_MARKDOWN, *};
+use ethers::types::U256;
+use forge::gas_report::GasInfo;
+use foundry_common::calc;
+use std::{collections::BTreeMap, fmt};
+
+/// The ergs used by a call to a function of a contract deployed by a test contract.
+#[derive(Debug, Clone)]
+pub struct ZkCallGas {
+    /// The contract identifier in the form `<path>:<contractname>`.
+    pub contract: String,
+    /// The name of the called function.
+    pub function: String,
+    /// The signature of the called function.
+    pub signature: String,
+    /// The ergs used by the call.
+    pub gas: U256,
+}
+
+/// The gas report of `forge zk-test --gas-report`.
+#[derive(Debug, Default)]
+pub struct ZkGasReport {
+    /// The names of the contracts to report, from the `gas_reports` config. `*` or an empty list
+    /// reports every contract.
+    pub report_for: Vec<String>,
+    /// The names of the contracts not to report, from the `gas_reports_ignore` config.
+    pub ignore: Vec<String>,
+    /// The ergs used by every function, keyed by contract identifier, function name and signature.
+    pub contracts: BTreeMap<String, BTreeMap<String, BTreeMap<String, GasInfo>>>,
+}
+
+impl ZkGasReport {
+    /// Creates an empty report for the contracts of `report_for`, except those of `ignore`.
+    pub fn new(report_for: Vec<String>, ignore: Vec<String>) -> Self {
+        Self { report_for, ignore, ..Default::default() }
+    }
+
+    /// Adds the ergs used by `calls` to the report, skipping the contracts that are not reported.
+    pub fn analyze(&mut self, calls: &[ZkCallGas]) {
+        for call in calls {
+            let name = call.contract.rsplit(':').next().unwrap_or(&call.contract);
+            if !self.should_report(name) {
+                continue;
+            }
+            self.contracts
+                .entry(call.contract.clone())
+                .or_default()
+                .entry(call.function.clone())
+                .or_default()
+                .entry(call.signature.clone())
+                .or_default()
+                .calls
+                .push(call.gas);
+        }
+    }
+
+    /// Returns whether the contract named `name` is reported, in the same way as `forge test`.
+    ///
+    /// A contract listed in both `gas_reports` and `gas_reports_ignore` is reported, with a
+    /// warning.
+    fn should_report(&self, name: &str) -> bool {
+        let listed = self.report_for.iter().any(|contract| contract == name);
+        let ignored = self.ignore.iter().any(|contract| contract == name);
+        if listed && ignored {
+            eprintln!(
+                "{}: {} is listed in both 'gas_reports' and 'gas_reports_ignore'.",
+                yansi::Paint::yellow("warning").bold(),
+                name
+            );
+        }
+        let all =
+            self.report_for.is_empty() || self.report_for.iter().any(|contract| contract == "*");
+        listed || (all && !ignored)
+    }
+
+    /// Computes the min, average, median and max ergs of every function of the report.
+    #[must_use]
+    pub fn finalize(mut self) -> Self {
+        for func in self.contracts.values_mut().flat_map(|functions| functions.values_mut()) {
+            for info in func.values_mut() {
+                info.calls.sort_unstable();
+                info.min = info.calls.first().copied().unwrap_or_default();
+                info.max = info.calls.last().copied().unwrap_or_default();
+                info.mean = calc::mean(&info.calls);
+                info.median = calc::median_sorted(&info.calls);
+            }
+        }
+        self
+    }
+}
+
+impl fmt::Display for ZkGasReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, functions) in &self.contracts {
+            let mut table = Table::new();
+            table.load_preset(ASCII_MARKDOWN);
+            table.set_header(vec![Cell::new(format!("{name} contract"))
+                .add_attribute(Attribute::Bold)
+                .fg(Color::Green)]);
+            table.add_row(vec![
+                Cell::new("Function Name").add_attribute(Attribute::Bold).fg(Color::Magenta),
+                Cell::new("min (ergs)").add_attribute(Attribute::Bold).fg(Color::Green),
+                Cell::new("avg (ergs)").add_attribute(Attribute::Bold).fg(Color::Yellow),
+                Cell::new("median (ergs)").add_attribute(Attribute::Bold).fg(Color::Yellow),
+                Cell::new("max (ergs)").add_attribute(Attribute::Bold).fg(Color::Red),
+                Cell::new("# calls").add_attribute(Attribute::Bold),
+            ]);
+            for (function, signatures) in functions {
+                for (signature, info) in signatures {
+                    // overloaded functions are displayed with their signature
+                    let display =
+                        if signatures.len() == 1 { function.clone() } else { signature.clone() };
+                    table.add_row(vec![
+                        Cell::new(display).add_attribute(Attribute::Bold),
+                        Cell::new(info.min.to_string()).fg(Color::Green),
+                        Cell::new(info.mean.to_string()).fg(Color::Yellow),
+                        Cell::new(info.median.to_string()).fg(Color::Yellow),
+                        Cell::new(info.max.to_string()).fg(Color::Red),
+                        Cell::new(info.calls.len().to_string()),
+                    ]);
+                }
+            }
+            writeln!(f, "{table}")?;
+            writeln!(f, "\n")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(contract: &str, function: &str, gas: u64) -> ZkCallGas {
+        ZkCallGas {
+            contract: contract.to_string(),
+            function: function.to_string(),
+            signature: format!("{function}()"),
+            gas: gas.into(),
+        }
+    }
+
+    #[test]
+    fn gas_report_aggregates_the_calls_of_every_function() {
+        let mut report = ZkGasReport::new(vec!["*".to_string()], vec!["Ignored".to_string()]);
+        report.analyze(&[
+            call("src/Counter.sol:Counter", "increment", 300),
+            call("src/Counter.sol:Counter", "increment", 100),
+            call("src/Counter.sol:Counter", "increment", 200),
+            call("src/Ignored.sol:Ignored", "increment", 100),
+        ]);
+        let report = report.finalize();
+
+        assert_eq!(report.contracts.len(), 1);
+        let info = &report.contracts["src/Counter.sol:Counter"]["increment"]["increment()"];
+        assert_eq!(info.calls.len(), 3);
+        assert_eq!(info.min, 100.into());
+        assert_eq!(info.mean, 200.into());
+        assert_eq!(info.median, 200.into());
+        assert_eq!(info.max, 300.into());
+        assert!(report.to_string().contains("median (ergs)"));
+    }
+}
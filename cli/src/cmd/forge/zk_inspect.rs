@@ -0,0 +1,216 @@
+//! The `zk_inspect` module provides the `forge zk-inspect` command, which prints a single field of
+//! the artifact of a contract compiled with `zk-build`, like `forge inspect` does for EVM
+//! artifacts.
+//!
+//! The fields are read from the Foundry artifact of the contract in the `zksync` directory of the
+//! `out` path, except for the `ast`, which is read from the `ast` directory `zk-build --ast` writes
+//! it to. The bytecodes are printed as 0x-prefixed hex strings and the other fields as JSON,
+//! compact by default or indented with `--pretty`.
+use super::zk_artifacts::read_artifact;
+use crate::cmd::{Cmd, LoadConfig};
+use clap::{Parser, ValueHint};
+use eyre::Context;
+use foundry_config::impl_figment_convert_basic;
+use serde_json::Value;
+use std::{fmt, fs, path::PathBuf, str::FromStr};
+
+/// The fields of a zkSync artifact which can be inspected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZkInspectField {
+    Abi,
+    Bytecode,
+    DeployedBytecode,
+    MethodIdentifiers,
+    StorageLayout,
+    Metadata,
+    Devdoc,
+    Userdoc,
+    Ast,
+}
+
+impl ZkInspectField {
+    /// All the fields, in the order they are listed in the error of an unknown field.
+    pub const ALL: [ZkInspectField; 9] = [
+        ZkInspectField::Abi,
+        ZkInspectField::Bytecode,
+        ZkInspectField::DeployedBytecode,
+        ZkInspectField::MethodIdentifiers,
+        ZkInspectField::StorageLayout,
+        ZkInspectField::Metadata,
+        ZkInspectField::Devdoc,
+        ZkInspectField::Userdoc,
+        ZkInspectField::Ast,
+    ];
+
+    /// Returns the name of the field in the Foundry artifact.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ZkInspectField::Abi => "abi",
+            ZkInspectField::Bytecode => "bytecode",
+            ZkInspectField::DeployedBytecode => "deployedBytecode",
+            ZkInspectField::MethodIdentifiers => "methodIdentifiers",
+            ZkInspectField::StorageLayout => "storageLayout",
+            ZkInspectField::Metadata => "metadata",
+            ZkInspectField::Devdoc => "devdoc",
+            ZkInspectField::Userdoc => "userdoc",
+            ZkInspectField::Ast => "ast",
+        }
+    }
+}
+
+impl FromStr for ZkInspectField {
+    type Err = String;
+
+    /// Parses the name of a field, case insensitively and ignoring `-` and `_`, so that both
+    /// `deployedBytecode` and `deployed-bytecode` are accepted.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalize = |name: &str| name.replace(['-', '_'], "").to_lowercase();
+        let name = normalize(s);
+        match Self::ALL.into_iter().find(|field| normalize(field.as_str()) == name) {
+            Some(field) => Ok(field),
+            None => Err(format!(
+                "Unknown field `{}`, supported fields are: {}",
+                s,
+                Self::ALL.iter().map(ZkInspectField::as_str).collect::<Vec<_>>().join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ZkInspectField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// CLI arguments for `forge zk-inspect`.
+#[derive(Debug, Clone, Parser)]
+pub struct ZkInspectArgs {
+    /// The name of the contract, optionally in the form `<path>:<contractname>`.
+    #[clap(value_name = "CONTRACT")]
+    pub contract: String,
+
+    /// The field to print.
+    #[clap(value_name = "FIELD")]
+    pub field: ZkInspectField,
+
+    /// Indent the JSON output.
+    #[clap(long)]
+    pub pretty: bool,
+
+    #[clap(
+        help = "The project's root path. By default, this is the root directory of the current Git repository or the current working directory if it is not part of a Git repository",
+        long,
+        value_hint = ValueHint::DirPath,
+        value_name = "PATH"
+    )]
+    root: Option<PathBuf>,
+}
+impl_figment_convert_basic!(ZkInspectArgs);
+
+impl Cmd for ZkInspectArgs {
+    type Output = ();
+
+    /// Prints the field of the artifact of the contract.
+    ///
+    /// # Errors
+    /// An error is returned if the contract was not compiled with `zk-build`, or if its artifact
+    /// doesn't have the field, for example the `storageLayout` of a contract compiled without
+    /// `--storage-layout`.
+    fn run(self) -> eyre::Result<Self::Output> {
+        let config = self.try_load_config_emit_warnings()?;
+        let paths = config.project_paths();
+        let out_dir = paths.artifacts.join("zksync");
+        let artifact = read_artifact(&out_dir, &self.contract)?;
+
+        let value = match self.field {
+            ZkInspectField::Ast => {
+                let source = match source_path(&self.contract, &artifact) {
+                    Some(source) => source,
+                    None => eyre::bail!(
+                        "Unable to find the source of {}, pass it as <path>:<contractname>",
+                        self.contract
+                    ),
+                };
+                let source = paths.root.join(source);
+                let source = source.strip_prefix(&paths.sources).unwrap_or(&source);
+                let path = out_dir.join("ast").join(format!("{}.json", source.display()));
+                let ast = fs::read_to_string(&path).wrap_err(format!(
+                    "Unable to read the AST of {} at {} - did you run zk-build --ast?",
+                    self.contract,
+                    path.display()
+                ))?;
+                serde_json::from_str(&ast)
+                    .wrap_err(format!("Unable to parse JSON from {}", path.display()))?
+            }
+            ZkInspectField::Bytecode | ZkInspectField::DeployedBytecode => {
+                artifact[self.field.as_str()]["object"].clone()
+            }
+            _ => artifact[self.field.as_str()].clone(),
+        };
+        if value.is_null() {
+            eyre::bail!("The artifact of {} has no {}", self.contract, self.field);
+        }
+        println!("{}", format_value(self.field, &value, self.pretty)?);
+        Ok(())
+    }
+}
+
+/// This function returns the path of the source of `contract`, relative to the project root.
+///
+/// It's the path of `contract` if it's in the form `<path>:<contractname>`, or the compilation
+/// target of the metadata of its `artifact` otherwise.
+fn source_path(contract: &str, artifact: &Value) -> Option<PathBuf> {
+    if let Some((source, _)) = contract.rsplit_once(':') {
+        return Some(PathBuf::from(source));
+    }
+    let metadata = match &artifact["metadata"] {
+        Value::String(metadata) => serde_json::from_str(metadata).ok()?,
+        metadata => metadata.clone(),
+    };
+    metadata["settings"]["compilationTarget"].as_object()?.keys().next().map(PathBuf::from)
+}
+
+/// This function formats the `value` of `field`, as a 0x-prefixed hex string for the bytecodes
+/// and as JSON, indented if `pretty` is set, for the other fields.
+fn format_value(field: ZkInspectField, value: &Value, pretty: bool) -> eyre::Result<String> {
+    if let (ZkInspectField::Bytecode | ZkInspectField::DeployedBytecode, Some(bytecode)) =
+        (field, value.as_str())
+    {
+        return Ok(format!("0x{}", bytecode.trim_start_matches("0x")));
+    }
+    if pretty {
+        return Ok(serde_json::to_string_pretty(value)?);
+    }
+    Ok(serde_json::to_string(value)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn fields_are_parsed_and_formatted() {
+        let args = ZkInspectArgs::parse_from(["zk-inspect", "Counter", "deployed-bytecode"]);
+        assert_eq!(args.field, ZkInspectField::DeployedBytecode);
+        assert_eq!("methodIdentifiers".parse(), Ok(ZkInspectField::MethodIdentifiers));
+        let err = "gasEstimates".parse::<ZkInspectField>().unwrap_err();
+        assert!(err.contains("supported fields are: abi, bytecode, deployedBytecode"));
+
+        assert_eq!(
+            format_value(ZkInspectField::Bytecode, &json!("0102"), false).unwrap(),
+            "0x0102"
+        );
+        assert_eq!(
+            format_value(ZkInspectField::Abi, &json!([{ "type": "fallback" }]), true).unwrap(),
+            "[\n  {\n    \"type\": \"fallback\"\n  }\n]"
+        );
+
+        let artifact = json!({
+            "metadata": "{\"settings\":{\"compilationTarget\":{\"src/Counter.sol\":\"Counter\"}}}"
+        });
+        assert_eq!(source_path("Counter", &artifact), Some(PathBuf::from("src/Counter.sol")));
+        assert_eq!(source_path("src/A.sol:Counter", &artifact), Some(PathBuf::from("src/A.sol")));
+    }
+}
@@ -0,0 +1,85 @@
+//! The `zk_remappings` module provides the `forge zk-remappings` command, which prints the
+//! remappings zksolc resolves the imports of the project with.
+//!
+//! These are the remappings of `foundry.toml`, the ones auto-detected from the libraries of the
+//! project and the ones passed with `--remappings`, formatted like in the `settings.remappings`
+//! entry of the standard JSON input `zk-build` passes to zksolc, in the `prefix=target` format of
+//! solc with the targets relative to the project root. A warning is printed for every remapping
+//! whose target doesn't exist, as imports through it fail with "file not found" errors.
+use super::{build::CoreBuildArgs, zksolc::remappings_settings};
+use crate::cmd::{Cmd, LoadConfig};
+use clap::Parser;
+use ethers::solc::remappings::Remapping;
+use std::path::{Path, PathBuf};
+use yansi::Paint;
+
+/// CLI arguments for `forge zk-remappings`.
+#[derive(Debug, Clone, Parser)]
+#[clap(next_help_heading = "ZkRemappings options", about = None)]
+pub struct ZkRemappingsArgs {
+    /// Core build arguments, which provide the project root and the `--remappings`.
+    #[clap(flatten)]
+    pub opts: CoreBuildArgs,
+}
+
+impl Cmd for ZkRemappingsArgs {
+    type Output = ();
+
+    /// Prints the effective remappings of the project, one per line.
+    ///
+    /// # Errors
+    /// An error is returned if the configuration or the project cannot be loaded.
+    fn run(self) -> eyre::Result<Self::Output> {
+        let config = self.opts.try_load_config_emit_warnings()?;
+        let project = config.project()?;
+        for (remapping, target) in
+            effective_remappings(&project.paths.remappings, &project.paths.root)
+        {
+            println!("{remapping}");
+            if !target.exists() {
+                eprintln!(
+                    "{}",
+                    Paint::yellow(format!(
+                        "Warning: the target {} of the remapping {} does not exist",
+                        target.display(),
+                        remapping
+                    ))
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// This function returns the `remappings` of the project in the format zksolc receives them, see
+/// `remappings_settings`, along with the absolute path of their target.
+fn effective_remappings(remappings: &[Remapping], root: &Path) -> Vec<(String, PathBuf)> {
+    remappings_settings(remappings, root)
+        .into_iter()
+        .zip(remappings)
+        .map(|(setting, remapping)| {
+            (setting.as_str().unwrap_or_default().to_string(), root.join(&remapping.path))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remappings_are_relative_to_the_root_with_absolute_targets() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("lib/forge-std/src")).unwrap();
+        let remappings: Vec<Remapping> = vec![
+            format!("forge-std/={}/lib/forge-std/src/", dir.path().display()).parse().unwrap(),
+            format!("@missing/={}/lib/missing/", dir.path().display()).parse().unwrap(),
+        ];
+
+        let effective = effective_remappings(&remappings, dir.path());
+        assert_eq!(effective[0].0, "forge-std/=lib/forge-std/src/");
+        assert!(effective[0].1.exists());
+        assert_eq!(effective[1].0, "@missing/=lib/missing/");
+        assert!(!effective[1].1.exists());
+    }
+}
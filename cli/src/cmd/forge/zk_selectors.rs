@@ -0,0 +1,174 @@
+//! The `zk_selectors` module provides the `forge zk-selectors` command, which prints the selectors
+//! of the functions, errors and events of a contract compiled with `zk-build`.
+//!
+//! The ABI is read from the Foundry artifact of the contract in the `zksync` directory of the
+//! `out` path. Functions and errors are printed with their 4 bytes selector and events with their
+//! 32 bytes topic, as a table or, with `--json`, as a JSON array, for example to configure the
+//! allowlists of proxies and governance contracts.
+use super::zk_artifacts::read_artifact;
+use crate::cmd::{Cmd, LoadConfig};
+use clap::{Parser, ValueHint};
+use comfy_table::{presets::ASCII_MARKDOWN, Attribute, Cell, Color, Table};
+use ethers::abi::{Abi, Param};
+use eyre::Context;
+use foundry_config::impl_figment_convert_basic;
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// CLI arguments for `forge zk-selectors`.
+#[derive(Debug, Clone, Parser)]
+pub struct ZkSelectorsArgs {
+    /// The name of the contract, optionally in the form `<path>:<contractname>`.
+    #[clap(value_name = "CONTRACT")]
+    pub contract: String,
+
+    /// Print the selectors as JSON.
+    #[clap(long)]
+    pub json: bool,
+
+    #[clap(
+        help = "The project's root path. By default, this is the root directory of the current Git repository or the current working directory if it is not part of a Git repository",
+        long,
+        value_hint = ValueHint::DirPath,
+        value_name = "PATH"
+    )]
+    root: Option<PathBuf>,
+}
+impl_figment_convert_basic!(ZkSelectorsArgs);
+
+/// The selector of a function, an error or an event of a contract.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct ZkSelector {
+    /// The kind of the item, `function`, `error` or `event`.
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    /// The signature of the item, like `transfer(address,uint256)`.
+    pub signature: String,
+    /// The 0x-prefixed selector of the item, 4 bytes for functions and errors and 32 bytes for
+    /// events.
+    pub selector: String,
+}
+
+impl Cmd for ZkSelectorsArgs {
+    type Output = ();
+
+    /// Prints the selectors of the contract.
+    ///
+    /// # Errors
+    /// An error is returned if the contract was not compiled with `zk-build`, or if its artifact
+    /// has no valid ABI.
+    fn run(self) -> eyre::Result<Self::Output> {
+        let config = self.try_load_config_emit_warnings()?;
+        let out_dir = config.project_paths().artifacts.join("zksync");
+        let artifact = read_artifact(&out_dir, &self.contract)?;
+        let abi: Abi = serde_json::from_value(artifact["abi"].clone())
+            .wrap_err(format!("The artifact of {} has no valid ABI", self.contract))?;
+
+        let selectors = selectors(&abi);
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&selectors)?);
+            return Ok(());
+        }
+
+        let mut table = Table::new();
+        table.load_preset(ASCII_MARKDOWN);
+        table.set_header(vec![
+            Cell::new("Type").add_attribute(Attribute::Bold).fg(Color::Blue),
+            Cell::new("Signature").add_attribute(Attribute::Bold).fg(Color::Blue),
+            Cell::new("Selector").add_attribute(Attribute::Bold).fg(Color::Blue),
+        ]);
+        for selector in selectors {
+            table.add_row(vec![selector.kind.to_string(), selector.signature, selector.selector]);
+        }
+        println!("{table}");
+        Ok(())
+    }
+}
+
+/// This function returns the selectors of the functions, errors and events of `abi`, in this
+/// order. It is shared with `zk-build --hashes`.
+pub(crate) fn selectors(abi: &Abi) -> Vec<ZkSelector> {
+    let functions = abi.functions().map(|function| ZkSelector {
+        kind: "function",
+        signature: signature(&function.name, &function.inputs),
+        selector: format!("0x{}", hex::encode(function.short_signature())),
+    });
+    let errors = abi.errors().map(|error| ZkSelector {
+        kind: "error",
+        signature: signature(&error.name, &error.inputs),
+        selector: format!("0x{}", hex::encode(&error.signature()[..4])),
+    });
+    let events = abi.events().map(|event| {
+        let types = event.inputs.iter().map(|input| input.kind.to_string()).collect::<Vec<_>>();
+        ZkSelector {
+            kind: "event",
+            signature: format!("{}({})", event.name, types.join(",")),
+            selector: format!("{:?}", event.signature()),
+        }
+    });
+    functions.chain(errors).chain(events).collect()
+}
+
+/// This function returns the signature of the function or error `name` with the `inputs`.
+fn signature(name: &str, inputs: &[Param]) -> String {
+    let types = inputs.iter().map(|input| input.kind.to_string()).collect::<Vec<_>>();
+    format!("{}({})", name, types.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selectors_of_functions_errors_and_events() {
+        let abi: Abi = serde_json::from_str(
+            r#"[
+                {
+                    "type": "function",
+                    "name": "transfer",
+                    "stateMutability": "nonpayable",
+                    "inputs": [
+                        { "name": "to", "type": "address" },
+                        { "name": "amount", "type": "uint256" }
+                    ],
+                    "outputs": [{ "name": "", "type": "bool" }]
+                },
+                { "type": "error", "name": "Unauthorized", "inputs": [] },
+                {
+                    "type": "event",
+                    "name": "Transfer",
+                    "anonymous": false,
+                    "inputs": [
+                        { "name": "from", "type": "address", "indexed": true },
+                        { "name": "to", "type": "address", "indexed": true },
+                        { "name": "value", "type": "uint256", "indexed": false }
+                    ]
+                }
+            ]"#,
+        )
+        .unwrap();
+
+        let selectors = selectors(&abi);
+        assert_eq!(
+            selectors,
+            vec![
+                ZkSelector {
+                    kind: "function",
+                    signature: "transfer(address,uint256)".to_string(),
+                    selector: "0xa9059cbb".to_string(),
+                },
+                ZkSelector {
+                    kind: "error",
+                    signature: "Unauthorized()".to_string(),
+                    selector: "0x82b42900".to_string(),
+                },
+                ZkSelector {
+                    kind: "event",
+                    signature: "Transfer(address,address,uint256)".to_string(),
+                    selector: "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"
+                        .to_string(),
+                },
+            ]
+        );
+    }
+}
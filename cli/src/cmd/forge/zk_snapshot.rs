@@ -0,0 +1,283 @@
+//! The `zk_snapshot` module provides the `forge zk-snapshot` command, which records the ergs used
+//! by the tests of a project on a zkSync node, like `forge snapshot` does for the EVM.
+//!
+//! The tests are run with `forge zk-test`, and the ergs used by every passing unit test are
+//! estimated by the node with `eth_estimateGas`, right after `setUp()`. The estimates are written
+//! to `.zk-gas-snapshot`, in the same format as `.gas-snapshot`. Fuzz and invariant tests are not
+//! recorded, as their calls are not sent as transactions.
+//!
+//! Once a snapshot exists, every run compares the tests with it and fails, printing the diff, if a
+//! test used more ergs than recorded, beyond `--tolerance`. The snapshot is only written when no
+//! test regressed, so a new baseline is recorded by removing it. `--diff` prints the diff without
+//! failing, and `--check` fails if the snapshot doesn't match the tests exactly.
+use super::{
+    snapshot::{fmt_change, fmt_pct_change, read_snapshot, within_tolerance, SnapshotEntry},
+    zk_test::{ZkSuiteResult, ZkTestArgs},
+};
+use clap::{builder::RangedU64ValueParser, Parser, ValueHint};
+use forge::result::TestKindReport;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::PathBuf,
+};
+
+/// CLI arguments for `forge zk-snapshot`.
+#[derive(Debug, Clone, Parser)]
+pub struct ZkSnapshotArgs {
+    /// All zk-test arguments are supported.
+    #[clap(flatten)]
+    pub test: ZkTestArgs,
+
+    /// Output a diff against a pre-existing snapshot, without failing.
+    ///
+    /// By default, the comparison is done with .zk-gas-snapshot.
+    #[clap(
+        conflicts_with = "check",
+        long,
+        value_hint = ValueHint::FilePath,
+        value_name = "SNAPSHOT_FILE",
+    )]
+    diff: Option<Option<PathBuf>>,
+
+    /// Compare against a pre-existing snapshot, failing if they do not match.
+    ///
+    /// By default, the comparison is done with .zk-gas-snapshot.
+    #[clap(
+        conflicts_with = "diff",
+        long,
+        value_hint = ValueHint::FilePath,
+        value_name = "SNAPSHOT_FILE",
+    )]
+    check: Option<Option<PathBuf>>,
+
+    #[clap(
+        help = "Output file for the snapshot.",
+        default_value = ".zk-gas-snapshot",
+        long,
+        value_name = "SNAPSHOT_FILE"
+    )]
+    snap: PathBuf,
+
+    #[clap(
+        help = "Tolerates gas deviations up to the specified percentage.",
+        long,
+        value_parser = RangedU64ValueParser::<u32>::new().range(0..100),
+        value_name = "SNAPSHOT_THRESHOLD"
+    )]
+    tolerance: Option<u32>,
+}
+
+impl ZkSnapshotArgs {
+    /// Runs the tests and records, diffs or checks the ergs they used.
+    ///
+    /// # Errors
+    /// An error is returned if a test fails, if the snapshot to compare with cannot be read, if a
+    /// test used more ergs than recorded in the snapshot beyond the tolerance or, with `--check`,
+    /// if the snapshot is out of date.
+    pub async fn run(mut self) -> eyre::Result<()> {
+        self.test.measure_gas = true;
+        let results = self.test.run().await?;
+        let tests = snapshot_entries(&results);
+
+        if let Some(path) = self.diff {
+            let snap = path.unwrap_or(self.snap);
+            let snaps = read_snapshot(&snap)?;
+            print_diff(&compare(&tests, &snaps));
+            return Ok(());
+        }
+
+        if let Some(path) = self.check {
+            let snap = path.unwrap_or(self.snap);
+            let snaps = read_snapshot(&snap)?;
+            if !check(&tests, &snaps, self.tolerance) {
+                eyre::bail!("The snapshot {} is out of date", snap.display());
+            }
+            return Ok(());
+        }
+
+        if self.snap.exists() {
+            let snaps = read_snapshot(&self.snap)?;
+            let increased = regressions(&compare(&tests, &snaps), self.tolerance);
+            if !increased.is_empty() {
+                print_diff(&increased);
+                eyre::bail!(
+                    "{} tests used more ergs than in {}, remove it to record a new baseline",
+                    increased.len(),
+                    self.snap.display()
+                );
+            }
+        }
+
+        let mut reports = tests
+            .iter()
+            .map(|test| format!("{}:{} {}", test.contract_name, test.signature, test.gas_used))
+            .collect::<Vec<_>>();
+        reports.sort();
+        fs::write(&self.snap, reports.join("\n"))?;
+        Ok(())
+    }
+}
+
+/// A test of the current run, with the ergs it used in the snapshot.
+type ZkSnapshotDiff<'a> = (&'a SnapshotEntry, u64);
+
+/// This function returns the snapshot entries of the unit tests of `results` that passed.
+fn snapshot_entries(results: &[ZkSuiteResult]) -> Vec<SnapshotEntry> {
+    results
+        .iter()
+        .flat_map(|suite| {
+            let contract_name = suite.id.rsplit(':').next().unwrap_or(&suite.id).to_string();
+            suite.results.iter().filter_map(move |result| {
+                result.gas_used.map(|gas| SnapshotEntry {
+                    contract_name: contract_name.clone(),
+                    signature: result.signature.clone(),
+                    gas_used: TestKindReport::Standard { gas },
+                })
+            })
+        })
+        .collect()
+}
+
+/// This function compares `tests` with the entries of a snapshot.
+///
+/// # Returns
+/// The tests which are in the snapshot, with the ergs they used in it.
+fn compare<'a>(tests: &'a [SnapshotEntry], snaps: &[SnapshotEntry]) -> Vec<ZkSnapshotDiff<'a>> {
+    let snaps = snaps
+        .iter()
+        .map(|snap| ((&snap.contract_name, &snap.signature), snap.gas_used.gas()))
+        .collect::<HashMap<_, _>>();
+    tests
+        .iter()
+        .filter_map(|test| {
+            snaps.get(&(&test.contract_name, &test.signature)).map(|target| (test, *target))
+        })
+        .collect()
+}
+
+/// This function returns the tests of `diffs` which used more ergs than in the snapshot, beyond
+/// `tolerance` percent.
+fn regressions<'a>(
+    diffs: &[ZkSnapshotDiff<'a>],
+    tolerance: Option<u32>,
+) -> Vec<ZkSnapshotDiff<'a>> {
+    diffs
+        .iter()
+        .filter(|(test, target)| {
+            let source = test.gas_used.gas();
+            source > *target && !within_tolerance(source, *target, tolerance)
+        })
+        .copied()
+        .collect()
+}
+
+/// This function checks that every test is in the snapshot, with ergs within `tolerance` percent
+/// of the recorded ones, and that every entry of the snapshot is still a test, printing every
+/// mismatch.
+///
+/// # Returns
+/// `true` if the snapshot matches the tests.
+fn check(tests: &[SnapshotEntry], snaps: &[SnapshotEntry], tolerance: Option<u32>) -> bool {
+    let targets = snaps
+        .iter()
+        .map(|snap| ((&snap.contract_name, &snap.signature), snap.gas_used.gas()))
+        .collect::<HashMap<_, _>>();
+    let mut matches = true;
+    for test in tests {
+        match targets.get(&(&test.contract_name, &test.signature)) {
+            Some(target) if within_tolerance(test.gas_used.gas(), *target, tolerance) => {}
+            Some(target) => {
+                eprintln!(
+                    "Diff in \"{}::{}\": consumed \"{}\" ergs, expected \"{}\" ergs",
+                    test.contract_name,
+                    test.signature,
+                    test.gas_used.gas(),
+                    target
+                );
+                matches = false;
+            }
+            None => {
+                eprintln!(
+                    "No matching snapshot entry found for \"{}::{}\" in snapshot file",
+                    test.contract_name, test.signature
+                );
+                matches = false;
+            }
+        }
+    }
+
+    let tests =
+        tests.iter().map(|test| (&test.contract_name, &test.signature)).collect::<HashSet<_>>();
+    for snap in snaps {
+        if !tests.contains(&(&snap.contract_name, &snap.signature)) {
+            eprintln!(
+                "No matching test found for snapshot entry \"{}::{}\"",
+                snap.contract_name, snap.signature
+            );
+            matches = false;
+        }
+    }
+    matches
+}
+
+/// This function prints the change of the ergs used by every test of `diffs`, in the same format
+/// as `forge snapshot --diff`.
+fn print_diff(diffs: &[ZkSnapshotDiff<'_>]) {
+    let mut overall_change = 0i128;
+    let mut overall_pct_change = 0f64;
+    for (test, target) in diffs {
+        let change = test.gas_used.gas() as i128 - *target as i128;
+        let pct_change = change as f64 / *target as f64;
+        overall_change += change;
+        overall_pct_change += pct_change;
+        println!(
+            "{}:{} (ergs: {} ({}))",
+            test.contract_name,
+            test.signature,
+            fmt_change(change),
+            fmt_pct_change(pct_change)
+        );
+    }
+    println!(
+        "Overall ergs change: {} ({})",
+        fmt_change(overall_change),
+        fmt_pct_change(overall_pct_change)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(signature: &str, gas: u64) -> SnapshotEntry {
+        SnapshotEntry {
+            contract_name: "CounterTest".to_string(),
+            signature: signature.to_string(),
+            gas_used: TestKindReport::Standard { gas },
+        }
+    }
+
+    #[test]
+    fn only_increases_beyond_the_tolerance_are_regressions() {
+        let tests = [entry("testA()", 106), entry("testB()", 104), entry("testC()", 50)];
+        let snaps = [entry("testA()", 100), entry("testB()", 100), entry("testC()", 100)];
+
+        let diffs = compare(&tests, &snaps);
+        let increased = regressions(&diffs, Some(5));
+        assert_eq!(increased.len(), 1);
+        assert_eq!(increased[0].0.signature, "testA()");
+        assert_eq!(regressions(&diffs, None).len(), 2);
+        assert!(!check(&tests, &snaps, Some(5)));
+        assert!(check(&tests[1..2], &snaps[1..2], Some(5)));
+    }
+
+    #[test]
+    fn snapshot_entries_without_a_test_fail_the_check() {
+        let tests = [entry("testB()", 100)];
+        let snaps = [entry("testA()", 100), entry("testB()", 100)];
+
+        assert!(!check(&tests, &snaps, None));
+        assert!(check(&tests, &snaps[1..], None));
+    }
+}
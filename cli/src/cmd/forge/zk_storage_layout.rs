@@ -0,0 +1,61 @@
+//! The `zk_storage_layout` module provides the `forge zk-storage-layout` command, which prints the
+//! storage layout of a contract compiled with `zk-build --storage-layout`.
+//!
+//! The layout is read from the Foundry artifact of the contract in the `zksync` directory of the
+//! `out` path, and printed as a table of the name, type, slot, offset and size of every storage
+//! variable, like `forge inspect <contract> storageLayout --pretty` does for the EVM compiler.
+use super::{inspect::print_storage_layout, zk_artifacts::read_artifact};
+use crate::cmd::{Cmd, LoadConfig};
+use clap::{Parser, ValueHint};
+use ethers::solc::artifacts::StorageLayout;
+use eyre::Context;
+use foundry_config::impl_figment_convert_basic;
+use std::path::PathBuf;
+
+/// CLI arguments for `forge zk-storage-layout`.
+#[derive(Debug, Clone, Parser)]
+pub struct ZkStorageLayoutArgs {
+    /// The name of the contract, optionally in the form `<path>:<contractname>`.
+    #[clap(value_name = "CONTRACT")]
+    pub contract: String,
+
+    /// Print the storage layout as JSON instead of a table.
+    #[clap(long)]
+    pub json: bool,
+
+    #[clap(
+        help = "The project's root path. By default, this is the root directory of the current Git repository or the current working directory if it is not part of a Git repository",
+        long,
+        value_hint = ValueHint::DirPath,
+        value_name = "PATH"
+    )]
+    root: Option<PathBuf>,
+}
+impl_figment_convert_basic!(ZkStorageLayoutArgs);
+
+impl Cmd for ZkStorageLayoutArgs {
+    type Output = ();
+
+    /// Prints the storage layout of the contract.
+    ///
+    /// # Errors
+    /// An error is returned if the contract was not compiled with `zk-build`, or if it was compiled
+    /// without `--storage-layout`.
+    fn run(self) -> eyre::Result<Self::Output> {
+        let config = self.try_load_config_emit_warnings()?;
+        let out_dir = config.project_paths().artifacts.join("zksync");
+        let artifact = read_artifact(&out_dir, &self.contract)?;
+
+        let storage_layout = &artifact["storageLayout"];
+        if storage_layout.is_null() {
+            eyre::bail!(
+                "The artifact of {} has no storage layout, build it with zk-build --storage-layout",
+                self.contract
+            );
+        }
+        let storage_layout: StorageLayout = serde_json::from_value(storage_layout.clone())
+            .wrap_err(format!("Unable to parse the storage layout of {}", self.contract))?;
+
+        print_storage_layout(&Some(storage_layout), !self.json)
+    }
+}
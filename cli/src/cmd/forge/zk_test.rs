@@ -0,0 +1,1552 @@
+//! The `zk_test` module provides the `forge zk-test` command, which runs the tests of a project
+//! against a zkSync node.
+//!
+//! Test contracts are compiled with the same `ZkSolc` pipeline used by `forge zk-build`, with the
+//! project's test directory as the root of the sources. Every test contract is then executed on the
+//! zkSync VM of the node behind `--rpc-url`:
+//!
+//! 1. A fresh instance of the test contract is deployed for every test function.
+//! 2. If the contract defines `setUp()`, it is sent as a transaction before the test is run.
+//! 3. The test function is executed with `eth_call`. If the call reverts, the revert data returned
+//!    by the node is decoded into a human readable reason.
+//! 4. If the call succeeds and the contract exposes `failed()` (as `DSTest` based contracts do), the
+//!    test is sent as a transaction and `failed()` is queried afterwards, so that failed assertions
+//!    which don't revert are reported as well.
+//!
+//! Functions whose names start with `testFail` are expected to fail, so their outcome is inverted.
+//!
+//! Test functions with parameters are fuzzed: they are called with `eth_call` for every run of the
+//! `[fuzz]` section of the config, with inputs generated by the strategies of `forge test`. The
+//! inputs of the first failing run are shrunk and reported as a counterexample.
+//!
+//! With `--invariant`, the `invariant*` functions are run instead. Every run deploys the test
+//! contract, runs `setUp()` and sends a random sequence of calls to the contracts it deployed as
+//! transactions, checking the invariant after each call. The sequence that breaks an invariant is
+//! shrunk and printed in full.
+//!
+//! With `--gas-report`, every passing unit test is traced with `debug_traceCall` and the ergs used
+//! by its calls to the contracts deployed by the test contract are printed once all the tests ran.
+//!
+//! This module consists of the following key structures:
+//!
+//! * `ZkTestArgs`: The CLI arguments of `forge zk-test`, which contain the test filters, the
+//!   verbosity, the `ZkBuildArgs` used to compile the test contracts and the `EthereumOpts` used to
+//!   connect to the zkSync node.
+//!
+//! * `ZkSuiteResult`: The outcome of every test function of a single test contract.
+//!
+//! * `ZkTestResult`: The outcome of a single test function.
+//!
+//! * `ZkCounterExample`: The inputs a fuzz test failed with.
+//!
+//! * `ZkInvariantStep`: A call of the sequence an invariant test failed with.
+use super::{
+    zk_build::ZkBuildArgs,
+    zk_gas_report::{ZkCallGas, ZkGasReport},
+    zksolc::{get_bytecode, get_factory_dependencies, read_contract_output, ZkCompilationOutput},
+};
+use crate::{
+    cmd::{
+        cast::zk_utils::zk_utils::{get_chain, get_private_key, get_rpc_url},
+        LoadConfig,
+    },
+    opts::EthereumOpts,
+};
+use clap::{ArgAction, Parser, ValueHint};
+use ethers::{
+    abi::{Abi, Function, StateMutability, Token},
+    types::U256,
+    utils::keccak256,
+};
+use eyre::Context;
+use forge::{decode::decode_revert, fuzz::strategies::fuzz_calldata, TestOptions};
+use foundry_common::{abi::format_tokens, TestFunctionExt};
+use proptest::{
+    strategy::{Strategy, ValueTree},
+    test_runner::TestRunner,
+};
+use regex::Regex;
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Write},
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+use yansi::Paint;
+use zksync_web3_rs::{
+    providers::{Http, Middleware, Provider, RpcError},
+    signers::{LocalWallet, Signer},
+    types::{Address, TransactionReceipt, TransactionRequest},
+    zks_provider::ZKSProvider,
+    zks_utils::CONTRACT_DEPLOYER_ADDR,
+    ZKSWallet,
+};
+
+/// The name of the function that is called before every test.
+const SETUP_FUNCTION: &str = "setUp";
+
+/// The name of the `DSTest` function that reports whether an assertion failed.
+const FAILED_FUNCTION: &str = "failed";
+
+/// The maximum number of calls made to shrink the inputs of a failing fuzz test, or the number
+/// of sequences replayed to shrink the calls that broke an invariant.
+const MAX_SHRINK_ITERATIONS: usize = 128;
+
+/// The event the `ContractDeployer` system contract emits for every deployed contract.
+const CONTRACT_DEPLOYED_EVENT: &str = "ContractDeployed(address,bytes32,address)";
+
+/// CLI arguments for `forge zk-test`.
+#[derive(Debug, Clone, Parser)]
+#[clap(next_help_heading = "ZkTest options", about = None)]
+pub struct ZkTestArgs {
+    /// Only run test functions matching the specified regex pattern.
+    #[clap(
+        long = "match-test",
+        visible_alias = "mt",
+        help_heading = "Test filtering",
+        value_name = "REGEX",
+        value_parser = parse_regex
+    )]
+    pub test_pattern: Option<Regex>,
+
+    /// Only run test functions that do not match the specified regex pattern.
+    #[clap(
+        long = "no-match-test",
+        visible_alias = "nmt",
+        help_heading = "Test filtering",
+        value_name = "REGEX",
+        value_parser = parse_regex
+    )]
+    pub test_pattern_inverse: Option<Regex>,
+
+    /// Only run tests in contracts matching the specified regex pattern.
+    #[clap(
+        long = "match-contract",
+        visible_alias = "mc",
+        help_heading = "Test filtering",
+        value_name = "REGEX",
+        value_parser = parse_regex
+    )]
+    pub contract_pattern: Option<Regex>,
+
+    /// Only run tests in contracts that do not match the specified regex pattern.
+    #[clap(
+        long = "no-match-contract",
+        visible_alias = "nmc",
+        help_heading = "Test filtering",
+        value_name = "REGEX",
+        value_parser = parse_regex
+    )]
+    pub contract_pattern_inverse: Option<Regex>,
+
+    /// Verbosity of the test output.
+    ///
+    /// Pass multiple times to increase the verbosity (e.g. -v, -vv, -vvv).
+    ///
+    /// Verbosity levels:
+    /// - 2: Print the address of the deployed test contracts
+    /// - 3: Print the raw revert data of failing tests
+    #[clap(long, short, verbatim_doc_comment, action = ArgAction::Count)]
+    pub verbosity: u8,
+
+    /// Run the invariant tests instead of the unit and fuzz tests.
+    ///
+    /// The `runs`, `depth` and `fail_on_revert` settings are read from the `[invariant]` section
+    /// of the config.
+    #[clap(long, help_heading = "Test filtering")]
+    pub invariant: bool,
+
+    /// Print a gas report of the calls of the unit tests to the contracts deployed by the test
+    /// contracts.
+    ///
+    /// The gas is reported in ergs, and the zkSync node must support `debug_traceCall`.
+    #[clap(long, env = "FORGE_GAS_REPORT")]
+    pub gas_report: bool,
+
+    /// Stop running tests after the first failure.
+    ///
+    /// The tests are run one at a time, so no other test is in flight when the run stops.
+    #[clap(long, short = 'x')]
+    pub fail_fast: bool,
+
+    /// The maximum time a single test function may run, in milliseconds.
+    ///
+    /// A test running longer, for example because it loops forever, fails with "Test timed out
+    /// after {ms}ms". The timeout covers the deployment of the test contract and the calls of the
+    /// test to the node, not the compilation.
+    #[clap(long, value_name = "MS", default_value_t = 30_000)]
+    pub timeout: u64,
+
+    /// Write the results of the tests to the given path as a JUnit XML report.
+    #[clap(long, value_hint = ValueHint::FilePath, value_name = "PATH")]
+    pub junit_xml: Option<PathBuf>,
+
+    /// Whether to measure the ergs used by every passing unit test, as `forge zk-snapshot` does.
+    #[clap(skip)]
+    pub measure_gas: bool,
+
+    /// The arguments used to compile the test contracts with zksolc.
+    #[clap(flatten)]
+    pub build: ZkBuildArgs,
+
+    /// Ethereum-specific options, such as the zkSync node and the wallet that deploys the tests.
+    #[clap(flatten)]
+    pub eth: EthereumOpts,
+}
+
+impl ZkTestArgs {
+    /// Executes the tests of the project against the zkSync node.
+    ///
+    /// # Procedure
+    /// 1. Loads the configuration and sets up the project, using the test directory as sources and
+    ///    "zkout" as the artifacts directory.
+    /// 2. Compiles the test contracts with zksolc.
+    /// 3. Collects every test function of the compiled contracts that matches the filters.
+    /// 4. Runs every test function on the zkSync node and prints its outcome.
+    ///
+    /// # Returns
+    /// The results of every test contract if all the tests passed, or an error if any test failed
+    /// or if the tests could not be compiled or executed.
+    pub async fn run(self) -> eyre::Result<Vec<ZkSuiteResult>> {
+        let private_key = get_private_key(&self.eth.wallet.private_key)?;
+        let rpc_url = get_rpc_url(&self.eth.rpc_url)?;
+        let chain = get_chain(self.eth.chain)?;
+
+        let config = self.build.try_load_config_emit_warnings()?;
+        let mut project = config.project()?;
+        let cache_dir = project.paths.artifacts.join("zksync").join("cache");
+        project.paths.artifacts = project.paths.root.join("zkout");
+        project.paths.sources = project.paths.tests.clone();
+
+        let compiler_path = self.build.zksolc_path(&config)?;
+
+        println!("Compiling test contracts...");
+        let artifacts = project.paths.artifacts.clone();
+        let output =
+            self.build.compile_smart_contracts(&config, compiler_path, project, cache_dir)?;
+        if let Some(failed) = output.failed().next() {
+            eyre::bail!(
+                "Failed to compile {}: {}",
+                failed.source.display(),
+                failed.error.as_ref().map(ToString::to_string).unwrap_or_default()
+            );
+        }
+
+        let suites = self.collect_test_suites(&artifacts, &output)?;
+        if suites.is_empty() {
+            println!(
+                "\nNo tests found in project! Forge looks for functions that starts with `test`."
+            );
+            return Ok(vec![]);
+        }
+
+        let provider = Provider::try_from(rpc_url)?;
+        let wallet = LocalWallet::from_str(&format!("{private_key:?}"))?.with_chain_id(chain);
+        let test_options = TestOptions { fuzz: config.fuzz, invariant: config.invariant };
+        let runner = ZkTestRunner {
+            provider,
+            wallet,
+            verbosity: self.verbosity,
+            test_options,
+            gas_report: self.gas_report,
+            measure_gas: self.measure_gas,
+            fail_fast: self.fail_fast,
+            timeout: Duration::from_millis(self.timeout),
+        };
+
+        let mut results = Vec::new();
+        for suite in &suites {
+            let result = runner.run_suite(suite).await;
+            println!("{}", result.summary());
+            let failure = result.failures().next().map(|test| test.signature.clone());
+            results.push(result);
+            if let (true, Some(signature)) = (self.fail_fast, failure) {
+                println!("\nStopped after the first failure: {}::{}", suite.id, signature);
+                break;
+            }
+        }
+
+        if self.gas_report {
+            let mut report =
+                ZkGasReport::new(config.gas_reports.clone(), config.gas_reports_ignore.clone());
+            for result in results.iter().flat_map(|suite| &suite.results) {
+                report.analyze(&result.gas);
+            }
+            println!("{}", report.finalize());
+        }
+
+        if let Some(path) = &self.junit_xml {
+            fs::write(path, junit_xml(&results))
+                .wrap_err(format!("Failed to write the JUnit XML report to {}", path.display()))?;
+        }
+
+        let failed: usize = results.iter().map(|result| result.failures().count()).sum();
+        if failed > 0 {
+            eyre::bail!("{} tests failed", failed);
+        }
+
+        Ok(results)
+    }
+
+    /// Returns whether the contract named `name` matches `--match-contract` and
+    /// `--no-match-contract`.
+    fn matches_contract(&self, name: &str) -> bool {
+        self.contract_pattern.as_ref().map_or(true, |re| re.is_match(name)) &&
+            !self.contract_pattern_inverse.as_ref().map_or(false, |re| re.is_match(name))
+    }
+
+    /// Returns whether the test function named `name` matches `--match-test` and
+    /// `--no-match-test`.
+    fn matches_test(&self, name: &str) -> bool {
+        self.test_pattern.as_ref().map_or(true, |re| re.is_match(name)) &&
+            !self.test_pattern_inverse.as_ref().map_or(false, |re| re.is_match(name))
+    }
+
+    /// This function collects the test contracts from the artifacts of the compiled sources.
+    ///
+    /// Only the contracts defined in the compiled sources themselves are considered, so that test
+    /// contracts imported by other test files are not run more than once.
+    ///
+    /// # Returns
+    /// A `Vec` with a `ZkTestSuite` for every contract matching the contract filters, containing
+    /// the test functions matching the test filters, or the invariant functions with
+    /// `--invariant`.
+    fn collect_test_suites(
+        &self,
+        artifacts: &Path,
+        output: &ZkCompilationOutput,
+    ) -> eyre::Result<Vec<ZkTestSuite>> {
+        let mut suites = Vec::new();
+
+        for outcome in &output.sources {
+            let output = read_contract_output(artifacts, &outcome.source)
+                .map_err(|err| eyre::eyre!("{}", err))?;
+            let files = if let Some(files) = output.as_object() { files } else { continue };
+
+            for (file, contracts) in files {
+                if !outcome.source.ends_with(file) {
+                    continue;
+                }
+                let contracts =
+                    if let Some(contracts) = contracts.as_object() { contracts } else { continue };
+
+                for (name, contract) in contracts {
+                    if !self.matches_contract(name) {
+                        continue;
+                    }
+
+                    let abi: Abi = serde_json::from_value(contract["abi"].clone())
+                        .wrap_err(format!("Failed to parse the ABI of {file}:{name}"))?;
+                    let tests: Vec<Function> = abi
+                        .functions()
+                        .filter(|func| {
+                            if self.invariant {
+                                func.is_invariant_test() && func.inputs.is_empty()
+                            } else {
+                                func.is_test()
+                            }
+                        })
+                        .filter(|func| self.matches_test(&func.name))
+                        .cloned()
+                        .collect();
+                    if tests.is_empty() {
+                        continue;
+                    }
+
+                    let bytecode = get_bytecode(contract).map_err(|err| {
+                        eyre::eyre!("Failed to find the bytecode of {file}:{name}: {err}")
+                    })?;
+                    let factory_deps =
+                        get_factory_dependencies(&output, contract).map_err(|err| {
+                            eyre::eyre!("Failed to find the factory deps of {file}:{name}: {err}")
+                        })?;
+
+                    suites.push(ZkTestSuite {
+                        id: format!("{file}:{name}"),
+                        abi_json: contract["abi"].clone(),
+                        abi,
+                        bytecode,
+                        factory_deps,
+                        tests,
+                        contracts: known_contracts(&output),
+                    });
+                }
+            }
+        }
+
+        Ok(suites)
+    }
+}
+
+/// A test contract compiled with zksolc, together with the test functions to run.
+struct ZkTestSuite {
+    /// The contract identifier in the form `<path>:<contractname>`.
+    id: String,
+    /// The raw JSON ABI of the contract, as used for the deployment.
+    abi_json: Value,
+    /// The parsed ABI of the contract.
+    abi: Abi,
+    /// The zkEVM bytecode of the contract.
+    bytecode: Vec<u8>,
+    /// The bytecode of the contracts the test contract deploys.
+    factory_deps: Vec<Vec<u8>>,
+    /// The test functions to run.
+    tests: Vec<Function>,
+    /// The contracts compiled with the test contract, keyed by their bytecode hash, which are the
+    /// targets of invariant tests if the test contract deploys them.
+    contracts: BTreeMap<String, ZkKnownContract>,
+}
+
+/// A contract compiled with zksolc, identified by the hash of its bytecode.
+struct ZkKnownContract {
+    /// The contract identifier in the form `<path>:<contractname>`.
+    id: String,
+    /// The parsed ABI of the contract.
+    abi: Abi,
+}
+
+/// A contract deployed by a test contract, whose functions an invariant test calls.
+struct ZkInvariantTarget {
+    /// The address of the contract.
+    address: Address,
+    /// The name of the contract.
+    name: String,
+    /// The functions of the contract which are neither `view` nor `pure`.
+    functions: Vec<Function>,
+}
+
+/// A call of an invariant test run, to the function `func` of the target at index `target`.
+///
+/// Targets are referred to by their index, as their addresses change with every deployment of the
+/// test contract.
+#[derive(Clone)]
+struct ZkInvariantCall {
+    target: usize,
+    func: Function,
+    calldata: Vec<u8>,
+}
+
+/// The outcome of every test function of a single test contract.
+#[derive(Debug, Clone)]
+pub struct ZkSuiteResult {
+    /// The contract identifier in the form `<path>:<contractname>`.
+    pub id: String,
+    /// The outcome of every test function of the contract.
+    pub results: Vec<ZkTestResult>,
+    /// The total time it took to run the test functions of the contract.
+    pub duration: Duration,
+}
+
+impl ZkSuiteResult {
+    /// Returns an iterator over all the succeeding tests.
+    pub fn successes(&self) -> impl Iterator<Item = &ZkTestResult> {
+        self.results.iter().filter(|result| result.success)
+    }
+
+    /// Returns an iterator over all the failing tests.
+    pub fn failures(&self) -> impl Iterator<Item = &ZkTestResult> {
+        self.results.iter().filter(|result| !result.success)
+    }
+
+    /// Returns the summary line of the test contract, in the same format as `forge test`.
+    pub fn summary(&self) -> String {
+        let failed = self.failures().count();
+        let result = if failed == 0 { Paint::green("ok") } else { Paint::red("FAILED") };
+        format!(
+            "Test result: {}. {} passed; {} failed; finished in {:.2?}",
+            result,
+            self.successes().count(),
+            failed,
+            self.duration
+        )
+    }
+}
+
+/// The outcome of a single test function.
+#[derive(Debug, Clone)]
+pub struct ZkTestResult {
+    /// The signature of the test function.
+    pub signature: String,
+    /// Whether the test passed.
+    pub success: bool,
+    /// The decoded reason of the failure, if the test failed with one.
+    pub reason: Option<String>,
+    /// The time it took to run the test, including the deployment of the test contract.
+    pub duration: Duration,
+    /// The number of runs of a fuzz test, `None` for tests without parameters.
+    pub runs: Option<usize>,
+    /// The shrunk inputs of a failed fuzz test.
+    pub counterexample: Option<ZkCounterExample>,
+    /// The shrunk sequence of calls that broke an invariant.
+    pub sequence: Vec<ZkInvariantStep>,
+    /// The ergs used by the calls of the test to the contracts deployed by the test contract, with
+    /// `--gas-report`.
+    pub gas: Vec<ZkCallGas>,
+    /// The ergs used by a passing unit test, as estimated by the node, for `forge zk-snapshot`.
+    pub gas_used: Option<u64>,
+}
+
+impl ZkTestResult {
+    /// Returns the result of the test function `signature` which was aborted after running for
+    /// `timeout`.
+    pub fn timed_out(signature: String, timeout: Duration) -> Self {
+        Self {
+            signature,
+            success: false,
+            reason: Some(format!("Test timed out after {}ms", timeout.as_millis())),
+            duration: timeout,
+            runs: None,
+            counterexample: None,
+            sequence: vec![],
+            gas: vec![],
+            gas_used: None,
+        }
+    }
+
+    /// Returns the result of the test function `signature` which could not be run because of
+    /// `error` after `duration`, for example because the test contract could not be deployed or
+    /// because `setUp()` reverted.
+    pub fn errored(signature: String, error: &eyre::Report, duration: Duration) -> Self {
+        Self {
+            signature,
+            success: false,
+            reason: Some(error.to_string()),
+            duration,
+            runs: None,
+            counterexample: None,
+            sequence: vec![],
+            gas: vec![],
+            gas_used: None,
+        }
+    }
+}
+
+/// The inputs a fuzz test failed with, after shrinking.
+#[derive(Debug, Clone)]
+pub struct ZkCounterExample {
+    /// The calldata of the failing call.
+    pub calldata: Vec<u8>,
+    /// The decoded arguments of the failing call.
+    pub args: Vec<Token>,
+}
+
+impl fmt::Display for ZkCounterExample {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let args = format_tokens(&self.args).collect::<Vec<_>>().join(", ");
+        write!(f, "calldata=0x{}, args=[{args}]", hex::encode(&self.calldata))
+    }
+}
+
+/// A call of the sequence that broke an invariant.
+#[derive(Debug, Clone)]
+pub struct ZkInvariantStep {
+    /// The name of the called contract.
+    pub contract: String,
+    /// The signature of the called function.
+    pub signature: String,
+    /// The calldata of the call.
+    pub calldata: Vec<u8>,
+    /// The decoded arguments of the call.
+    pub args: Vec<Token>,
+    /// The decoded return values of the call, empty if it reverted.
+    pub returned: Vec<Token>,
+}
+
+impl fmt::Display for ZkInvariantStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let args = format_tokens(&self.args).collect::<Vec<_>>().join(", ");
+        let returned = format_tokens(&self.returned).collect::<Vec<_>>().join(", ");
+        write!(
+            f,
+            "{}.{} selector=0x{} args=[{args}] returned=[{returned}]",
+            self.contract,
+            self.signature,
+            hex::encode(self.calldata.get(..4).unwrap_or_default())
+        )
+    }
+}
+
+/// Runs test contracts on the zkSync node behind `provider`, using `wallet` to deploy them.
+struct ZkTestRunner {
+    provider: Provider<Http>,
+    wallet: LocalWallet,
+    verbosity: u8,
+    test_options: TestOptions,
+    gas_report: bool,
+    measure_gas: bool,
+    fail_fast: bool,
+    timeout: Duration,
+}
+
+/// The result of executing a function of a test contract with `eth_call`.
+enum CallOutcome {
+    /// The call succeeded and returned the given data.
+    Success(Vec<u8>),
+    /// The call reverted with the given decoded reason and raw revert data.
+    Revert { reason: String, data: Vec<u8> },
+}
+
+impl ZkTestRunner {
+    /// This function runs every test function of `suite`, printing the outcome of each one.
+    ///
+    /// A test function which doesn't complete within `--timeout` is aborted and fails, see
+    /// `ZkTestResult::timed_out`. A test function which cannot be run, for example because
+    /// `setUp()` reverts, fails with the error, see `ZkTestResult::errored`. With `--fail-fast`, the
+    /// remaining test functions are skipped after the first failure, including a timeout.
+    async fn run_suite(&self, suite: &ZkTestSuite) -> ZkSuiteResult {
+        println!("\nRunning {} tests for {}", suite.tests.len(), suite.id);
+
+        let start = Instant::now();
+        let mut results = Vec::with_capacity(suite.tests.len());
+        for func in &suite.tests {
+            let test_start = Instant::now();
+            let execution = async {
+                if func.is_invariant_test() {
+                    self.run_invariant_test(suite, func).await
+                } else {
+                    self.run_test(suite, func).await
+                }
+            };
+            let result = match tokio::time::timeout(self.timeout, execution).await {
+                Ok(Ok(result)) => result,
+                Ok(Err(err)) => ZkTestResult::errored(func.signature(), &err, test_start.elapsed()),
+                Err(_) => ZkTestResult::timed_out(func.signature(), self.timeout),
+            };
+            print_test_result(&result);
+            let failed = !result.success;
+            results.push(result);
+            if self.fail_fast && failed {
+                break;
+            }
+        }
+
+        ZkSuiteResult { id: suite.id.clone(), results, duration: start.elapsed() }
+    }
+
+    /// This function runs a single test function on a freshly deployed instance of the test
+    /// contract.
+    ///
+    /// Test functions with parameters are fuzzed with `run_fuzz_test`.
+    ///
+    /// # Errors
+    /// An error is returned if the test contract cannot be deployed or if `setUp()` reverts.
+    /// Failures of the test function itself are reported in the returned `ZkTestResult`.
+    async fn run_test(&self, suite: &ZkTestSuite, func: &Function) -> eyre::Result<ZkTestResult> {
+        let start = Instant::now();
+        let signature = func.signature();
+        let expect_fail = func.name.is_test_fail();
+
+        let (address, deployed) = self.setup(suite).await?;
+        if self.verbosity >= 2 {
+            println!("Deployed {} to {:?}", suite.id, address);
+        }
+
+        if !func.inputs.is_empty() {
+            return self.run_fuzz_test(suite, func, address, start).await;
+        }
+
+        // the test is traced before `has_failed` sends it, which changes the state of the contracts
+        let outcome = self.call(address, func, &suite.abi).await?;
+        let gas = match outcome {
+            CallOutcome::Success(_) if self.gas_report => {
+                self.trace_gas(address, func, &deployed).await?
+            }
+            _ => vec![],
+        };
+        let gas_used = match outcome {
+            CallOutcome::Success(_) if self.measure_gas => {
+                Some(self.estimate_gas(address, func).await?)
+            }
+            _ => None,
+        };
+
+        let reason = match outcome {
+            CallOutcome::Revert { reason, data } => {
+                if self.verbosity >= 3 {
+                    println!("Revert data of {signature}: 0x{}", hex::encode(data));
+                }
+                Some(reason)
+            }
+            CallOutcome::Success(_) if self.has_failed(suite, address, &signature).await? => {
+                Some("Assertion failed.".to_string())
+            }
+            CallOutcome::Success(_) => None,
+        };
+
+        let (success, reason) = match (reason, expect_fail) {
+            (None, false) => (true, None),
+            (Some(_), true) => (true, None),
+            (Some(reason), false) => (false, Some(reason)),
+            (None, true) => (false, None),
+        };
+
+        Ok(ZkTestResult {
+            signature,
+            success,
+            reason,
+            duration: start.elapsed(),
+            runs: None,
+            counterexample: None,
+            sequence: vec![],
+            gas,
+            gas_used: if success { gas_used } else { None },
+        })
+    }
+
+    /// This function fuzzes a test function with parameters on the deployed test contract.
+    ///
+    /// The function is called with `eth_call` for every run of the `[fuzz]` config, with inputs
+    /// generated by the strategies of `forge test` and the `seed` of the config, if any. A run
+    /// fails if the call reverts, or if it doesn't revert for a `testFail` function. As `eth_call`
+    /// doesn't persist any state, the runs don't affect each other, but failed `DSTest` assertions
+    /// which don't revert are not detected. The inputs of the first failing run are shrunk with
+    /// `shrink`.
+    ///
+    /// # Errors
+    /// An error is returned if the inputs cannot be generated, for example if more than
+    /// `max_test_rejects` of them are rejected, or if the node cannot be reached.
+    async fn run_fuzz_test(
+        &self,
+        suite: &ZkTestSuite,
+        func: &Function,
+        address: Address,
+        start: Instant,
+    ) -> eyre::Result<ZkTestResult> {
+        let signature = func.signature();
+        let expect_fail = func.name.is_test_fail();
+        let runs = self.test_options.fuzz.runs as usize;
+        let strategy = fuzz_calldata(func.clone());
+        let mut fuzzer = self.test_options.fuzzer();
+
+        for _ in 0..runs {
+            let mut tree = strategy
+                .new_tree(&mut fuzzer)
+                .map_err(|err| eyre::eyre!("Failed to generate inputs for {signature}: {err}"))?;
+            let calldata = tree.current().to_vec();
+            let reason = match self.run_fuzz_case(address, &calldata, suite, expect_fail).await? {
+                Some(reason) => reason,
+                None => continue,
+            };
+
+            let (calldata, reason) =
+                self.shrink(&mut *tree, calldata, reason, address, suite, expect_fail).await?;
+            let args = func.decode_input(&calldata[4..]).unwrap_or_default();
+            return Ok(ZkTestResult {
+                signature,
+                success: false,
+                reason: Some(reason),
+                duration: start.elapsed(),
+                runs: Some(runs),
+                counterexample: Some(ZkCounterExample { calldata, args }),
+                sequence: vec![],
+                gas: vec![],
+                gas_used: None,
+            });
+        }
+
+        Ok(ZkTestResult {
+            signature,
+            success: true,
+            reason: None,
+            duration: start.elapsed(),
+            runs: Some(runs),
+            counterexample: None,
+            sequence: vec![],
+            gas: vec![],
+            gas_used: None,
+        })
+    }
+
+    /// This function runs a single fuzz case with `calldata`.
+    ///
+    /// # Returns
+    /// The reason of the failure if the case failed, `None` if it passed.
+    async fn run_fuzz_case(
+        &self,
+        address: Address,
+        calldata: &[u8],
+        suite: &ZkTestSuite,
+        expect_fail: bool,
+    ) -> eyre::Result<Option<String>> {
+        let outcome = self.call_raw(address, calldata.to_vec(), &suite.abi).await?;
+        Ok(match (outcome, expect_fail) {
+            (CallOutcome::Revert { reason, .. }, false) => Some(reason),
+            (CallOutcome::Success(_), true) => Some("Expected the call to revert".to_string()),
+            _ => None,
+        })
+    }
+
+    /// This function shrinks the inputs of a failing fuzz case, like proptest does.
+    ///
+    /// The inputs are simplified as long as the case keeps failing and complicated again when it
+    /// passes, until they cannot be shrunk any further or `MAX_SHRINK_ITERATIONS` cases were run.
+    ///
+    /// # Returns
+    /// The calldata of the simplest failing case and the reason it failed with.
+    async fn shrink(
+        &self,
+        tree: &mut dyn ValueTree<Value = ethers::types::Bytes>,
+        mut calldata: Vec<u8>,
+        mut reason: String,
+        address: Address,
+        suite: &ZkTestSuite,
+        expect_fail: bool,
+    ) -> eyre::Result<(Vec<u8>, String)> {
+        let mut shrinking = tree.simplify();
+        let mut iterations = 0;
+        while shrinking && iterations < MAX_SHRINK_ITERATIONS {
+            iterations += 1;
+            let candidate = tree.current().to_vec();
+            match self.run_fuzz_case(address, &candidate, suite, expect_fail).await? {
+                Some(candidate_reason) => {
+                    calldata = candidate;
+                    reason = candidate_reason;
+                    shrinking = tree.simplify();
+                }
+                None => shrinking = tree.complicate(),
+            }
+        }
+        Ok((calldata, reason))
+    }
+
+    /// This function runs an invariant test.
+    ///
+    /// Every run of the `[invariant]` config deploys a fresh instance of the test contract, runs
+    /// `setUp()` and sends `depth` calls to the contracts deployed by the test contract as
+    /// transactions. The called functions are chosen at random from the functions of the targets
+    /// which are neither `view` nor `pure`, with inputs generated by the strategies of
+    /// `forge test`. The invariant is checked after every call, it is broken if it reverts or
+    /// returns `false`, or if a call reverts and `fail_on_revert` is set. The sequence that broke
+    /// the invariant is shrunk with `shrink_sequence`.
+    ///
+    /// # Errors
+    /// An error is returned if the test contract cannot be deployed, if `setUp()` reverts or if the
+    /// test contract doesn't deploy any contract to call.
+    async fn run_invariant_test(
+        &self,
+        suite: &ZkTestSuite,
+        func: &Function,
+    ) -> eyre::Result<ZkTestResult> {
+        let start = Instant::now();
+        let signature = func.signature();
+        let config = self.test_options.invariant;
+        let mut fuzzer = self.test_options.invariant_fuzzer();
+
+        for _ in 0..config.runs {
+            let (address, targets) = self.deploy_invariant_targets(suite).await?;
+            let mut sequence = Vec::new();
+            for _ in 0..config.depth {
+                let call = generate_invariant_call(&targets, &mut fuzzer)?;
+                sequence.push(call.clone());
+                let reason = match self.execute_invariant_call(&targets, &call).await? {
+                    Some(reason) if config.fail_on_revert => Some(reason),
+                    Some(_) => continue,
+                    None => self.check_invariant(address, func, suite).await?,
+                };
+                let reason = match reason {
+                    Some(reason) => reason,
+                    None => continue,
+                };
+
+                let (sequence, reason) =
+                    self.shrink_sequence(suite, func, sequence, reason).await?;
+                return Ok(ZkTestResult {
+                    signature,
+                    success: false,
+                    reason: Some(reason),
+                    duration: start.elapsed(),
+                    runs: Some(config.runs as usize),
+                    counterexample: None,
+                    sequence,
+                    gas: vec![],
+                    gas_used: None,
+                });
+            }
+        }
+
+        Ok(ZkTestResult {
+            signature,
+            success: true,
+            reason: None,
+            duration: start.elapsed(),
+            runs: Some(config.runs as usize),
+            counterexample: None,
+            sequence: vec![],
+            gas: vec![],
+            gas_used: None,
+        })
+    }
+
+    /// This function deploys a fresh instance of the test contract and runs `setUp()`.
+    ///
+    /// The contracts deployed by the constructor or by `setUp()` are found in the
+    /// `ContractDeployed` events of the `ContractDeployer` system contract. Contracts which were
+    /// not compiled with the test contract are skipped.
+    ///
+    /// # Returns
+    /// The address of the test contract and the contracts it deployed, in the order they were
+    /// deployed in.
+    async fn setup<'a>(
+        &self,
+        suite: &'a ZkTestSuite,
+    ) -> eyre::Result<(Address, Vec<(Address, &'a ZkKnownContract)>)> {
+        let mut receipts = vec![self.deploy(suite).await?];
+        let address =
+            receipts[0].contract_address.ok_or(eyre::eyre!("Error retrieving deployed address"))?;
+        if suite.abi.functions().any(|func| func.name.is_setup() && func.inputs.is_empty()) {
+            let receipt = self
+                .send(address, &format!("{SETUP_FUNCTION}()"))
+                .await
+                .wrap_err(format!("setUp() failed for {}", suite.id))?;
+            receipts.push(receipt);
+        }
+
+        let deployer = Address::from_str(CONTRACT_DEPLOYER_ADDR)?;
+        let topic = keccak256(CONTRACT_DEPLOYED_EVENT);
+        let mut deployed = Vec::new();
+        for log in receipts.iter().flat_map(|receipt| &receipt.logs) {
+            if log.address != deployer || log.topics.len() != 4 || log.topics[0].0 != topic {
+                continue;
+            }
+            let contract_address = Address::from(log.topics[3]);
+            if contract_address == address {
+                continue;
+            }
+            if let Some(contract) = suite.contracts.get(&hex::encode(log.topics[2])) {
+                deployed.push((contract_address, contract));
+            }
+        }
+        Ok((address, deployed))
+    }
+
+    /// This function deploys a fresh instance of the test contract and runs `setUp()`.
+    ///
+    /// The targets of the invariant test are the contracts deployed by the constructor or by
+    /// `setUp()`, as returned by `setup`. Contracts which have no function to call are skipped.
+    ///
+    /// # Returns
+    /// The address of the test contract and the targets, in the order they were deployed in.
+    async fn deploy_invariant_targets(
+        &self,
+        suite: &ZkTestSuite,
+    ) -> eyre::Result<(Address, Vec<ZkInvariantTarget>)> {
+        let (address, deployed) = self.setup(suite).await?;
+        let mut targets = Vec::new();
+        for (target, contract) in deployed {
+            let functions: Vec<Function> = contract
+                .abi
+                .functions()
+                .filter(|func| {
+                    !matches!(func.state_mutability, StateMutability::View | StateMutability::Pure)
+                })
+                .cloned()
+                .collect();
+            if functions.is_empty() {
+                continue;
+            }
+            let name = contract.id.rsplit(':').next().unwrap_or(&contract.id).to_string();
+            targets.push(ZkInvariantTarget { address: target, name, functions });
+        }
+
+        if targets.is_empty() {
+            eyre::bail!(
+                "No contracts to fuzz for {}, deploy the contracts to target in setUp()",
+                suite.id
+            );
+        }
+        Ok((address, targets))
+    }
+
+    /// This function sends `call` to its target as a transaction.
+    ///
+    /// # Returns
+    /// The reason of the revert if the call reverted, `None` if it succeeded.
+    async fn execute_invariant_call(
+        &self,
+        targets: &[ZkInvariantTarget],
+        call: &ZkInvariantCall,
+    ) -> eyre::Result<Option<String>> {
+        let target = &targets[call.target];
+        let abi = Abi::default();
+        if let CallOutcome::Revert { reason, .. } =
+            self.call_raw(target.address, call.calldata.clone(), &abi).await?
+        {
+            return Ok(Some(reason));
+        }
+        match self.send_raw(target.address, call.calldata.clone()).await {
+            Ok(receipt) if receipt.status == Some(1u64.into()) => Ok(None),
+            Ok(_) => Ok(Some("Transaction reverted".to_string())),
+            Err(err) => Ok(Some(err.to_string())),
+        }
+    }
+
+    /// This function checks the invariant `func` on the test contract at `address`.
+    ///
+    /// # Returns
+    /// The reason the invariant is broken, `None` if it holds.
+    async fn check_invariant(
+        &self,
+        address: Address,
+        func: &Function,
+        suite: &ZkTestSuite,
+    ) -> eyre::Result<Option<String>> {
+        match self.call(address, func, &suite.abi).await? {
+            CallOutcome::Revert { reason, .. } => Ok(Some(reason)),
+            CallOutcome::Success(returned) => {
+                let holds = match func.decode_output(&returned).ok().as_deref() {
+                    Some([Token::Bool(holds)]) => *holds,
+                    _ => true,
+                };
+                Ok((!holds).then(|| format!("{} returned false", func.signature())))
+            }
+        }
+    }
+
+    /// This function replays `sequence` on a fresh instance of the test contract.
+    ///
+    /// # Returns
+    /// The reason the invariant is broken after the sequence, `None` if it holds after every call.
+    async fn replay_sequence(
+        &self,
+        suite: &ZkTestSuite,
+        func: &Function,
+        sequence: &[ZkInvariantCall],
+    ) -> eyre::Result<Option<String>> {
+        let config = self.test_options.invariant;
+        let (address, targets) = self.deploy_invariant_targets(suite).await?;
+        for call in sequence {
+            if call.target >= targets.len() {
+                return Ok(None);
+            }
+            match self.execute_invariant_call(&targets, call).await? {
+                Some(reason) if config.fail_on_revert => return Ok(Some(reason)),
+                Some(_) => continue,
+                None => {}
+            }
+            if let Some(reason) = self.check_invariant(address, func, suite).await? {
+                return Ok(Some(reason));
+            }
+        }
+        Ok(None)
+    }
+
+    /// This function shrinks the sequence of calls that broke an invariant.
+    ///
+    /// Every call is removed in turn, and the removal is kept if the invariant is still broken when
+    /// the remaining sequence is replayed, for at most `MAX_SHRINK_ITERATIONS` replays.
+    ///
+    /// # Returns
+    /// The calls of the shrunk sequence, with their return values, and the reason the invariant is
+    /// broken after it.
+    async fn shrink_sequence(
+        &self,
+        suite: &ZkTestSuite,
+        func: &Function,
+        mut sequence: Vec<ZkInvariantCall>,
+        mut reason: String,
+    ) -> eyre::Result<(Vec<ZkInvariantStep>, String)> {
+        let mut index = 0;
+        let mut iterations = 0;
+        while index < sequence.len() && sequence.len() > 1 && iterations < MAX_SHRINK_ITERATIONS {
+            iterations += 1;
+            let mut candidate = sequence.clone();
+            candidate.remove(index);
+            match self.replay_sequence(suite, func, &candidate).await? {
+                Some(candidate_reason) => {
+                    sequence = candidate;
+                    reason = candidate_reason;
+                }
+                None => index += 1,
+            }
+        }
+
+        // the return values of the calls are those of a replay of the shrunk sequence
+        let (_, targets) = self.deploy_invariant_targets(suite).await?;
+        let mut steps = Vec::with_capacity(sequence.len());
+        for call in &sequence {
+            let target = match targets.get(call.target) {
+                Some(target) => target,
+                None => break,
+            };
+            let returned = match self
+                .call_raw(target.address, call.calldata.clone(), &Abi::default())
+                .await?
+            {
+                CallOutcome::Success(returned) => {
+                    call.func.decode_output(&returned).unwrap_or_default()
+                }
+                CallOutcome::Revert { .. } => vec![],
+            };
+            self.execute_invariant_call(&targets, call).await?;
+            steps.push(ZkInvariantStep {
+                contract: target.name.clone(),
+                signature: call.func.signature(),
+                calldata: call.calldata.clone(),
+                args: call.func.decode_input(&call.calldata[4..]).unwrap_or_default(),
+                returned,
+            });
+        }
+        Ok((steps, reason))
+    }
+
+    /// This function deploys a new instance of the test contract.
+    ///
+    /// # Returns
+    /// The receipt of the deployment.
+    async fn deploy(&self, suite: &ZkTestSuite) -> eyre::Result<TransactionReceipt> {
+        let zk_wallet =
+            ZKSWallet::new(self.wallet.clone(), None, Some(self.provider.clone()), None)?;
+        let factory_deps =
+            if suite.factory_deps.is_empty() { None } else { Some(suite.factory_deps.clone()) };
+
+        zk_wallet
+            .deploy(
+                serde_json::from_value(suite.abi_json.clone())?,
+                suite.bytecode.clone(),
+                vec![],
+                factory_deps,
+            )
+            .await
+            .wrap_err(format!("Failed to deploy {}", suite.id))
+    }
+
+    /// This function sends a transaction calling `signature` on the contract at `address`.
+    async fn send(&self, address: Address, signature: &str) -> eyre::Result<TransactionReceipt> {
+        let zk_wallet =
+            ZKSWallet::new(self.wallet.clone(), None, Some(self.provider.clone()), None)?;
+        zk_wallet
+            .get_era_provider()?
+            .send_eip712(&zk_wallet.l2_wallet, address, signature, None, None)
+            .await?
+            .await?
+            .ok_or(eyre::eyre!("Error getting the receipt for transaction"))
+    }
+
+    /// This function sends a transaction with `calldata` to the contract at `address`.
+    async fn send_raw(
+        &self,
+        address: Address,
+        calldata: Vec<u8>,
+    ) -> eyre::Result<TransactionReceipt> {
+        let zk_wallet =
+            ZKSWallet::new(self.wallet.clone(), None, Some(self.provider.clone()), None)?;
+        let tx = TransactionRequest::new().from(self.wallet.address()).to(address).data(calldata);
+        zk_wallet
+            .get_era_provider()?
+            .send_transaction(tx, None)
+            .await?
+            .await?
+            .ok_or(eyre::eyre!("Error getting the receipt for transaction"))
+    }
+
+    /// This function executes `func` on the contract at `address` with `eth_call`.
+    ///
+    /// If the call reverts, the revert data is decoded with the ABI of the test contract, so that
+    /// both revert strings and custom errors are displayed in a human readable form.
+    async fn call(
+        &self,
+        address: Address,
+        func: &Function,
+        abi: &Abi,
+    ) -> eyre::Result<CallOutcome> {
+        self.call_raw(address, func.encode_input(&[])?, abi).await
+    }
+
+    /// This function executes `calldata` on the contract at `address` with `eth_call`, decoding
+    /// the revert data with `abi` if the call reverts.
+    async fn call_raw(
+        &self,
+        address: Address,
+        calldata: Vec<u8>,
+        abi: &Abi,
+    ) -> eyre::Result<CallOutcome> {
+        let tx = TransactionRequest::new().from(self.wallet.address()).to(address).data(calldata);
+
+        match self.provider.call(&tx.into(), None).await {
+            Ok(returned) => Ok(CallOutcome::Success(returned.to_vec())),
+            Err(err) => {
+                let data = match err.as_error_response().and_then(|err| err.as_revert_data()) {
+                    Some(data) => data.to_vec(),
+                    None => {
+                        return Ok(CallOutcome::Revert { reason: err.to_string(), data: vec![] })
+                    }
+                };
+                let reason = decode_revert(&data, Some(abi), None)
+                    .unwrap_or_else(|_| format!("custom error 0x{}", hex::encode(&data)));
+                Ok(CallOutcome::Revert { reason, data })
+            }
+        }
+    }
+
+    /// This function traces `func` on the test contract at `address` with `debug_traceCall`.
+    ///
+    /// # Returns
+    /// The ergs used by every call of the test to the `deployed` contracts, in the order they were
+    /// made.
+    ///
+    /// # Errors
+    /// An error is returned if the node doesn't support `debug_traceCall` with the `callTracer`.
+    async fn trace_gas(
+        &self,
+        address: Address,
+        func: &Function,
+        deployed: &[(Address, &ZkKnownContract)],
+    ) -> eyre::Result<Vec<ZkCallGas>> {
+        let tx = TransactionRequest::new()
+            .from(self.wallet.address())
+            .to(address)
+            .data(func.encode_input(&[])?);
+        let trace: Value = self
+            .provider
+            .request(
+                "debug_traceCall",
+                (tx, "latest", serde_json::json!({ "tracer": "callTracer" })),
+            )
+            .await
+            .wrap_err(format!(
+                "Failed to trace {} for the gas report, the node must support debug_traceCall",
+                func.signature()
+            ))?;
+
+        let mut calls = Vec::new();
+        collect_call_gas(&trace, deployed, &mut calls);
+        Ok(calls)
+    }
+
+    /// This function estimates the ergs used by `func` on the test contract at `address` with
+    /// `eth_estimateGas`.
+    async fn estimate_gas(&self, address: Address, func: &Function) -> eyre::Result<u64> {
+        let tx = TransactionRequest::new()
+            .from(self.wallet.address())
+            .to(address)
+            .data(func.encode_input(&[])?);
+        let gas = self
+            .provider
+            .estimate_gas(&tx.into(), None)
+            .await
+            .wrap_err(format!("Failed to estimate the ergs used by {}", func.signature()))?;
+        Ok(gas.as_u64())
+    }
+
+    /// This function checks whether a test that did not revert failed an assertion.
+    ///
+    /// `DSTest` based contracts record failed assertions in storage rather than reverting, which
+    /// `eth_call` does not persist. The test is therefore sent as a transaction and `failed()` is
+    /// queried afterwards. Contracts without a `failed()` function never fail this way.
+    async fn has_failed(
+        &self,
+        suite: &ZkTestSuite,
+        address: Address,
+        signature: &str,
+    ) -> eyre::Result<bool> {
+        let failed = match suite
+            .abi
+            .functions()
+            .find(|func| func.name == FAILED_FUNCTION && func.inputs.is_empty())
+        {
+            Some(failed) => failed,
+            None => return Ok(false),
+        };
+
+        self.send(address, signature).await.wrap_err(format!("{signature} failed"))?;
+
+        match self.call(address, failed, &suite.abi).await? {
+            CallOutcome::Success(returned) => Ok(returned.iter().any(|byte| *byte != 0)),
+            CallOutcome::Revert { reason, .. } => {
+                eyre::bail!("failed() reverted for {}: {}", suite.id, reason)
+            }
+        }
+    }
+}
+
+/// This function returns the contracts of the `output` of a source, keyed by the hash of their
+/// bytecode as reported by zksolc.
+fn known_contracts(output: &Value) -> BTreeMap<String, ZkKnownContract> {
+    let mut contracts = BTreeMap::new();
+    let files = match output.as_object() {
+        Some(files) => files,
+        None => return contracts,
+    };
+    for (file, file_contracts) in files {
+        let file_contracts = match file_contracts.as_object() {
+            Some(file_contracts) => file_contracts,
+            None => continue,
+        };
+        for (name, contract) in file_contracts {
+            let hash = match contract["hash"].as_str() {
+                Some(hash) => hash.trim_start_matches("0x").to_lowercase(),
+                None => continue,
+            };
+            if let Ok(abi) = serde_json::from_value(contract["abi"].clone()) {
+                contracts.insert(hash, ZkKnownContract { id: format!("{file}:{name}"), abi });
+            }
+        }
+    }
+    contracts
+}
+
+/// This function returns the JUnit XML report of `results`, with a `<testsuite>` for every test
+/// contract and a `<testcase>` for every test function.
+///
+/// Failing tests contain a `<failure>` with the decoded reason of the failure, and the
+/// counterexample or the call sequence it failed with, if any.
+fn junit_xml(results: &[ZkSuiteResult]) -> String {
+    let tests: usize = results.iter().map(|suite| suite.results.len()).sum();
+    let failures: usize = results.iter().map(|suite| suite.failures().count()).sum();
+    let duration: Duration = results.iter().map(|suite| suite.duration).sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<testsuites name=\"zk-test\" tests=\"{tests}\" failures=\"{failures}\" time=\"{:.3}\">",
+        duration.as_secs_f64()
+    );
+    for suite in results {
+        let _ = writeln!(
+            xml,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">",
+            escape_xml(&suite.id),
+            suite.results.len(),
+            suite.failures().count(),
+            suite.duration.as_secs_f64()
+        );
+        for result in &suite.results {
+            let _ = write!(
+                xml,
+                "    <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\"",
+                escape_xml(&suite.id),
+                escape_xml(&result.signature),
+                result.duration.as_secs_f64()
+            );
+            if result.success {
+                xml.push_str("/>\n");
+                continue;
+            }
+
+            let reason = result.reason.as_deref().unwrap_or("Assertion failed.");
+            let mut details = reason.to_string();
+            if let Some(counterexample) = &result.counterexample {
+                let _ = write!(details, "\nCounterexample: {counterexample}");
+            }
+            for (i, step) in result.sequence.iter().enumerate() {
+                let _ = write!(details, "\n{}. {step}", i + 1);
+            }
+            let _ = writeln!(
+                xml,
+                ">\n      <failure message=\"{}\">{}</failure>\n    </testcase>",
+                escape_xml(reason),
+                escape_xml(&details)
+            );
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+/// This function escapes the characters of `text` which cannot appear as is in XML attributes or
+/// text.
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// This function parses the regex of a test filter.
+///
+/// # Errors
+/// If `pattern` is not a valid regex, the error contains the pattern with the position of the
+/// syntax error highlighted, as reported by the `regex` crate.
+fn parse_regex(pattern: &str) -> Result<Regex, String> {
+    Regex::new(pattern).map_err(|err| format!("invalid regex `{pattern}`\n{err}"))
+}
+
+/// This function collects the ergs used by the calls to the `deployed` contracts in `frame`, a
+/// call frame of the `callTracer`, and in the frames of its nested calls.
+fn collect_call_gas(
+    frame: &Value,
+    deployed: &[(Address, &ZkKnownContract)],
+    calls: &mut Vec<ZkCallGas>,
+) {
+    let to = frame["to"].as_str().and_then(|to| Address::from_str(to).ok());
+    if let Some((_, contract)) =
+        to.and_then(|to| deployed.iter().find(|(address, _)| *address == to))
+    {
+        let input = frame["input"]
+            .as_str()
+            .and_then(|input| hex::decode(input.trim_start_matches("0x")).ok())
+            .unwrap_or_default();
+        let func = input.get(..4).and_then(|selector| {
+            contract.abi.functions().find(|func| func.short_signature()[..] == *selector)
+        });
+        let gas = frame["gasUsed"]
+            .as_str()
+            .and_then(|gas| U256::from_str_radix(gas.trim_start_matches("0x"), 16).ok());
+        if let (Some(func), Some(gas)) = (func, gas) {
+            calls.push(ZkCallGas {
+                contract: contract.id.clone(),
+                function: func.name.clone(),
+                signature: func.signature(),
+                gas,
+            });
+        }
+    }
+
+    for call in frame["calls"].as_array().into_iter().flatten() {
+        collect_call_gas(call, deployed, calls);
+    }
+}
+
+/// This function generates a random call to one of the `targets` of an invariant test.
+fn generate_invariant_call(
+    targets: &[ZkInvariantTarget],
+    fuzzer: &mut TestRunner,
+) -> eyre::Result<ZkInvariantCall> {
+    let mut pick = |len: usize| {
+        (0..len)
+            .new_tree(fuzzer)
+            .map(|tree| tree.current())
+            .map_err(|err| eyre::eyre!("Failed to generate an invariant call: {err}"))
+    };
+    let target = pick(targets.len())?;
+    let func = targets[target].functions[pick(targets[target].functions.len())?].clone();
+    let calldata = fuzz_calldata(func.clone())
+        .new_tree(fuzzer)
+        .map_err(|err| eyre::eyre!("Failed to generate inputs for {}: {err}", func.signature()))?
+        .current()
+        .to_vec();
+    Ok(ZkInvariantCall { target, func, calldata })
+}
+
+/// This function prints the outcome of a single test, in the same format as `forge test`.
+fn print_test_result(result: &ZkTestResult) {
+    let status = if result.success {
+        Paint::green("[PASS]".to_string())
+    } else {
+        let reason = result
+            .reason
+            .as_ref()
+            .map(|reason| format!("Reason: {reason}"))
+            .unwrap_or_else(|| "Reason: Assertion failed.".to_string());
+        let counterexample = result
+            .counterexample
+            .as_ref()
+            .map(|counterexample| format!(" Counterexample: {counterexample}"))
+            .unwrap_or_default();
+        let sequence = if result.sequence.is_empty() {
+            String::new()
+        } else {
+            let calls = result
+                .sequence
+                .iter()
+                .enumerate()
+                .map(|(i, step)| format!("\t\t{}. {step}\n", i + 1))
+                .collect::<String>();
+            format!("\n\t[Sequence]\n{calls}")
+        };
+        Paint::red(format!("[FAIL. {reason}{counterexample}]{sequence}"))
+    };
+
+    match result.runs {
+        Some(runs) => {
+            println!("{} {} (runs: {}, {:.2?})", status, result.signature, runs, result.duration)
+        }
+        None => println!("{} {} ({:.2?})", status, result.signature, result.duration),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filters_exclude_the_inverse_patterns() {
+        let args = ZkTestArgs::parse_from([
+            "foundry-cli",
+            "--match-contract",
+            "Counter",
+            "--no-match-contract",
+            "Fork",
+            "--no-match-test",
+            "testFail",
+        ]);
+        assert!(args.matches_contract("CounterTest"));
+        assert!(!args.matches_contract("CounterForkTest"));
+        assert!(!args.matches_contract("TokenTest"));
+        assert!(args.matches_test("testIncrement"));
+        assert!(!args.matches_test("testFailIncrement"));
+    }
+
+    #[test]
+    fn tests_time_out_after_the_timeout() {
+        let args = ZkTestArgs::parse_from(["foundry-cli"]);
+        assert_eq!(args.timeout, 30_000);
+        let args = ZkTestArgs::parse_from(["foundry-cli", "--timeout", "500"]);
+        assert_eq!(args.timeout, 500);
+
+        let result = ZkTestResult::timed_out("testLoop()".to_string(), Duration::from_millis(500));
+        assert!(!result.success);
+        assert_eq!(result.reason.as_deref(), Some("Test timed out after 500ms"));
+    }
+
+    #[test]
+    fn tests_which_cannot_run_fail_with_the_error() {
+        let err = eyre::eyre!("setUp() reverted: Not allowed");
+        let result =
+            ZkTestResult::errored("testIncrement()".to_string(), &err, Duration::from_millis(20));
+        assert!(!result.success);
+        assert_eq!(result.reason.as_deref(), Some("setUp() reverted: Not allowed"));
+        assert_eq!(result.duration, Duration::from_millis(20));
+    }
+
+    #[test]
+    fn junit_xml_reports_every_test() {
+        let result = |signature: &str, reason: Option<&str>| ZkTestResult {
+            signature: signature.to_string(),
+            success: reason.is_none(),
+            reason: reason.map(ToString::to_string),
+            duration: Duration::from_millis(1500),
+            runs: None,
+            counterexample: None,
+            sequence: vec![],
+            gas: vec![],
+            gas_used: None,
+        };
+        let results = [ZkSuiteResult {
+            id: "test/Counter.t.sol:CounterTest".to_string(),
+            results: vec![
+                result("testIncrement()", None),
+                result("testDecrement()", Some("Counter < 0 & underflow")),
+            ],
+            duration: Duration::from_secs(3),
+        }];
+
+        let xml = junit_xml(&results);
+        assert!(xml.contains(r#"<testsuites name="zk-test" tests="2" failures="1" time="3.000">"#));
+        assert!(
+            xml.contains(r#"classname="test/Counter.t.sol:CounterTest" name="testIncrement()""#)
+        );
+        assert!(xml.contains(r#"name="testIncrement()" time="1.500"/>"#));
+        assert!(xml.contains(r#"<failure message="Counter &lt; 0 &amp; underflow">"#));
+    }
+
+    #[test]
+    fn invalid_test_filters_report_the_pattern() {
+        let err = ZkTestArgs::try_parse_from(["foundry-cli", "--match-test", "test("]).unwrap_err();
+        let err = err.to_string();
+        assert!(err.contains("invalid regex `test(`"), "{err}");
+        assert!(err.contains("unclosed group"), "{err}");
+    }
+}
@@ -0,0 +1,110 @@
+//! The `zk_update` module provides the `forge zk-update` command, which upgrades the `zksolc`
+//! compiler of the project to the latest stable release.
+//!
+//! The latest release is found with `ZkSolcManager::get_latest_version` and compared to the
+//! version set as `use_zksolc` in the `[zksync]` section of `foundry.toml`, or to
+//! `DEFAULT_ZKSOLC_VERSION` if none is set. If it is newer, it is downloaded, with its checksum
+//! verified if it is known, and set as the version of the project after the user confirms it, or
+//! right away with `--yes`.
+use super::zksolc_manager::{
+    ZkSolcManager, ZkSolcManagerBuilder, ZkSolcManagerOpts, ZkSolcVersion, ZkSolcVersionSelection,
+};
+use crate::cmd::{Cmd, LoadConfig};
+use clap::{Parser, ValueHint};
+use dialoguer::Confirm;
+use foundry_config::impl_figment_convert_basic;
+use std::path::PathBuf;
+
+/// CLI arguments for `forge zk-update`.
+#[derive(Debug, Clone, Parser)]
+pub struct ZkUpdateArgs {
+    /// Set the new version in `foundry.toml` without asking for confirmation.
+    #[clap(long, short)]
+    pub yes: bool,
+
+    #[clap(
+        help = "The project's root path. By default, this is the root directory of the current Git repository or the current working directory if it is not part of a Git repository",
+        long,
+        value_hint = ValueHint::DirPath,
+        value_name = "PATH"
+    )]
+    root: Option<PathBuf>,
+}
+impl_figment_convert_basic!(ZkUpdateArgs);
+
+impl Cmd for ZkUpdateArgs {
+    type Output = ();
+
+    /// Upgrades the `zksolc` version of the project to the latest stable release.
+    ///
+    /// # Procedure
+    /// 1. Finds the latest stable release and the version of the project, see `pinned_version`.
+    /// 2. If the latest release is newer, downloads it with `ZkSolcManager::ensure_version`.
+    /// 3. Sets it as `use_zksolc` in `foundry.toml`, once confirmed or with `--yes`.
+    ///
+    /// # Errors
+    /// An error is returned if the releases cannot be queried, if the version of the project is
+    /// invalid, or if the latest release cannot be downloaded.
+    fn run(self) -> eyre::Result<Self::Output> {
+        let config = self.try_load_config_emit_warnings()?;
+        let latest = ZkSolcManager::get_latest_version()
+            .map_err(|err| eyre::eyre!("Failed to find the latest zksolc version: {}", err))?;
+        let pinned = pinned_version(config.zksync.use_zksolc.as_deref(), &latest)?;
+        if latest <= pinned {
+            println!("Already up to date ({pinned})");
+            return Ok(());
+        }
+
+        println!("Updating zksolc from {pinned} to {latest}");
+        let zksolc_manager = ZkSolcManagerBuilder::new(ZkSolcManagerOpts::new(latest.clone()))
+            .build()
+            .map_err(|err| eyre::eyre!("Error building zksolc_manager: {}", err))?;
+        let path = zksolc_manager.ensure_version().map_err(|err| eyre::eyre!("{}", err))?;
+        println!("zksolc {latest} is installed at {}", path.display());
+
+        if !config.get_config_path().exists() {
+            println!("No foundry.toml found, pass --use-zksolc {latest} to zk-build to use it");
+            return Ok(());
+        }
+        let confirmed = self.yes ||
+            Confirm::new()
+                .with_prompt(format!("Set use_zksolc to {latest} in foundry.toml?"))
+                .interact()?;
+        if confirmed {
+            config.update_zksolc_version(&latest.to_string())?;
+            println!("Using zksolc {latest}");
+        }
+        Ok(())
+    }
+}
+
+/// This function returns the `zksolc` version of the project, given its `use_zksolc` setting.
+///
+/// It's `DEFAULT_ZKSOLC_VERSION` if `use_zksolc` is not set, and the `latest` release if the
+/// project already follows it.
+fn pinned_version(use_zksolc: Option<&str>, latest: &ZkSolcVersion) -> eyre::Result<ZkSolcVersion> {
+    let selection = match use_zksolc {
+        Some(version) => version.parse::<ZkSolcVersionSelection>().map_err(|err| {
+            eyre::eyre!("Invalid use_zksolc in the [zksync] section of the config: {}", err)
+        })?,
+        None => ZkSolcVersionSelection::default(),
+    };
+    match selection {
+        ZkSolcVersionSelection::Latest => Ok(latest.clone()),
+        ZkSolcVersionSelection::Version(version) => Ok(version),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pinned_version_defaults_to_the_default_version() {
+        let latest: ZkSolcVersion = "v1.3.11".parse().unwrap();
+        assert_eq!(pinned_version(Some("1.3.9"), &latest).unwrap(), "v1.3.9".parse().unwrap());
+        assert_eq!(pinned_version(None, &latest).unwrap(), ZkSolcVersion::default());
+        assert_eq!(pinned_version(Some("latest"), &latest).unwrap(), latest);
+        assert!(pinned_version(Some("2.0"), &latest).is_err());
+    }
+}
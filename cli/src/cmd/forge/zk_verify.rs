@@ -0,0 +1,620 @@
+//! The `zk_verify` module provides the `forge zk-verify` command, which verifies the source code of
+//! a contract deployed to zkSync Era on the block explorer.
+//!
+//! The verification process involves:
+//! 1. Finding the artifact of the contract in the `zkout` directory, together with the standard
+//!    JSON input it was compiled from.
+//! 2. Submitting a verification request in the `solidity-standard-json-input` format to the
+//!    contract verification API of the explorer.
+//! 3. Polling the status of the request, backing off exponentially, until it is confirmed, rejected
+//!    or `--timeout` is reached.
+//!
+//! If the explorer rejects the request, its reason is printed together with a suggested fix. Once
+//! the contract is verified, its page on the explorer is printed. With `--watch`, for verifications
+//! which take several minutes, the status is polled less often and for longer, showing a spinner.
+//!
+//! With `--via-sourcify`, the contract is verified on Sourcify instead: its metadata, with the
+//! zksolc version as `compiler.version`, is uploaded together with its sources, and the contract is
+//! looked up on Sourcify until it is verified.
+use super::{
+    build::CoreBuildArgs,
+    zk_artifacts::{find_contract, ZkArtifactContract},
+    zksolc_manager::DEFAULT_ZKSOLC_VERSION,
+};
+use crate::cmd::LoadConfig;
+use clap::Parser;
+use ethers::types::Address;
+use eyre::Context;
+use foundry_config::ZkConfig;
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+/// The contract verification API of the zkSync Era mainnet explorer.
+const MAINNET_VERIFIER_URL: &str =
+    "https://zksync2-mainnet-explorer.zksync.io/contract_verification";
+
+/// The contract verification API of the zkSync Era testnet explorer.
+const TESTNET_VERIFIER_URL: &str =
+    "https://zksync2-testnet-explorer.zksync.dev/contract_verification";
+
+/// The server of the Sourcify API, used with `--via-sourcify`.
+const SOURCIFY_URL: &str = "https://sourcify.dev/server";
+
+/// The interval before the status of a verification request is polled for the first time.
+const INITIAL_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The maximum interval between two polls of the status of a verification request.
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(16);
+
+/// The interval before the status of a verification request is polled for the first time with
+/// `--watch`.
+const WATCH_INITIAL_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The maximum interval between two polls of the status of a verification request with `--watch`.
+const WATCH_MAX_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long to wait for the verification to be confirmed by default, in seconds.
+const DEFAULT_TIMEOUT: u64 = 60;
+
+/// How long to wait for the verification to be confirmed by default with `--watch`, in seconds.
+const DEFAULT_WATCH_TIMEOUT: u64 = 600;
+
+/// CLI arguments for `forge zk-verify`.
+#[derive(Debug, Clone, Parser)]
+#[clap(next_help_heading = "ZkVerify options", about = None)]
+pub struct ZkVerifyArgs {
+    /// The address of the deployed contract.
+    #[clap(long = "contract-address", value_name = "ADDRESS")]
+    pub contract_address: Address,
+
+    /// The name of the contract to verify, optionally in the form `<path>:<contractname>`.
+    #[clap(long = "contract-name", value_name = "CONTRACT")]
+    pub contract_name: String,
+
+    /// The id of the chain the contract is deployed to.
+    #[clap(long = "chain-id", value_name = "CHAIN_ID")]
+    pub chain_id: u64,
+
+    /// The ABI-encoded constructor arguments used for the deployment, as a hex string.
+    #[clap(long = "constructor-args", value_name = "HEX")]
+    pub constructor_args: Option<String>,
+
+    /// How long to wait for the verification to be confirmed, in seconds.
+    ///
+    /// Defaults to 60 seconds, or to 600 seconds with `--watch`.
+    #[clap(long, value_name = "SECONDS")]
+    pub timeout: Option<u64>,
+
+    /// Watch the verification until it completes, polling its status every 5 seconds and backing
+    /// off up to every 60 seconds.
+    #[clap(long)]
+    pub watch: bool,
+
+    /// Verify the contract on Sourcify instead of the block explorer.
+    #[clap(long)]
+    pub via_sourcify: bool,
+
+    /// The URL of the contract verification API.
+    ///
+    /// Defaults to the explorer of the zkSync Era mainnet or testnet, depending on `--chain-id`, or
+    /// to the Sourcify server with `--via-sourcify`.
+    #[clap(long = "verifier-url", value_name = "URL")]
+    pub verifier_url: Option<String>,
+
+    /// Core build arguments, used to locate the project and its configuration.
+    #[clap(flatten)]
+    pub opts: CoreBuildArgs,
+}
+
+/// A verification request in the format of the zkSync Era explorer.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ZkVerificationRequest {
+    contract_address: Address,
+    source_code: Value,
+    code_format: &'static str,
+    contract_name: String,
+    compiler_solc_version: String,
+    compiler_zksolc_version: String,
+    optimization_used: bool,
+    constructor_arguments: String,
+}
+
+/// The status of a verification request, as returned by the zkSync Era explorer.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ZkVerificationStatus {
+    status: String,
+    error: Option<String>,
+    #[serde(default)]
+    compilation_errors: Vec<String>,
+}
+
+/// The response of the Sourcify API to a verification request.
+#[derive(Debug, Deserialize)]
+struct SourcifyResponse {
+    #[serde(default)]
+    result: Vec<SourcifyMatch>,
+    error: Option<String>,
+}
+
+/// Whether a contract is verified on Sourcify, as returned by the Sourcify API.
+#[derive(Debug, Deserialize)]
+struct SourcifyMatch {
+    /// `perfect` or `partial` if the contract is verified, `false` otherwise.
+    status: String,
+}
+
+impl SourcifyMatch {
+    /// Returns `true` if the contract is verified, even if only its bytecode matches.
+    fn is_verified(&self) -> bool {
+        self.status == "perfect" || self.status == "partial"
+    }
+}
+
+impl ZkVerifyArgs {
+    /// Executes the command to verify a contract.
+    ///
+    /// # Procedure
+    /// 1. Finds the artifact and the standard JSON input of the contract in the "zkout" directory.
+    /// 2. Submits the verification request to the explorer, or to Sourcify with `--via-sourcify`,
+    ///    see `verify_with_sourcify`.
+    /// 3. Polls the status of the request until it is confirmed, rejected or timed out, see
+    ///    `wait_for_verification`, and prints the page of the contract on the explorer.
+    ///
+    /// # Errors
+    /// An error is returned if the contract was not compiled with `zk-build`, if the explorer
+    /// cannot be reached, if the verification is rejected or if it is not confirmed in time.
+    pub async fn run(self) -> eyre::Result<()> {
+        let verifier_url = self.verifier_url()?;
+        let config = self.opts.try_load_config_emit_warnings()?;
+        let artifacts = config.project()?.paths.root.join("zkout");
+
+        let found = find_contract(&artifacts, &self.contract_name)?;
+        let request = self.prepare_request(&artifacts, &found, &config.zksync)?;
+
+        println!(
+            "Submitting verification for [{}] {:?}.",
+            request.contract_name, self.contract_address
+        );
+        let client = reqwest::Client::new();
+        if self.via_sourcify {
+            return self.verify_with_sourcify(&client, &verifier_url, &found, &request).await;
+        }
+        let response = client.post(&verifier_url).json(&request).send().await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await?;
+            eyre::bail!(
+                "Verification request failed with status code {}\nDetails: {}\nSuggested fix: {}",
+                status,
+                body,
+                suggest_fix(&body)
+            );
+        }
+        let id: Value = response.json().await?;
+        let id = id.as_u64().map(|id| id.to_string()).unwrap_or_else(|| id.to_string());
+        println!("Submitted verification request with id {id}.");
+
+        let spinner = self.spinner();
+        let verified =
+            self.wait_for_verification(&client, &verifier_url, &id, spinner.as_ref()).await;
+        if let Some(spinner) = spinner {
+            spinner.finish_and_clear();
+        }
+        verified?;
+
+        println!("Contract successfully verified.");
+        if let Some(url) = self.explorer_url() {
+            println!("{url}");
+        }
+        Ok(())
+    }
+
+    /// This function verifies the contract on Sourcify, for `--via-sourcify`.
+    ///
+    /// The files returned by `sourcify_files` are uploaded to the `/verify` endpoint of the server.
+    /// If the contract is not verified right away, it is looked up with the `/check-by-addresses`
+    /// endpoint, backing off exponentially between two polls, until it is verified or `--timeout`
+    /// is reached.
+    ///
+    /// # Errors
+    /// An error is returned if the contract has no metadata, if Sourcify cannot be reached, if it
+    /// rejects the verification or if the verification is not confirmed in time.
+    async fn verify_with_sourcify(
+        &self,
+        client: &reqwest::Client,
+        sourcify_url: &str,
+        found: &ZkArtifactContract,
+        request: &ZkVerificationRequest,
+    ) -> eyre::Result<()> {
+        let files = sourcify_files(
+            &found.contract,
+            &request.source_code,
+            &request.compiler_zksolc_version,
+        )?;
+        let body = serde_json::json!({
+            "address": self.contract_address,
+            "chain": self.chain_id.to_string(),
+            "files": files,
+        });
+        let response = client.post(format!("{sourcify_url}/verify")).json(&body).send().await?;
+        let status = response.status();
+        let response: SourcifyResponse = response.json().await?;
+        if !status.is_success() {
+            let error = response.error.unwrap_or_default();
+            eyre::bail!(
+                "Sourcify rejected the verification with status code {}: {}\nSuggested fix: {}",
+                status,
+                error,
+                suggest_fix(&error)
+            );
+        }
+
+        if !response.result.iter().any(SourcifyMatch::is_verified) {
+            let spinner = self.spinner();
+            let verified = self.wait_for_sourcify(client, sourcify_url, spinner.as_ref()).await;
+            if let Some(spinner) = spinner {
+                spinner.finish_and_clear();
+            }
+            verified?;
+        }
+
+        println!("Contract successfully verified on Sourcify.");
+        if let Some(url) = self.explorer_url() {
+            println!("{url}");
+        }
+        Ok(())
+    }
+
+    /// This function looks up the contract on Sourcify until it is verified or `--timeout` is
+    /// reached, backing off exponentially between two polls.
+    ///
+    /// # Errors
+    /// An error is returned if Sourcify cannot be reached or if the contract is not verified in
+    /// time.
+    async fn wait_for_sourcify(
+        &self,
+        client: &reqwest::Client,
+        sourcify_url: &str,
+        spinner: Option<&ProgressBar>,
+    ) -> eyre::Result<()> {
+        let (mut interval, max_interval, timeout_secs) = self.poll_settings();
+        let timeout = Duration::from_secs(timeout_secs);
+        let start = Instant::now();
+        loop {
+            let elapsed = start.elapsed();
+            if elapsed >= timeout {
+                eyre::bail!(
+                    "The contract {:?} was not verified on Sourcify within {}s",
+                    self.contract_address,
+                    timeout_secs
+                );
+            }
+            tokio::time::sleep(interval.min(timeout - elapsed)).await;
+            interval = next_poll_interval(interval, max_interval);
+
+            let matches: Vec<SourcifyMatch> = client
+                .get(format!("{sourcify_url}/check-by-addresses"))
+                .query(&[
+                    ("addresses", format!("{:?}", self.contract_address)),
+                    ("chainIds", self.chain_id.to_string()),
+                ])
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            if matches.iter().any(SourcifyMatch::is_verified) {
+                return Ok(());
+            }
+            match spinner {
+                Some(spinner) => spinner.set_message("Verification status: pending"),
+                None => println!("Verification status: pending"),
+            }
+        }
+    }
+
+    /// This function returns the spinner showing the status of the verification with `--watch`.
+    fn spinner(&self) -> Option<ProgressBar> {
+        self.watch.then(|| {
+            let spinner = ProgressBar::new_spinner();
+            spinner.set_style(
+                ProgressStyle::with_template("{spinner:.green} [{elapsed_precise}] {msg}").unwrap(),
+            );
+            spinner.set_message("Waiting for the verification to complete");
+            spinner.enable_steady_tick(Duration::from_millis(100));
+            spinner
+        })
+    }
+
+    /// This function returns the interval before the first poll, the maximum interval between two
+    /// polls and the timeout in seconds, which are longer with `--watch`.
+    fn poll_settings(&self) -> (Duration, Duration, u64) {
+        if self.watch {
+            let timeout = self.timeout.unwrap_or(DEFAULT_WATCH_TIMEOUT);
+            (WATCH_INITIAL_POLL_INTERVAL, WATCH_MAX_POLL_INTERVAL, timeout)
+        } else {
+            (INITIAL_POLL_INTERVAL, MAX_POLL_INTERVAL, self.timeout.unwrap_or(DEFAULT_TIMEOUT))
+        }
+    }
+
+    /// This function polls the status of the verification request `id` until it is confirmed,
+    /// rejected or `--timeout` is reached, backing off exponentially between two polls.
+    ///
+    /// The status is printed after every poll, or shown next to the `spinner` with `--watch`.
+    ///
+    /// # Errors
+    /// An error is returned if the explorer cannot be reached, if the verification is rejected or if
+    /// it is not confirmed in time.
+    async fn wait_for_verification(
+        &self,
+        client: &reqwest::Client,
+        verifier_url: &str,
+        id: &str,
+        spinner: Option<&ProgressBar>,
+    ) -> eyre::Result<()> {
+        let (mut interval, max_interval, timeout_secs) = self.poll_settings();
+        let timeout = Duration::from_secs(timeout_secs);
+        let start = Instant::now();
+        loop {
+            let elapsed = start.elapsed();
+            if elapsed >= timeout {
+                eyre::bail!(
+                    "Verification request {} was not confirmed within {}s, check its status at {}/{}",
+                    id,
+                    timeout_secs,
+                    verifier_url,
+                    id
+                );
+            }
+            tokio::time::sleep(interval.min(timeout - elapsed)).await;
+            interval = next_poll_interval(interval, max_interval);
+
+            let status: ZkVerificationStatus = client
+                .get(format!("{verifier_url}/{id}"))
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+
+            match status.status.as_str() {
+                "successful" => return Ok(()),
+                "failed" => {
+                    let reason = status
+                        .error
+                        .into_iter()
+                        .chain(status.compilation_errors)
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    eyre::bail!(
+                        "Verification was rejected: {}\nSuggested fix: {}",
+                        reason,
+                        suggest_fix(&reason)
+                    );
+                }
+                other => match spinner {
+                    Some(spinner) => spinner.set_message(format!("Verification status: {other}")),
+                    None => println!("Verification status: {other}"),
+                },
+            }
+        }
+    }
+
+    /// This function returns the page of the contract on the block explorer of `--chain-id`, or
+    /// `None` if no explorer is known for the chain.
+    fn explorer_url(&self) -> Option<String> {
+        let explorer = match self.chain_id {
+            324 => "https://explorer.zksync.io",
+            280 => "https://goerli.explorer.zksync.io",
+            _ => return None,
+        };
+        Some(format!("{}/address/{:?}#contract", explorer, self.contract_address))
+    }
+
+    /// This function returns the URL of the verification API to use.
+    ///
+    /// `--verifier-url` takes precedence, otherwise the Sourcify server is used with
+    /// `--via-sourcify` and the explorer is selected based on `--chain-id` without it.
+    fn verifier_url(&self) -> eyre::Result<String> {
+        if let Some(url) = &self.verifier_url {
+            return Ok(url.trim_end_matches('/').to_string());
+        }
+        if self.via_sourcify {
+            return Ok(SOURCIFY_URL.to_string());
+        }
+        match self.chain_id {
+            324 => Ok(MAINNET_VERIFIER_URL.to_string()),
+            280 => Ok(TESTNET_VERIFIER_URL.to_string()),
+            chain_id => eyre::bail!(
+                "No block explorer is known for chain {}, use --verifier-url to provide one",
+                chain_id
+            ),
+        }
+    }
+
+    /// This function builds the verification request of the contract.
+    ///
+    /// The standard JSON input and the compiler versions are read from the files written by
+    /// `zk-build` for the source the contract is defined in. If the zksolc version is missing from
+    /// the artifacts, the one of the `[zksync]` section of the config is assumed.
+    fn prepare_request(
+        &self,
+        artifacts: &Path,
+        found: &ZkArtifactContract,
+        zk_config: &ZkConfig,
+    ) -> eyre::Result<ZkVerificationRequest> {
+        let filename = Path::new(&found.file)
+            .file_name()
+            .ok_or(eyre::eyre!("Invalid contract source {}", found.file))?;
+        let source_code = read_json(&artifacts.join(filename).join("json_input.json"))?;
+        let output = read_json(&artifacts.join(filename).join("artifacts.json"))?;
+
+        let compiler_solc_version = output["version"]
+            .as_str()
+            .ok_or(eyre::eyre!("Unable to find the solc version of {}", found.id()))?
+            .to_string();
+        let compiler_zksolc_version = output["zk_version"]
+            .as_str()
+            .map(|version| format!("v{}", version.trim_start_matches('v')))
+            .or_else(|| zk_config.use_zksolc.clone())
+            .unwrap_or_else(|| DEFAULT_ZKSOLC_VERSION.to_string());
+        let optimization_used =
+            source_code["settings"]["optimizer"]["enabled"].as_bool().unwrap_or_default();
+
+        let constructor_arguments = match &self.constructor_args {
+            Some(args) => format!("0x{}", args.trim_start_matches("0x")),
+            None => "0x".to_string(),
+        };
+
+        Ok(ZkVerificationRequest {
+            contract_address: self.contract_address,
+            source_code,
+            code_format: "solidity-standard-json-input",
+            contract_name: found.id(),
+            compiler_solc_version,
+            compiler_zksolc_version,
+            optimization_used,
+            constructor_arguments,
+        })
+    }
+}
+
+/// This function reads and parses the JSON file at `path`.
+fn read_json(path: &Path) -> eyre::Result<Value> {
+    let data = fs::read_to_string(path)
+        .wrap_err(format!("Unable to read {} - did you run zk-build?", path.display()))?;
+    serde_json::from_str(&data).wrap_err(format!("Unable to parse JSON from {}", path.display()))
+}
+
+/// This function returns the files uploaded to Sourcify to verify the `contract`: its metadata,
+/// as `metadata.json`, and the contents of the sources of its standard JSON input `source_code`.
+///
+/// The metadata is the one solc generated, which zksolc outputs as is or in its `solc_metadata`
+/// field, with the zksolc version the contract was compiled with as `compiler.version`.
+fn sourcify_files(
+    contract: &Value,
+    source_code: &Value,
+    zksolc_version: &str,
+) -> eyre::Result<BTreeMap<String, String>> {
+    let metadata = match &contract["metadata"] {
+        Value::Object(metadata) if metadata.contains_key("solc_metadata") => {
+            metadata["solc_metadata"].clone()
+        }
+        metadata => metadata.clone(),
+    };
+    let mut metadata = match metadata {
+        Value::String(metadata) => serde_json::from_str(&metadata)
+            .wrap_err("Unable to parse the metadata of the contract")?,
+        metadata => metadata,
+    };
+    if !metadata.is_object() {
+        eyre::bail!("The contract has no metadata, rebuild it with zk-build");
+    }
+    metadata["compiler"]["version"] = zksolc_version.trim_start_matches('v').into();
+
+    let mut files = BTreeMap::from([("metadata.json".to_string(), metadata.to_string())]);
+    for (path, source) in source_code["sources"].as_object().into_iter().flatten() {
+        match source["content"].as_str() {
+            Some(content) => files.insert(path.clone(), content.to_string()),
+            None => eyre::bail!("The standard JSON input has no content for {}", path),
+        };
+    }
+    Ok(files)
+}
+
+/// This function returns the interval before the next poll, doubling the current one up to
+/// `max_interval`.
+fn next_poll_interval(interval: Duration, max_interval: Duration) -> Duration {
+    (interval * 2).min(max_interval)
+}
+
+/// This function suggests a fix for the reason a verification was rejected with.
+fn suggest_fix(reason: &str) -> &'static str {
+    let reason = reason.to_lowercase();
+    if reason.contains("already verified") {
+        "The contract is already verified, there is nothing to do."
+    } else if reason.contains("constructor") {
+        "Make sure --constructor-args contains the ABI-encoded arguments used for the deployment."
+    } else if reason.contains("bytecode") {
+        "Make sure the contract was built with the same zksolc and solc versions and settings that \
+         were used for the deployment, and that --constructor-args matches the deployment."
+    } else if reason.contains("not found") || reason.contains("no contract") {
+        "Make sure --contract-address and --chain-id point to the deployed contract."
+    } else {
+        "Rebuild the contract with zk-build using the settings of the deployment and try again."
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn poll_interval_backs_off_exponentially_up_to_the_maximum() {
+        let mut interval = INITIAL_POLL_INTERVAL;
+        let mut intervals = Vec::new();
+        for _ in 0..6 {
+            intervals.push(interval.as_secs());
+            interval = next_poll_interval(interval, MAX_POLL_INTERVAL);
+        }
+        assert_eq!(intervals, vec![1, 2, 4, 8, 16, 16]);
+
+        let mut interval = WATCH_INITIAL_POLL_INTERVAL;
+        let mut intervals = Vec::new();
+        for _ in 0..5 {
+            intervals.push(interval.as_secs());
+            interval = next_poll_interval(interval, WATCH_MAX_POLL_INTERVAL);
+        }
+        assert_eq!(intervals, vec![5, 10, 20, 40, 60]);
+    }
+
+    #[test]
+    fn sourcify_metadata_has_the_zksolc_version() {
+        let contract = serde_json::json!({
+            "metadata": {
+                "solc_metadata": "{\"compiler\":{\"version\":\"0.8.20+commit.a1b79de6\"},\"settings\":{\"optimizer\":{\"enabled\":true}}}"
+            }
+        });
+        let source_code = serde_json::json!({
+            "language": "Solidity",
+            "sources": { "src/Counter.sol": { "content": "contract Counter {}" } }
+        });
+
+        let files = sourcify_files(&contract, &source_code, "v1.3.11").unwrap();
+        assert_eq!(files["src/Counter.sol"], "contract Counter {}");
+        let metadata: Value = serde_json::from_str(&files["metadata.json"]).unwrap();
+        assert_eq!(metadata["compiler"]["version"], "1.3.11");
+        assert_eq!(metadata["settings"]["optimizer"]["enabled"], true);
+
+        assert!(sourcify_files(&serde_json::json!({}), &source_code, "v1.3.11").is_err());
+    }
+
+    #[test]
+    fn verified_contracts_link_to_the_explorer() {
+        let args = ZkVerifyArgs::parse_from([
+            "zk-verify",
+            "--contract-address",
+            "0x0000000000000000000000000000000000000001",
+            "--contract-name",
+            "Counter",
+            "--chain-id",
+            "280",
+            "--watch",
+        ]);
+        assert!(args.watch);
+        assert_eq!(args.timeout, None);
+        assert_eq!(
+            args.explorer_url().unwrap(),
+            "https://goerli.explorer.zksync.io/address/0x0000000000000000000000000000000000000001#contract"
+        );
+    }
+}
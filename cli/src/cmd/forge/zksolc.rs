@@ -13,40 +13,735 @@
 ///   parses the JSON input, builds compiler arguments, runs the compiler, and handles the output.
 ///
 /// - Error and Warning Handling: The compiler output is checked for errors and warnings, and they are
-///   displayed appropriately. If errors are encountered, the process will exit with a non-zero status code.
+///   displayed appropriately. If errors are encountered, the source is reported as failed and the
+///   remaining sources are still compiled.
 ///
 /// - JSON Input Generation: The `parse_json_input` method generates the JSON input required by the compiler
-///   for each contract. It configures the Solidity compiler, saves the input to the artifacts directory, and
-///   handles the output.
+///   for each contract. It configures the Solidity compiler, passes the remappings and injects the addresses
+///   of the deployed libraries of the project, saves the input to the artifacts directory, and handles the output.
 ///
 /// - Source Management: The `get_versioned_sources` method retrieves the project sources, resolves the graph
 ///   of sources and versions, and returns the sources grouped by Solc version.
 ///
 /// - Artifact Path Generation: The `build_artifacts_path` and `build_artifacts_file` methods construct the
 ///   path and file for saving the compiler output artifacts.
+///
+/// - Caching: The output of every successful compilation is cached under a key derived from everything
+///   that affects it, so that sources that did not change are not compiled again.
+///
+/// - Artifact Reading: The `read_contract_output`, `get_bytecode` and `get_factory_dependencies` functions
+///   read back the artifacts written by the compiler, so that the compiled contracts can be deployed.
 use ansi_term::Colour::{Red, Yellow};
-use anyhow::{Error, Result};
-use ethers::prelude::{artifacts::Source, Solc};
-use ethers::solc::{
-    artifacts::{output_selection::FileOutputSelection, StandardJsonCompilerInput},
-    Graph, Project,
+use ethers::prelude::{
+    artifacts::{Libraries, Source, StorageLayout},
+    remappings::Remapping,
+    Solc,
 };
+use ethers::solc::{artifacts::output_selection::FileOutputSelection, Graph, Project};
+use foundry_common::TestFunctionExt;
+use once_cell::sync::Lazy;
+use rayon::prelude::*;
+use regex::Regex;
 use semver::Version;
-use serde_json::Value;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
 use std::{
     collections::{BTreeMap, HashSet},
     fmt, fs,
     fs::File,
-    io::Write,
-    path::PathBuf,
-    process::{exit, Command, Stdio},
+    io::{Read, Write},
+    path::{Component, Path, PathBuf},
+    process::{Command, Output, Stdio},
+    thread,
+    time::{Duration, Instant},
 };
 
-#[derive(Debug, Clone)]
+type Result<T, E = ZkCompilationError> = std::result::Result<T, E>;
+
+/// The time a zksolc process may take to compile a single source with `ZkSolc::compile`.
+pub const DEFAULT_COMPILER_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// The interval at which a running zksolc process is checked for completion.
+const COMPILER_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Possible errors when compiling with zksolc
+///
+/// Every error is scoped to a single source, the compilation of the other sources carries on.
+#[derive(Debug, thiserror::Error)]
+pub enum ZkCompilationError {
+    /// A file does not exist, for example the artifacts of a source that was not compiled yet.
+    #[error("Could not find {} - did you run zk-build?", .0.display())]
+    FileNotFound(PathBuf),
+    /// A file or a directory could not be read or written.
+    #[error("{message} {}: {error}", .path.display())]
+    Io {
+        message: String,
+        path: PathBuf,
+        #[source]
+        error: std::io::Error,
+    },
+    /// zksolc exited with a non-zero status.
+    #[error(
+        "Compilation failed with {stderr:?} (exit code {exit_code:?}). Using compiler: {compiler:?}, with args {path:?} {args:?}"
+    )]
+    CompilerExited {
+        compiler: PathBuf,
+        path: PathBuf,
+        args: Vec<String>,
+        exit_code: Option<i32>,
+        stderr: String,
+    },
+    /// zksolc did not finish compiling the source within the timeout and was killed.
+    #[error(
+        "Compilation timed out after {timeout:?}, zksolc was killed. Using compiler: {compiler:?}, with args {path:?} {args:?}"
+    )]
+    TimedOut { compiler: PathBuf, path: PathBuf, args: Vec<String>, timeout: Duration },
+    /// A JSON document, like the standard JSON input or the output of zksolc, could not be
+    /// serialized or parsed.
+    #[error("{message}: {error}")]
+    Json {
+        message: String,
+        #[source]
+        error: serde_json::Error,
+    },
+    /// zksolc reported errors for the source, they are listed with their source location.
+    #[error("Compiler run completed with errors")]
+    Compilation(Vec<ZkSolcDiagnostic>),
+    /// The sources or the standard JSON input could not be prepared for zksolc.
+    #[error("{0}")]
+    Setup(String),
+    /// The output of zksolc does not contain what was expected.
+    #[error("{0}")]
+    InvalidOutput(String),
+    /// The sources import each other in a cycle, listed from the first source of the cycle back to
+    /// it.
+    #[error("Circular import detected: {}", format_import_cycle(.0))]
+    CircularImport(Vec<PathBuf>),
+    /// The compiled artifacts were requested before the contracts were compiled.
+    #[error("The contracts have not been compiled yet, call ZkSolc::compile first")]
+    NotCompiled,
+}
+
+impl ZkCompilationError {
+    fn io(message: &str, path: impl Into<PathBuf>, error: std::io::Error) -> Self {
+        Self::Io { message: message.to_string(), path: path.into(), error }
+    }
+
+    fn json(message: impl Into<String>, error: serde_json::Error) -> Self {
+        Self::Json { message: message.into(), error }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct ZkSolcOpts {
     pub compiler_path: PathBuf,
     pub is_system: bool,
     pub force_evmla: bool,
+    /// Compile through the Yul IR pipeline, mutually exclusive with `force_evmla`.
+    pub via_ir: bool,
+    /// The zksolc optimization mode, one of `0`, `1`, `2`, `3`, `s` or `z`. Enables the optimizer if set.
+    pub optimizer_mode: Option<char>,
+    /// Fall back to optimizing for size if the bytecode of a contract is too large.
+    pub optimize_for_size: bool,
+    /// The hash of the metadata appended to the bytecode, one of `none`, `ipfs` or `bzzr1`. `none`
+    /// produces bytecode which doesn't change with the metadata, zksolc's default is used if `None`.
+    pub metadata_hash: Option<String>,
+    /// The directory compiler outputs are cached in, caching is disabled if `None`.
+    pub cache_dir: Option<PathBuf>,
+    /// Ignore the cached compiler outputs and compile every source again.
+    pub force: bool,
+    /// Don't print the diagnostics and progress of the compilation, they are only collected in the
+    /// `ZkSourceOutcome` of every source.
+    pub silent: bool,
+    /// The number of zksolc processes to run in parallel, defaults to the number of logical CPUs.
+    pub jobs: Option<usize>,
+    /// The directory zksolc writes its intermediate artifacts to, like Yul, LLVM IR and EraVM
+    /// assembly.
+    pub debug_output_dir: Option<PathBuf>,
+    /// The codes of the warnings to suppress, like `ignored_error_codes` of the config.
+    pub ignored_error_codes: Vec<u64>,
+    /// Don't print any warning. Unlike `ignored_error_codes`, the warnings are still collected in
+    /// the `ZkSourceOutcome` of every source, so that they can be counted.
+    pub suppress_warnings: bool,
+    /// Additional output selectors to request from zksolc, like `storageLayout` or `devdoc`. They
+    /// are included in the compiler output saved in `artifacts.json`.
+    pub extra_output: Vec<String>,
+    /// Additional output selectors to request from zksolc, which are also written to a separate
+    /// `<contract>.<selector>.json` file next to `artifacts.json`.
+    pub extra_output_files: Vec<String>,
+    /// The directory the artifacts of the compiled contracts are written to in the layout of
+    /// Foundry artifacts, as `<ContractName>.json`. Not written if `None`.
+    pub out_dir: Option<PathBuf>,
+    /// The directory the artifacts of the compiled contracts are also written to in the layout of
+    /// Hardhat artifacts, as `<path>/<ContractName>.json`. Not written if `None`.
+    pub hardhat_artifacts_dir: Option<PathBuf>,
+    /// The path of the compilation database to write, listing the invocation of zksolc for every
+    /// source, for IDEs and language servers. Not written if `None`.
+    pub compilation_db: Option<PathBuf>,
+    /// Request the AST of every source from zksolc and write it to the `ast` directory of
+    /// `out_dir`, named after the path of the source relative to the sources directory.
+    pub ast: bool,
+    /// Request the storage layout of every contract from zksolc, it is included in the Foundry
+    /// artifacts of the contracts.
+    pub storage_layout: bool,
+    /// Request the NatSpec `devdoc` and `userdoc` of every contract from zksolc, they are
+    /// included in the Foundry artifacts of the contracts.
+    pub natspec: bool,
+    /// The directory relative imports and sources are resolved against, passed to zksolc as
+    /// `--base-path`, so that they resolve the same way regardless of the working directory. If
+    /// `None`, the project root is only passed as the base path together with `include_paths`.
+    pub base_path: Option<PathBuf>,
+    /// Additional directories searched when resolving imports, passed to zksolc as
+    /// `--include-path`.
+    pub include_paths: Vec<PathBuf>,
+    /// The directories imports may be read from besides the base path, like the targets of
+    /// symlinked libraries, passed to zksolc as `--allow-paths`.
+    pub allow_paths: Vec<PathBuf>,
+}
+
+impl ZkSolcOpts {
+    /// Returns the zksolc specific entries of the `settings` object of the standard JSON input.
+    ///
+    /// These entries are not known to `solc` and are therefore not part of the settings generated
+    /// by the project, they are merged into the standard JSON input by `parse_json_input`.
+    /// Flags that are not set are omitted, leaving zksolc to its defaults.
+    ///
+    /// The `optimizer` entry only contains the zksolc specific optimizer settings, it is merged into
+    /// the `optimizer` object generated by the project rather than replacing it.
+    pub fn zksync_settings(&self) -> Map<String, Value> {
+        let mut settings = Map::new();
+        if self.force_evmla {
+            settings.insert("forceEVMLA".to_string(), Value::Bool(true));
+        }
+        if self.via_ir {
+            settings.insert("viaIR".to_string(), Value::Bool(true));
+        }
+
+        let mut optimizer = Map::new();
+        if let Some(mode) = self.optimizer_mode {
+            optimizer.insert("enabled".to_string(), Value::Bool(true));
+            optimizer.insert("mode".to_string(), Value::String(mode.to_string()));
+        }
+        if self.optimize_for_size {
+            optimizer.insert("fallbackToOptimizingForSize".to_string(), Value::Bool(true));
+        }
+        if !optimizer.is_empty() {
+            settings.insert("optimizer".to_string(), Value::Object(optimizer));
+        }
+
+        if let Some(metadata_hash) = &self.metadata_hash {
+            settings.insert(
+                "metadata".to_string(),
+                serde_json::json!({ "bytecodeHash": metadata_hash }),
+            );
+        }
+        settings
+    }
+}
+
+/// `ZkSolcOptsBuilder` builds the `ZkSolcOpts` of a compilation.
+///
+/// Every option starts from its default and has a `with_*` setter, which returns the builder so
+/// that the setters can be chained. `build` checks that the required options are set and that they
+/// are consistent, so that adding an option doesn't break the existing call sites.
+///
+/// # Example
+///
+/// ```
+/// let opts = ZkSolcOptsBuilder::default()
+///     .with_compiler_path("/path/to/zksolc")
+///     .with_optimizer_mode(Some('3'))
+///     .build()?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ZkSolcOptsBuilder {
+    compiler_path: Option<PathBuf>,
+    opts: ZkSolcOpts,
+}
+
+impl ZkSolcOptsBuilder {
+    /// Sets the path of the zksolc binary, which is required.
+    pub fn with_compiler_path(&mut self, compiler_path: impl Into<PathBuf>) -> &mut Self {
+        self.compiler_path = Some(compiler_path.into());
+        self
+    }
+
+    /// Sets whether to compile in the system mode, see `ZkSolcOpts::is_system`.
+    pub fn with_is_system(&mut self, is_system: bool) -> &mut Self {
+        self.opts.is_system = is_system;
+        self
+    }
+
+    /// Sets whether to compile through the EVM legacy assembly pipeline.
+    pub fn with_force_evmla(&mut self, force_evmla: bool) -> &mut Self {
+        self.opts.force_evmla = force_evmla;
+        self
+    }
+
+    /// Sets whether to compile through the Yul IR pipeline.
+    pub fn with_via_ir(&mut self, via_ir: bool) -> &mut Self {
+        self.opts.via_ir = via_ir;
+        self
+    }
+
+    /// Sets the zksolc optimization mode, see `ZkSolcOpts::optimizer_mode`.
+    pub fn with_optimizer_mode(&mut self, optimizer_mode: Option<char>) -> &mut Self {
+        self.opts.optimizer_mode = optimizer_mode;
+        self
+    }
+
+    /// Sets whether to fall back to optimizing for size if a bytecode is too large.
+    pub fn with_optimize_for_size(&mut self, optimize_for_size: bool) -> &mut Self {
+        self.opts.optimize_for_size = optimize_for_size;
+        self
+    }
+
+    /// Sets the hash of the metadata appended to the bytecode.
+    pub fn with_metadata_hash(&mut self, metadata_hash: Option<String>) -> &mut Self {
+        self.opts.metadata_hash = metadata_hash;
+        self
+    }
+
+    /// Sets the directory compiler outputs are cached in.
+    pub fn with_cache_dir(&mut self, cache_dir: Option<PathBuf>) -> &mut Self {
+        self.opts.cache_dir = cache_dir;
+        self
+    }
+
+    /// Sets whether to ignore the cached compiler outputs.
+    pub fn with_force(&mut self, force: bool) -> &mut Self {
+        self.opts.force = force;
+        self
+    }
+
+    /// Sets whether to print the diagnostics and progress of the compilation.
+    pub fn with_silent(&mut self, silent: bool) -> &mut Self {
+        self.opts.silent = silent;
+        self
+    }
+
+    /// Sets the number of zksolc processes to run in parallel.
+    pub fn with_jobs(&mut self, jobs: Option<usize>) -> &mut Self {
+        self.opts.jobs = jobs;
+        self
+    }
+
+    /// Sets the directory zksolc writes its intermediate artifacts to.
+    pub fn with_debug_output_dir(&mut self, debug_output_dir: Option<PathBuf>) -> &mut Self {
+        self.opts.debug_output_dir = debug_output_dir;
+        self
+    }
+
+    /// Sets the codes of the warnings to suppress.
+    pub fn with_ignored_error_codes(&mut self, ignored_error_codes: Vec<u64>) -> &mut Self {
+        self.opts.ignored_error_codes = ignored_error_codes;
+        self
+    }
+
+    /// Sets whether to print warnings.
+    pub fn with_suppress_warnings(&mut self, suppress_warnings: bool) -> &mut Self {
+        self.opts.suppress_warnings = suppress_warnings;
+        self
+    }
+
+    /// Sets the additional output selectors to request from zksolc.
+    pub fn with_extra_output(&mut self, extra_output: Vec<String>) -> &mut Self {
+        self.opts.extra_output = extra_output;
+        self
+    }
+
+    /// Sets the additional output selectors also written to separate files.
+    pub fn with_extra_output_files(&mut self, extra_output_files: Vec<String>) -> &mut Self {
+        self.opts.extra_output_files = extra_output_files;
+        self
+    }
+
+    /// Sets the directory the Foundry artifacts are written to.
+    pub fn with_out_dir(&mut self, out_dir: Option<PathBuf>) -> &mut Self {
+        self.opts.out_dir = out_dir;
+        self
+    }
+
+    /// Sets the directory the Hardhat artifacts are written to.
+    pub fn with_hardhat_artifacts_dir(
+        &mut self,
+        hardhat_artifacts_dir: Option<PathBuf>,
+    ) -> &mut Self {
+        self.opts.hardhat_artifacts_dir = hardhat_artifacts_dir;
+        self
+    }
+
+    /// Sets the path of the compilation database to write.
+    pub fn with_compilation_db(&mut self, compilation_db: Option<PathBuf>) -> &mut Self {
+        self.opts.compilation_db = compilation_db;
+        self
+    }
+
+    /// Sets whether to request and write the AST of every source.
+    pub fn with_ast(&mut self, ast: bool) -> &mut Self {
+        self.opts.ast = ast;
+        self
+    }
+
+    /// Sets whether to request the storage layout of every contract.
+    pub fn with_storage_layout(&mut self, storage_layout: bool) -> &mut Self {
+        self.opts.storage_layout = storage_layout;
+        self
+    }
+
+    /// Sets whether to request the NatSpec of every contract.
+    pub fn with_natspec(&mut self, natspec: bool) -> &mut Self {
+        self.opts.natspec = natspec;
+        self
+    }
+
+    /// Sets the directory relative imports and sources are resolved against.
+    pub fn with_base_path(&mut self, base_path: Option<PathBuf>) -> &mut Self {
+        self.opts.base_path = base_path;
+        self
+    }
+
+    /// Sets the additional directories searched when resolving imports.
+    pub fn with_include_paths(&mut self, include_paths: Vec<PathBuf>) -> &mut Self {
+        self.opts.include_paths = include_paths;
+        self
+    }
+
+    /// Sets the directories imports may be read from besides the base path.
+    pub fn with_allow_paths(&mut self, allow_paths: Vec<PathBuf>) -> &mut Self {
+        self.opts.allow_paths = allow_paths;
+        self
+    }
+
+    /// Returns the `ZkSolcOpts` with the options set on the builder.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ZkCompilationError::Setup` if the compiler path is not set, or if both `via_ir`
+    /// and `force_evmla` are set.
+    pub fn build(&self) -> Result<ZkSolcOpts> {
+        let compiler_path = self.compiler_path.clone().ok_or_else(|| {
+            ZkCompilationError::Setup("The path of the zksolc binary is not set".to_string())
+        })?;
+        if self.opts.via_ir && self.opts.force_evmla {
+            return Err(ZkCompilationError::Setup(
+                "`via_ir` and `force_evmla` are mutually exclusive, the Yul IR and the EVM legacy assembly pipelines cannot both be used".to_string(),
+            ));
+        }
+        Ok(ZkSolcOpts { compiler_path, ..self.opts.clone() })
+    }
+}
+
+/// The outcome of compiling a single source file with zksolc.
+///
+/// `error` is `None` if the source compiled successfully, otherwise it holds the reason why the
+/// compilation of this source failed as a `ZkCompilationError`. `contracts` holds the contracts
+/// defined in the source if it compiled successfully, and `diagnostics` the errors and warnings
+/// reported by the compiler.
+#[derive(Debug, Default)]
+pub struct ZkSourceOutcome {
+    pub source: PathBuf,
+    pub error: Option<ZkCompilationError>,
+    pub contracts: Vec<ZkCompiledContract>,
+    pub diagnostics: Vec<ZkSolcDiagnostic>,
+}
+
+/// The output of compiling a project with zksolc.
+///
+/// It holds the outcome of every compiled source, in the order they were compiled, including the
+/// ones that failed. The compiled contracts and the diagnostics of all the sources can be accessed
+/// through `contracts`, `errors` and `warnings`, so that the output can be consumed without
+/// parsing what is printed during the compilation.
+#[derive(Debug, Default)]
+pub struct ZkCompilationOutput {
+    pub sources: Vec<ZkSourceOutcome>,
+}
+
+impl ZkCompilationOutput {
+    /// Returns an iterator over the contracts of all the sources that compiled successfully.
+    pub fn contracts(&self) -> impl Iterator<Item = &ZkCompiledContract> {
+        self.sources.iter().flat_map(|outcome| &outcome.contracts)
+    }
+
+    /// Returns an iterator over the sources that failed to compile.
+    pub fn failed(&self) -> impl Iterator<Item = &ZkSourceOutcome> {
+        self.sources.iter().filter(|outcome| !outcome.is_success())
+    }
+
+    /// Returns the compiled contract with the identifier `<path>:<contractname>`, or with the name
+    /// `<contractname>` if it's defined in a single source.
+    pub fn find_contract(&self, name: &str) -> Option<&ZkCompiledContract> {
+        find_compiled_contract(self.contracts(), name)
+    }
+
+    /// Returns an iterator over the errors reported by zksolc for all the sources.
+    pub fn errors(&self) -> impl Iterator<Item = &ZkSolcDiagnostic> {
+        self.diagnostics().filter(|diagnostic| !diagnostic.is_warning())
+    }
+
+    /// Returns an iterator over the warnings reported by zksolc for all the sources.
+    pub fn warnings(&self) -> impl Iterator<Item = &ZkSolcDiagnostic> {
+        self.diagnostics().filter(|diagnostic| diagnostic.is_warning())
+    }
+
+    fn diagnostics(&self) -> impl Iterator<Item = &ZkSolcDiagnostic> {
+        self.sources.iter().flat_map(|outcome| &outcome.diagnostics)
+    }
+}
+
+/// This function returns the contract of `contracts` with the identifier `<path>:<contractname>`,
+/// or with the name `<contractname>` if a single one of them has this name.
+fn find_compiled_contract<'a>(
+    mut contracts: impl Iterator<Item = &'a ZkCompiledContract>,
+    name: &str,
+) -> Option<&'a ZkCompiledContract> {
+    if name.contains(':') {
+        return contracts.find(|contract| contract.name == name);
+    }
+    let mut matches = contracts.filter(|contract| contract.contract_name() == name);
+    match (matches.next(), matches.next()) {
+        (Some(contract), None) => Some(contract),
+        _ => None,
+    }
+}
+
+/// A source prepared for the compilation with zksolc, see `ZkSolc::prepare_source`.
+#[derive(Debug)]
+struct ZkCompilationJob {
+    contract_path: PathBuf,
+    filename: String,
+    standard_json: Value,
+    comp_args: Vec<String>,
+    cache_key: String,
+}
+
+/// A contract compiled with zksolc.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ZkCompiledContract {
+    /// The contract identifier in the form `<path>:<contractname>`.
+    pub name: String,
+    /// The hex encoded zkEVM bytecode of the contract.
+    pub bytecode: String,
+    /// The hex encoded deployed zkEVM bytecode of the contract. On zkSync the constructor is part
+    /// of the deployed bytecode, so it's the same as `bytecode` unless zksolc reports otherwise.
+    pub deployed_bytecode: String,
+    /// The ABI of the contract.
+    pub abi: Value,
+    /// The source map of the bytecode, mapping bytecode offsets to positions in the Solidity
+    /// sources, if the compiler emitted one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_map: Option<String>,
+    /// The selectors of the functions of the contract, keyed by their signature.
+    #[serde(skip)]
+    pub method_identifiers: Value,
+    /// The metadata of the contract, as emitted by the compiler.
+    #[serde(skip)]
+    pub metadata: Value,
+    /// The storage layout of the contract, if it was requested from the compiler.
+    #[serde(skip)]
+    pub storage_layout: Option<StorageLayout>,
+    /// The NatSpec developer documentation of the contract, if it was requested from the compiler.
+    #[serde(skip)]
+    pub devdoc: Option<Value>,
+    /// The NatSpec user documentation of the contract, if it was requested from the compiler.
+    #[serde(skip)]
+    pub userdoc: Option<Value>,
+}
+
+/// The in-memory artifact of a contract compiled with zksolc, returned by `ZkSolc::get_artifact`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ZkContractArtifact {
+    /// The ABI of the contract.
+    pub abi: Value,
+    /// The hex encoded zkEVM bytecode of the contract.
+    pub bytecode: String,
+    /// The hex encoded deployed zkEVM bytecode of the contract.
+    pub deployed_bytecode: String,
+    /// The source map of the bytecode, if the compiler emitted one.
+    pub source_map: Option<String>,
+    /// The selectors of the functions of the contract, keyed by their signature.
+    pub method_identifiers: Value,
+}
+
+/// An error or a warning reported by zksolc.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ZkSolcDiagnostic {
+    /// The severity of the diagnostic, either "error" or "warning".
+    pub severity: String,
+    /// The message of the diagnostic.
+    pub message: String,
+    /// The source file the diagnostic refers to, if any.
+    pub source_file: Option<String>,
+    /// The byte offset the diagnostic starts at in `source_file`, `-1` if unknown.
+    pub start: i64,
+    /// The byte offset the diagnostic ends at in `source_file`, `-1` if unknown.
+    pub end: i64,
+}
+
+impl ZkCompiledContract {
+    /// Returns the name of the contract, without the path of its source.
+    pub fn contract_name(&self) -> &str {
+        self.name.rsplit_once(':').map(|(_, name)| name).unwrap_or(&self.name)
+    }
+
+    /// Returns the size of the zkEVM bytecode of the contract, in bytes.
+    pub fn bytecode_size(&self) -> usize {
+        self.bytecode.trim_start_matches("0x").len() / 2
+    }
+
+    /// Returns the in-memory artifact of the contract.
+    pub fn to_artifact(&self) -> ZkContractArtifact {
+        ZkContractArtifact {
+            abi: self.abi.clone(),
+            bytecode: self.bytecode.clone(),
+            deployed_bytecode: self.deployed_bytecode.clone(),
+            source_map: self.source_map.clone(),
+            method_identifiers: self.method_identifiers.clone(),
+        }
+    }
+
+    /// Returns the artifact of the contract in the layout of Foundry artifacts, with the `abi`,
+    /// `bytecode`, `deployedBytecode`, `methodIdentifiers` and `metadata` fields.
+    ///
+    /// zksolc links libraries through `settings.libraries`, so there are no link references.
+    pub fn to_foundry_artifact(&self) -> Value {
+        let mut artifact = serde_json::json!({
+            "abi": self.abi,
+            "bytecode": {
+                "object": self.bytecode,
+                "sourceMap": self.source_map,
+                "linkReferences": {},
+            },
+            "deployedBytecode": {
+                "object": self.deployed_bytecode,
+                "sourceMap": self.source_map,
+                "linkReferences": {},
+            },
+            "methodIdentifiers": self.method_identifiers,
+            "metadata": self.metadata,
+        });
+        if let Some(storage_layout) = &self.storage_layout {
+            artifact["storageLayout"] = serde_json::json!(storage_layout);
+        }
+        if let Some(devdoc) = &self.devdoc {
+            artifact["devdoc"] = devdoc.clone();
+        }
+        if let Some(userdoc) = &self.userdoc {
+            artifact["userdoc"] = userdoc.clone();
+        }
+        artifact
+    }
+
+    /// Returns the artifact of the contract in the layout of Hardhat artifacts, with the
+    /// `contractName`, `sourceName`, `abi`, `bytecode` and `deployedBytecode` fields.
+    ///
+    /// The bytecodes are the 0x-prefixed zkEVM bytecodes, so that Hardhat deploy scripts and
+    /// ethers.js tests deploy the contract compiled with zksolc.
+    pub fn to_hardhat_artifact(&self) -> Value {
+        let source_name = self.name.rsplit_once(':').map(|(source, _)| source).unwrap_or_default();
+        serde_json::json!({
+            "_format": "hh-sol-artifact-1",
+            "contractName": self.contract_name(),
+            "sourceName": source_name,
+            "abi": self.abi,
+            "bytecode": format!("0x{}", self.bytecode.trim_start_matches("0x")),
+            "deployedBytecode": format!("0x{}", self.deployed_bytecode.trim_start_matches("0x")),
+            "linkReferences": {},
+            "deployedLinkReferences": {},
+        })
+    }
+
+    /// Returns true if the contract is a test or a script, whose size does not matter.
+    pub fn is_dev_contract(&self) -> bool {
+        self.abi.as_array().into_iter().flatten().any(|item| {
+            item["type"] == "function" &&
+                item["name"].as_str().map_or(false, |name| {
+                    name.is_test() || name == "IS_TEST" || name == "IS_SCRIPT"
+                })
+        })
+    }
+}
+
+impl ZkSolcDiagnostic {
+    /// Returns `true` if the diagnostic is a warning.
+    pub fn is_warning(&self) -> bool {
+        self.severity.eq_ignore_ascii_case("warning")
+    }
+
+    /// Renders the diagnostic like rustc does, with a colored severity prefix and, if it has a
+    /// source location, the `file:line:col` location and the source line with the range of the
+    /// diagnostic underlined with `^`.
+    ///
+    /// The source file is read relative to `root`. If the diagnostic has no source location, or
+    /// its source cannot be read, only the message and the file are rendered.
+    pub fn render(&self, root: &Path) -> String {
+        let color = if self.is_warning() { Yellow } else { Red };
+        let mut rendered =
+            format!("{}: {}", color.bold().paint(self.severity.to_lowercase()), self.message);
+        let file = match &self.source_file {
+            Some(file) => file,
+            None => return rendered,
+        };
+        let source = fs::read_to_string(root.join(file)).unwrap_or_default();
+        let snippet = match source_snippet(&source, self.start, self.end) {
+            Some(snippet) => snippet,
+            None => {
+                rendered.push_str(&format!("\n --> {file}"));
+                return rendered;
+            }
+        };
+
+        let gutter = " ".repeat(snippet.line_number.to_string().len());
+        rendered.push_str(&format!(
+            "\n{gutter}--> {}:{}:{}\n{gutter} |\n{} | {}\n{gutter} | {}{}",
+            file,
+            snippet.line_number,
+            snippet.padding.chars().count() + 1,
+            snippet.line_number,
+            snippet.line,
+            snippet.padding,
+            color.bold().paint("^".repeat(snippet.width))
+        ));
+        rendered
+    }
+}
+
+impl fmt::Display for ZkSolcDiagnostic {
+    /// Formats the severity and the message of the diagnostic, followed by its source file if it
+    /// has one. The source snippet is only rendered by `render`, which knows the project root the
+    /// source file is relative to.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.message)?;
+        if let Some(file) = &self.source_file {
+            write!(f, "\n --> {file}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The source line a `ZkSolcDiagnostic` refers to, see `source_snippet`.
+#[derive(Debug, PartialEq)]
+struct ZkSourceSnippet<'a> {
+    /// The 1-based number of the line.
+    line_number: usize,
+    /// The text of the line.
+    line: &'a str,
+    /// The whitespace before the start of the diagnostic in the line, keeping tabs so that the
+    /// underline is aligned.
+    padding: String,
+    /// The number of characters to underline, at least 1.
+    width: usize,
+}
+
+impl ZkSourceOutcome {
+    /// Returns `true` if the source compiled without errors.
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
 }
 
 /// This struct represents the ZkSolc compiler for compiling Solidity contracts.
@@ -58,9 +753,8 @@ pub struct ZkSolcOpts {
 ///
 /// Struct Members:
 /// - `project`: Represents the project details and configurations.
-/// - `compiler_path`: The path to the ZkSolc compiler executable.
-/// - `is_system`: A flag indicating whether the compiler is in system mode.
-/// - `force_evmla`: A flag indicating whether to force EVMLA optimization.
+/// - `opts`: The `ZkSolcOpts` the compiler was configured with, such as the path to the ZkSolc
+///   compiler executable, the system mode and the force EVMLA flags.
 /// - `standard_json`: An optional field to store the parsed standard JSON input for the contracts.
 /// - `sources`: An optional field to store the versioned sources for the contracts.
 ///
@@ -71,8 +765,8 @@ pub struct ZkSolcOpts {
 ///   and its subdirectories.
 ///
 /// Error Handling:
-/// - The methods in this struct return the `Result` type from the `anyhow` crate for flexible
-///   and easy-to-use error handling.
+/// - The methods in this struct return a `ZkCompilationError` on failure, which tells what went wrong
+///   with the relevant data, like the diagnostics reported by zksolc.
 ///
 /// Example Usage:
 /// ```rust
@@ -98,11 +792,10 @@ pub struct ZkSolcOpts {
 #[derive(Debug)]
 pub struct ZkSolc {
     project: Project,
-    compiler_path: PathBuf,
-    is_system: bool,
-    force_evmla: bool,
-    standard_json: Option<StandardJsonCompilerInput>,
+    opts: ZkSolcOpts,
+    standard_json: Option<Value>,
     sources: Option<BTreeMap<Solc, (Version, BTreeMap<PathBuf, Source>)>>,
+    compiled: Option<Vec<ZkCompiledContract>>,
 }
 
 impl fmt::Display for ZkSolc {
@@ -113,7 +806,7 @@ impl fmt::Display for ZkSolc {
                 compiler_path: {},   
                 output_path: {},
             )",
-            self.compiler_path.display(),
+            self.opts.compiler_path.display(),
             self.project.paths.artifacts.display(),
         )
     }
@@ -121,14 +814,21 @@ impl fmt::Display for ZkSolc {
 
 impl ZkSolc {
     pub fn new(opts: ZkSolcOpts, project: Project) -> Self {
-        Self {
-            project,
-            compiler_path: opts.compiler_path,
-            is_system: opts.is_system,
-            force_evmla: opts.force_evmla,
-            standard_json: None,
-            sources: None,
-        }
+        Self { project, opts, standard_json: None, sources: None, compiled: None }
+    }
+
+    /// Returns the artifact of the contract with the identifier `<path>:<contractname>`, or with the
+    /// name `<contractname>` if it's defined in a single source, from the last call to `compile`.
+    ///
+    /// The artifact is kept in memory, so it is returned without reading the artifacts written to
+    /// disk. `None` is returned if no compiled contract has this name.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ZkCompilationError::NotCompiled` if `compile` was not called yet.
+    pub fn get_artifact(&self, contract_name: &str) -> Result<Option<ZkContractArtifact>> {
+        let compiled = self.compiled.as_ref().ok_or(ZkCompilationError::NotCompiled)?;
+        Ok(find_compiled_contract(compiled.iter(), contract_name).map(|c| c.to_artifact()))
     }
 
     /// Compiles the Solidity contracts in the project's 'sources' directory and its subdirectories
@@ -136,7 +836,8 @@ impl ZkSolc {
     ///
     /// # Arguments
     ///
-    /// * `self` - A mutable reference to the `ZkSolc` instance.
+    /// * `self` - A mutable reference to the `ZkSolc` instance, which keeps the compiled contracts for
+    ///   `get_artifact`.
     ///
     /// # Errors
     ///
@@ -150,28 +851,32 @@ impl ZkSolc {
     ///
     /// ```rust
     /// let project = Project::new(...);
-    /// let opts = ZkSolcOpts {
-    ///     compiler_path: PathBuf::from("/path/to/zksolc"),
-    ///     is_system: false,
-    ///     force_evmla: true,
-    /// };
+    /// let opts = ZkSolcOptsBuilder::default()
+    ///     .with_compiler_path("/path/to/zksolc")
+    ///     .with_force_evmla(true)
+    ///     .with_optimizer_mode(Some('3'))
+    ///     .build()?;
     /// let mut zksolc = ZkSolc::new(opts, project);
     /// zksolc.compile()?;
     /// ```
     ///
-    /// In this example, a `ZkSolc` instance is created using `ZkSolcOpts` and a `Project`. Then, the
-    /// `compile` method is invoked to compile the contracts.
+    /// In this example, a `ZkSolc` instance is created using the `ZkSolcOpts` built by a
+    /// `ZkSolcOptsBuilder` and a `Project`. Then, the `compile` method is invoked to compile the
+    /// contracts.
     ///
     /// # Workflow
     ///
     /// The `compile` function performs the following operations:
     ///
     /// 1. Collect Source Files:
-    ///    - It collects the source files from the project's 'sources' directory and its subdirectories.
-    ///    - Only the files within the 'sources' directory and its subdirectories are considered for compilation.
+    ///    - It collects the source files from the project's 'sources' directory and its
+    ///      subdirectories.
+    ///    - Only the files within the 'sources' directory and its subdirectories are considered for
+    ///      compilation.
     ///
     /// 2. Configure Solidity Compiler:
-    ///    - It configures the Solidity compiler by setting options like the compiler path, system mode, and force EVMLA flag.
+    ///    - It configures the Solidity compiler by setting options like the compiler path, system
+    ///      mode, and force EVMLA flag.
     ///
     /// 3. Parse JSON Input:
     ///    - For each source file, it parses the JSON input using the Solidity compiler.
@@ -179,10 +884,16 @@ impl ZkSolc {
     ///
     /// 4. Build Compiler Arguments:
     ///    - It builds the compiler arguments for each source file.
-    ///    - The compiler arguments include options like the compiler path, system mode, and force EVMLA flag.
+    ///    - The compiler arguments include options like the solc compiler path and the system mode
+    ///      flag.
+    ///
+    ///    - With `compilation_db`, the invocation of the compiler for every source is written to a
+    ///      compilation database.
     ///
     /// 5. Run Compiler and Handle Output:
-    ///    - It runs the Solidity compiler for each source file with the corresponding compiler arguments.
+    ///    - It runs the Solidity compiler for each source file with the corresponding compiler
+    ///      arguments.
+    ///    - The sources are compiled in parallel, running up to `jobs` compiler processes at once.
     ///    - The output of the compiler, including errors and warnings, is captured.
     ///
     /// 6. Handle Output (Errors and Warnings):
@@ -191,19 +902,50 @@ impl ZkSolc {
     ///
     /// 7. Save Artifacts:
     ///    - It saves the artifacts (compiler output) as a JSON file for each source file.
-    ///    - The artifacts are saved in the project's artifacts directory under the corresponding source file's directory.
+    ///    - The artifacts are saved in the project's artifacts directory under the corresponding
+    ///      source file's directory.
+    ///    - The artifacts of the compiled contracts are also written in the layout of Foundry
+    ///      artifacts to `out_dir`, and of Hardhat artifacts to `hardhat_artifacts_dir`.
     ///
     /// # Note
     ///
-    /// The `compile` function modifies the `ZkSolc` instance to store the parsed JSON input and the versioned sources.
-    /// These modified values can be accessed after the compilation process for further processing or analysis.
-    pub fn compile(mut self) -> Result<()> {
+    /// The `compile` function modifies the `ZkSolc` instance to store the parsed JSON input and the
+    /// versioned sources. These modified values can be accessed after the compilation process for
+    /// further processing or analysis.
+    ///
+    /// Every zksolc process is killed if it takes longer than `DEFAULT_COMPILER_TIMEOUT`, see
+    /// `compile_with_timeout`.
+    pub fn compile(&mut self) -> Result<ZkCompilationOutput> {
+        self.compile_with_timeout(DEFAULT_COMPILER_TIMEOUT)
+    }
+
+    /// Compiles the sources like `compile`, killing every zksolc process which takes longer than
+    /// `timeout` to compile its source.
+    ///
+    /// zksolc can hang on pathological inputs, so without a timeout the compilation could block
+    /// forever. A source whose compilation timed out fails with `ZkCompilationError::TimedOut`,
+    /// the other sources are still compiled.
+    pub fn compile_with_timeout(&mut self, timeout: Duration) -> Result<ZkCompilationOutput> {
         // Step 1: Collect Source Files
         self.configure_solc();
+        if let Some(debug_output_dir) = &self.opts.debug_output_dir {
+            fs::create_dir_all(debug_output_dir).map_err(|e| {
+                ZkCompilationError::io(
+                    "Could not create debug output directory",
+                    debug_output_dir,
+                    e,
+                )
+            })?;
+        }
         let sources = self.sources.clone().unwrap();
         let mut displayed_warnings = HashSet::new();
+        let mut output = ZkCompilationOutput::default();
 
-        // Step 2: Compile Contracts for Each Source
+        // The cached outputs are only valid for the exact compiler binary which produced them
+        let compiler_digest = compiler_digest(&self.opts.compiler_path)?;
+
+        // Steps 2 to 4 configure the project for each source, so they are performed sequentially
+        let mut jobs = Vec::new();
         for (solc, version) in sources {
             //configure project solc for each solc version
             for source in version.1 {
@@ -219,67 +961,275 @@ impl ZkSolc {
                     continue;
                 }
 
-                // Step 3: Parse JSON Input for each Source
-                if let Err(err) = self.parse_json_input(contract_path.clone()) {
-                    eprintln!("Failed to parse json input for zksolc compiler: {}", err);
-                }
+                jobs.push((contract_path, self.prepare_source(&solc, source, &compiler_digest)));
+            }
+        }
 
-                // Step 4: Build Compiler Arguments
-                let comp_args = self.build_compiler_args(source.clone(), solc.clone());
-
-                // Step 5: Run Compiler and Handle Output
-                let mut cmd = Command::new(&self.compiler_path);
-                let mut child = cmd
-                    .arg(contract_path.clone())
-                    .args(&comp_args)
-                    .stdin(Stdio::piped())
-                    .stderr(Stdio::piped())
-                    .stdout(Stdio::piped())
-                    .spawn();
-                let stdin = child.as_mut().unwrap().stdin.take().expect("Stdin exists.");
-
-                serde_json::to_writer(stdin, &self.standard_json.clone().unwrap()).map_err(
-                    |e| Error::msg(format!("Could not assign standard_json to writer: {}", e)),
-                )?;
-
-                let output = child
-                    .unwrap()
-                    .wait_with_output()
-                    .map_err(|e| Error::msg(format!("Could not run compiler cmd: {}", e)))?;
-
-                if !output.status.success() {
-                    return Err(Error::msg(format!(
-                        "Compilation failed with {:?}. Using compiler: {:?}, with args {:?} {:?}",
-                        String::from_utf8(output.stderr).unwrap_or_default(),
-                        self.compiler_path,
-                        contract_path,
-                        &comp_args
-                    )));
-                }
+        // Write the invocation of zksolc for every prepared source to the compilation database
+        if let Some(compilation_db) = &self.opts.compilation_db {
+            self.write_compilation_db(compilation_db, &jobs)?;
+        }
+
+        // Step 5: Run Compiler for all the sources in parallel, a failure only affects that source
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(self.opts.jobs.unwrap_or_default())
+            .build()
+            .map_err(|e| {
+                ZkCompilationError::Setup(format!("Could not build compilation thread pool: {}", e))
+            })?;
+        let compiled: Vec<_> = pool.install(|| {
+            jobs.into_par_iter()
+                .map(|(contract_path, job)| {
+                    let compiled =
+                        job.and_then(|job| match self.read_cached_output(&job.cache_key) {
+                            Some(output_json) => Ok((job, output_json, true)),
+                            None => self
+                                .run_compiler(&job, timeout)
+                                .map(|output_json| (job, output_json, false)),
+                        });
+                    (contract_path, compiled)
+                })
+                .collect()
+        });
+
+        // Step 6: Handle Output, in the order of the sources so that the diagnostics are printed
+        // deterministically and warnings shared by several sources are only displayed once
+        for (contract_path, compiled) in compiled {
+            let mut outcome = ZkSourceOutcome { source: contract_path, ..Default::default() };
+            let result = compiled.and_then(|(job, output_json, cached)| {
+                self.finish_source(job, output_json, cached, &mut displayed_warnings, &mut outcome)
+            });
+            outcome.error = result.err();
+            output.sources.push(outcome);
+        }
+
+        // Step 7: Write the artifacts of the compiled contracts in the layout of Foundry
+        if let Some(out_dir) = &self.opts.out_dir {
+            write_foundry_artifacts(out_dir, &output)?;
+        }
+        if let Some(hardhat_artifacts_dir) = &self.opts.hardhat_artifacts_dir {
+            write_hardhat_artifacts(hardhat_artifacts_dir, &output)?;
+        }
+
+        // Step 8: Keep the compiled contracts and return the outcome of every compiled source
+        self.compiled = Some(output.contracts().cloned().collect());
+        Ok(output)
+    }
+
+    /// Prepares a single source file for the compilation with zksolc.
+    ///
+    /// This covers steps 3 and 4 of the `compile` workflow: parsing the JSON input and building the
+    /// compiler arguments. As it updates the settings of the project, it must not run concurrently
+    /// for several sources. Any error returned is scoped to the given source so that `compile` can
+    /// carry on with the remaining ones. `compiler_digest` is the digest of the compiler binary
+    /// the output is cached for, see `cache_key`.
+    fn prepare_source(
+        &mut self,
+        solc: &Solc,
+        source: (PathBuf, Source),
+        compiler_digest: &str,
+    ) -> Result<ZkCompilationJob> {
+        let contract_path = source.0.clone();
+
+        // Step 3: Parse JSON Input for each Source
+        self.parse_json_input(contract_path.clone())?;
+        let standard_json = self
+            .standard_json
+            .clone()
+            .ok_or_else(|| ZkCompilationError::Setup("Missing standard JSON input".to_string()))?;
 
-                let filename = contract_path
-                    .to_str()
-                    .expect("Unable to convert source to string")
-                    .split(
-                        self.project
-                            .paths
-                            .root
-                            .to_str()
-                            .expect("Unable to convert source to string"),
-                    )
-                    .nth(1)
-                    .expect("Failed to get Contract relative path")
-                    .split("/")
-                    .last()
-                    .expect("Failed to get Contract filename.");
-
-                // Step 6: Handle Output (Errors and Warnings)
-                self.handle_output(output, filename.to_string(), &mut displayed_warnings);
+        // Step 4: Build Compiler Arguments
+        let comp_args = self.build_compiler_args(source, solc.clone())?;
+
+        let filename = contract_path
+            .strip_prefix(&self.project.paths.root)
+            .ok()
+            .and_then(Path::file_name)
+            .and_then(|filename| filename.to_str())
+            .ok_or_else(|| {
+                ZkCompilationError::Setup(format!(
+                    "{} is not a source of the project in {}",
+                    contract_path.display(),
+                    self.project.paths.root.display()
+                ))
+            })?
+            .to_string();
+
+        let cache_key = Self::cache_key(compiler_digest, &comp_args, &standard_json)?;
+        Ok(ZkCompilationJob { contract_path, filename, standard_json, comp_args, cache_key })
+    }
+
+    /// Writes the compilation database of the prepared `jobs` to `path`, as a JSON array with the
+    /// `directory`, `file`, `arguments` and `command` of every invocation of zksolc, see
+    /// `compilation_db_entry`. The sources which could not be prepared are skipped.
+    fn write_compilation_db(
+        &self,
+        path: &Path,
+        jobs: &[(PathBuf, Result<ZkCompilationJob>)],
+    ) -> Result<()> {
+        let mut entries = Vec::new();
+        for job in jobs.iter().filter_map(|(_, job)| job.as_ref().ok()) {
+            let json_input =
+                self.build_artifacts_path(job.contract_path.clone())?.join("json_input.json");
+            entries.push(compilation_db_entry(
+                &self.opts.compiler_path,
+                &self.project.paths.root,
+                job,
+                &json_input,
+            ));
+        }
+
+        let compilation_db = serde_json::to_string_pretty(&entries)
+            .map_err(|e| ZkCompilationError::json("Could not serialize compilation database", e))?;
+        fs::write(path, compilation_db)
+            .map_err(|e| ZkCompilationError::io("Could not write compilation database", path, e))
+    }
+
+    /// Runs zksolc for a prepared source and returns its parsed output.
+    ///
+    /// This is step 5 of the `compile` workflow. It only reads from the `ZkSolc` instance, so that
+    /// it can run for several sources in parallel. zksolc is killed if it does not exit within
+    /// `timeout`.
+    fn run_compiler(&self, job: &ZkCompilationJob, timeout: Duration) -> Result<Value> {
+        let input = serde_json::to_vec(&job.standard_json)
+            .map_err(|e| ZkCompilationError::json("Could not assign standard_json to writer", e))?;
+
+        let mut cmd = Command::new(&self.opts.compiler_path);
+        cmd.arg(job.contract_path.clone()).args(&job.comp_args);
+        let output = match output_with_timeout(&mut cmd, input, timeout) {
+            Ok(Some(output)) => output,
+            Ok(None) => {
+                return Err(ZkCompilationError::TimedOut {
+                    compiler: self.opts.compiler_path.clone(),
+                    path: job.contract_path.clone(),
+                    args: job.comp_args.clone(),
+                    timeout,
+                })
+            }
+            Err(e) => {
+                return Err(ZkCompilationError::io(
+                    "Could not run compiler",
+                    &self.opts.compiler_path,
+                    e,
+                ))
             }
+        };
+
+        if !output.status.success() {
+            return Err(ZkCompilationError::CompilerExited {
+                compiler: self.opts.compiler_path.clone(),
+                path: job.contract_path.clone(),
+                args: job.comp_args.clone(),
+                exit_code: output.status.code(),
+                stderr: String::from_utf8(output.stderr).unwrap_or_default(),
+            });
         }
 
-        // Step 7: Return Ok if the compilation process completes without errors
-        Ok(())
+        // Deserialize the compiler output into a serde_json::Value object
+        let output_json: Value = serde_json::from_slice(&output.stdout)
+            .map_err(|e| ZkCompilationError::json("Could not parse zksolc compiler output", e))?;
+
+        Ok(output_json)
+    }
+
+    /// Handles the compiler output of a source and saves its artifacts.
+    ///
+    /// This is step 6 of the `compile` workflow. `cached` tells whether `output_json` was read from
+    /// the cache, in which case the source did not change since it was last compiled. Otherwise the
+    /// output is cached if the compilation succeeded. The compiled contracts and the diagnostics of
+    /// the source are collected in `outcome`.
+    fn finish_source(
+        &self,
+        job: ZkCompilationJob,
+        output_json: Value,
+        cached: bool,
+        displayed_warnings: &mut HashSet<String>,
+        outcome: &mut ZkSourceOutcome,
+    ) -> Result<()> {
+        if cached {
+            if !self.opts.silent {
+                println!("No changes in {}, using cached artifacts", job.filename);
+            }
+            self.handle_output(
+                output_json.clone(),
+                job.filename,
+                &job.contract_path,
+                displayed_warnings,
+                outcome,
+            )?;
+            return self.write_ast(&output_json, &job.contract_path);
+        }
+
+        self.handle_output(
+            output_json.clone(),
+            job.filename,
+            &job.contract_path,
+            displayed_warnings,
+            outcome,
+        )?;
+        self.write_ast(&output_json, &job.contract_path)?;
+
+        // Only successful compilations are cached, so that errors are reported again
+        self.write_cached_output(&job.cache_key, &output_json)
+    }
+
+    /// Computes the key the compiler output of a source is cached under.
+    ///
+    /// The key is the SHA-256 hash of everything that affects the output of zksolc: the digest of
+    /// the compiler binary, see `compiler_digest`, so that a compiler rebuilt or replaced at the
+    /// same path invalidates the cache, the compiler arguments, which contain the solc path and the
+    /// system mode flag, and the standard JSON input, which contains the content of the source and
+    /// of all its imports together with the settings from `ZkSolcOpts`. Changing any of them
+    /// results in a different key, which invalidates the cached output.
+    fn cache_key(
+        compiler_digest: &str,
+        comp_args: &[String],
+        standard_json: &Value,
+    ) -> Result<String> {
+        let mut hasher = Sha256::new();
+        hasher.update(compiler_digest.as_bytes());
+        for arg in comp_args {
+            hasher.update(arg.as_bytes());
+        }
+        let standard_json = serde_json::to_vec(standard_json)
+            .map_err(|e| ZkCompilationError::json("Could not serialize standard JSON input", e))?;
+        hasher.update(&standard_json);
+        Ok(hex::encode(hasher.finalize()))
+    }
+
+    /// Returns the cached compiler output for `cache_key`, if any.
+    ///
+    /// Nothing is returned if caching is disabled, if `force` is set or if the cached output cannot
+    /// be read, in which case the source is simply compiled again. The cache is also bypassed if
+    /// `debug_output_dir` is set, as the intermediate artifacts are only written by zksolc.
+    fn read_cached_output(&self, cache_key: &str) -> Option<Value> {
+        if self.opts.force || self.opts.debug_output_dir.is_some() {
+            return None;
+        }
+        let path = self.opts.cache_dir.as_ref()?.join(cache_key).join("artifacts.json");
+        let data = fs::read_to_string(path).ok()?;
+        serde_json::from_str(&data).ok()
+    }
+
+    /// Caches the compiler output of a successful compilation under `cache_key`.
+    ///
+    /// # Errors
+    ///
+    /// This function can return an error if the cache directory or the cached output cannot be written.
+    fn write_cached_output(&self, cache_key: &str, output_json: &Value) -> Result<()> {
+        let cache_dir = match &self.opts.cache_dir {
+            Some(cache_dir) => cache_dir.join(cache_key),
+            None => return Ok(()),
+        };
+        fs::create_dir_all(&cache_dir).map_err(|e| {
+            ZkCompilationError::io("Could not create cache directory", &cache_dir, e)
+        })?;
+        let output_json = serde_json::to_string(output_json).map_err(|e| {
+            ZkCompilationError::json("Could not serialize zksolc compiler output", e)
+        })?;
+        let cached_path = cache_dir.join("artifacts.json");
+        fs::write(&cached_path, output_json)
+            .map_err(|e| ZkCompilationError::io("Could not write cached artifacts", cached_path, e))
     }
 
     /// Builds the compiler arguments for the Solidity compiler based on the provided versioned source
@@ -294,16 +1244,25 @@ impl ZkSolc {
     /// # Returns
     ///
     /// A vector of strings representing the compiler arguments.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the path of the solc compiler is not valid UTF-8.
     fn build_compiler_args(
         &mut self,
         versioned_source: (PathBuf, Source),
         solc: Solc,
-    ) -> Vec<String> {
+    ) -> Result<Vec<String>> {
         // Get the solc compiler path as a string
         let solc_path = solc
             .solc
             .to_str()
-            .unwrap_or_else(|| panic!("Error configuring solc compiler."))
+            .ok_or_else(|| {
+                ZkCompilationError::Setup(format!(
+                    "Error configuring solc compiler: invalid path {}",
+                    solc.solc.display()
+                ))
+            })?
             .to_string();
 
         // Build compiler arguments
@@ -313,15 +1272,26 @@ impl ZkSolc {
         comp_args.push(solc_path.to_owned());
 
         // Check if system mode is enabled or if the source path contains "is-system"
-        if self.is_system || versioned_source.0.to_str().unwrap().contains("is-system") {
+        if self.opts.is_system || versioned_source.0.to_string_lossy().contains("is-system") {
             comp_args.push("--system-mode".to_string());
         }
 
-        // Check if force-evmla is enabled
-        if self.force_evmla {
-            comp_args.push("--force-evmla".to_string());
+        // Write the intermediate artifacts for inspection
+        if let Some(debug_output_dir) = &self.opts.debug_output_dir {
+            comp_args.push("--debug-output-dir".to_string());
+            comp_args.push(debug_output_dir.to_string_lossy().to_string());
         }
-        comp_args
+
+        // Resolve imports against the base path and search the include paths
+        comp_args.extend(include_path_args(
+            &self.project.paths.root,
+            self.opts.base_path.as_deref(),
+            &self.opts.include_paths,
+        ));
+
+        // Allow imports from outside of the base path
+        comp_args.extend(allow_paths_args(&self.opts.allow_paths));
+        Ok(comp_args)
     }
 
     /// Handles the output of the Solidity compiler after the compilation process is completed. It processes
@@ -329,21 +1299,25 @@ impl ZkSolc {
     ///
     /// # Arguments
     ///
-    /// * `output` - The output of the Solidity compiler as a `std::process::Output` struct.
-    /// * `source` - The path of the contract source file that was compiled.
+    /// * `output_json` - The output of the Solidity compiler, either produced by the current run or
+    ///   read from the cache.
+    /// * `source` - The file name of the contract source file that was compiled.
+    /// * `contract_path` - The path of the contract source file, the output is only collected for
+    ///   the contracts defined in this file, see `is_output_of`.
     /// * `displayed_warnings` - A mutable set that keeps track of displayed warnings to avoid duplicates.
+    /// * `outcome` - The outcome of the source, which collects its diagnostics and compiled contracts.
     ///
     /// # Output Handling
     ///
-    /// - The output of the Solidity compiler is expected to be in JSON format.
-    /// - The output is deserialized into a `serde_json::Value` object for further processing.
+    /// - The output of the Solidity compiler is expected to be deserialized into a `serde_json::Value`
+    ///   object by the caller.
     ///
     /// # Error and Warning Handling
     ///
     /// - The function checks for errors and warnings in the compiler output and handles them accordingly.
     /// - Errors are printed in red color.
     /// - Warnings are printed in yellow color.
-    /// - If an error is encountered, the function exits with a non-zero status code.
+    /// - If an error is encountered, the function returns an error and no artifacts are saved.
     /// - If only warnings are present, a message indicating the presence of warnings is printed.
     ///
     /// # Artifacts Saving
@@ -354,10 +1328,13 @@ impl ZkSolc {
     /// # Example
     ///
     /// ```rust
-    /// let output = std::process::Output { ... };
-    /// let source = "/path/to/contract.sol".to_string();
+    /// let output_json = serde_json::from_slice(&output.stdout)?;
+    /// let source = "contract.sol".to_string();
+    /// let contract_path = Path::new("/path/to/contract.sol");
     /// let mut displayed_warnings = HashSet::new();
-    /// self.handle_output(output, source, &mut displayed_warnings);
+    /// let mut outcome = ZkSourceOutcome::default();
+    /// let warnings = &mut displayed_warnings;
+    /// self.handle_output(output_json, source, contract_path, warnings, &mut outcome)?;
     /// ```
     ///
     /// In this example, the `handle_output` function is called with the compiler output, contract source,
@@ -365,102 +1342,279 @@ impl ZkSolc {
     /// saves the artifacts.
     fn handle_output(
         &self,
-        output: std::process::Output,
+        output_json: Value,
         source: String,
+        contract_path: &Path,
         displayed_warnings: &mut HashSet<String>,
-    ) {
-        // Deserialize the compiler output into a serde_json::Value object
-        let output_json: Value = serde_json::from_slice(&output.clone().stdout)
-            .unwrap_or_else(|e| panic!("Could not parse zksolc compiler output: {}", e));
-
+        outcome: &mut ZkSourceOutcome,
+    ) -> Result<()> {
         // Handle errors and warnings in the output
-        self.handle_output_errors(&output_json, displayed_warnings);
+        self.handle_output_errors(&output_json, displayed_warnings, &mut outcome.diagnostics)?;
 
         // Create the artifacts file for saving the compiler output
-        let mut artifacts_file = self
-            .build_artifacts_file(source.clone())
-            .unwrap_or_else(|e| panic!("Error configuring solc compiler: {}", e));
+        let mut artifacts_file = self.build_artifacts_file(source.clone())?;
 
         // Get the bytecode hashes for each contract in the output
-        let output_obj = output_json["contracts"].as_object().unwrap();
+        let output_obj = output_json["contracts"].as_object().ok_or_else(|| {
+            ZkCompilationError::InvalidOutput(
+                "Could not find 'contracts' object in the output JSON".to_string(),
+            )
+        })?;
         for key in output_obj.keys() {
-            if key.contains(&source) {
+            if is_output_of(&self.project.paths.root, key, contract_path) {
                 let b_code = output_obj[key].clone();
-                let b_code_obj = b_code.as_object().unwrap();
+                let b_code_obj = match b_code.as_object() {
+                    Some(b_code_obj) => b_code_obj,
+                    None => continue,
+                };
                 let b_code_keys = b_code_obj.keys();
                 for hash in b_code_keys {
                     if let Some(bcode_hash) = b_code_obj[hash]["hash"].as_str() {
-                        println!("{}", format!("{} -> Bytecode Hash: {} ", hash, bcode_hash));
+                        if !self.opts.silent {
+                            println!("{}", format!("{} -> Bytecode Hash: {} ", hash, bcode_hash));
+                        }
                     }
+                    let bytecode = b_code_obj[hash]["evm"]["bytecode"]["object"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .trim_start_matches("0x");
+                    outcome.contracts.push(ZkCompiledContract {
+                        name: format!("{}:{}", key, hash),
+                        bytecode: format!("0x{}", bytecode),
+                        deployed_bytecode: get_deployed_bytecode(&b_code_obj[hash]),
+                        abi: b_code_obj[hash]["abi"].clone(),
+                        source_map: get_source_map(&b_code_obj[hash]),
+                        method_identifiers: b_code_obj[hash]["evm"]["methodIdentifiers"].clone(),
+                        metadata: b_code_obj[hash]["metadata"].clone(),
+                        storage_layout: serde_json::from_value(
+                            b_code_obj[hash]["storageLayout"].clone(),
+                        )
+                        .ok(),
+                        devdoc: b_code_obj[hash].get("devdoc").cloned(),
+                        userdoc: b_code_obj[hash].get("userdoc").cloned(),
+                    });
                 }
             }
         }
 
         // Beautify the output JSON
-        let output_json_pretty = serde_json::to_string_pretty(&output_json)
-            .unwrap_or_else(|e| panic!("Could not beautify zksolc compiler output: {}", e));
+        let output_json_pretty = serde_json::to_string_pretty(&output_json).map_err(|e| {
+            ZkCompilationError::json("Could not beautify zksolc compiler output", e)
+        })?;
 
         // Write the beautified output JSON to the artifacts file
-        artifacts_file
-            .write_all(output_json_pretty.as_bytes())
-            .unwrap_or_else(|e| panic!("Could not write artifacts file: {}", e));
+        artifacts_file.write_all(output_json_pretty.as_bytes()).map_err(|e| {
+            ZkCompilationError::io(
+                "Could not write artifacts file",
+                self.project.paths.artifacts.join(&source).join("artifacts.json"),
+                e,
+            )
+        })?;
+
+        self.write_extra_output_files(&output_json, &source, contract_path)
     }
 
-    /// Handles the errors and warnings present in the output JSON from the compiler.
-    ///
-    /// # Arguments
+    /// Writes the outputs selected with `extra_output_files` of every contract of the source at
+    /// `contract_path` to their own file in the artifacts directory of the source, named
+    /// `<contract>.<selector>.json`.
     ///
-    /// * `output_json` - A reference to the output JSON from the compiler, represented as a `Value` from
-    ///   the `serde_json` crate.
-    /// * `displayed_warnings` - A mutable reference to a `HashSet` that tracks displayed warnings to
-    ///   avoid duplicates.
+    /// The `ast` selector is a per-file output, it is written to `<source>.ast.json` instead.
+    /// Outputs missing from the compiler output are skipped.
     ///
-    /// # Behavior
+    /// # Errors
     ///
-    /// This function iterates over the `errors` array in the output JSON and processes each error or
-    /// warning individually. For each error or warning, it extracts the severity and formatted message
-    /// from the JSON. If the severity is "warning", it checks if the same warning message has been
-    /// displayed before to avoid duplicates. If the warning message has not been displayed before, it
-    /// adds the message to the `displayed_warnings` set, prints the formatted warning message in
-    /// yellow, and sets the `has_warning` flag to true. If the severity is not "warning", it prints
-    /// the formatted error message in red and sets the `has_error` flag to true.
-    ///
-    /// If any errors are encountered, the function calls `exit(1)` to terminate the program. If only
-    /// warnings are encountered, it prints a message indicating that the compiler run completed with
-    /// warnings.
-    fn handle_output_errors(&self, output_json: &Value, displayed_warnings: &mut HashSet<String>) {
-        let errors = output_json
-            .get("errors")
-            .and_then(|v| v.as_array())
-            .unwrap_or_else(|| panic!("Could not find 'errors' array in the output JSON"));
-
-        let mut has_error = false;
-        let mut has_warning = false;
+    /// This function can return an error if one of the files cannot be written.
+    fn write_extra_output_files(
+        &self,
+        output_json: &Value,
+        source: &str,
+        contract_path: &Path,
+    ) -> Result<()> {
+        if self.opts.extra_output_files.is_empty() {
+            return Ok(());
+        }
+        let root = &self.project.paths.root;
+        let artifacts_dir = self.project.paths.artifacts.join(source);
+
+        let mut files = Vec::new();
+        for selector in &self.opts.extra_output_files {
+            if selector == "ast" {
+                if let Some(sources) = output_json["sources"].as_object() {
+                    files.extend(
+                        sources
+                            .iter()
+                            .filter(|(file, _)| is_output_of(root, file, contract_path))
+                            .map(|(_, output)| (format!("{source}.ast.json"), &output["ast"])),
+                    );
+                }
+                continue;
+            }
+            if let Some(contracts) = output_json["contracts"].as_object() {
+                for (file, contracts) in contracts {
+                    if !is_output_of(root, file, contract_path) {
+                        continue;
+                    }
+                    let contracts = match contracts.as_object() {
+                        Some(contracts) => contracts,
+                        None => continue,
+                    };
+                    for (name, contract) in contracts {
+                        files.push((
+                            format!("{name}.{selector}.json"),
+                            select_output(contract, selector),
+                        ));
+                    }
+                }
+            }
+        }
+
+        for (filename, output) in files {
+            if output.is_null() {
+                continue;
+            }
+            let path = artifacts_dir.join(filename);
+            let output = serde_json::to_string_pretty(output).map_err(|e| {
+                ZkCompilationError::json("Could not serialize extra compiler output", e)
+            })?;
+            fs::write(&path, output).map_err(|e| {
+                ZkCompilationError::io("Could not write extra output file", path, e)
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Writes the AST of the source at `contract_path` to the `ast` directory of `out_dir`, if `ast`
+    /// is set.
+    ///
+    /// The file is named after the path of the source relative to the sources directory of the
+    /// project, for example `ast/tokens/Token.sol.json` for `src/tokens/Token.sol`. The ASTs of the
+    /// imported sources are part of the output as well, but they are only written for the sources
+    /// they belong to.
+    ///
+    /// # Errors
+    ///
+    /// This function can return an error if the AST file cannot be written.
+    fn write_ast(&self, output_json: &Value, contract_path: &Path) -> Result<()> {
+        let out_dir = match &self.opts.out_dir {
+            Some(out_dir) if self.opts.ast => out_dir,
+            _ => return Ok(()),
+        };
+        let root = &self.project.paths.root;
+        let ast = output_json["sources"]
+            .as_object()
+            .and_then(|sources| {
+                sources.iter().find(|(file, _)| is_output_of(root, file, contract_path))
+            })
+            .map(|(_, output)| &output["ast"]);
+        let ast = match ast {
+            Some(ast) if !ast.is_null() => ast,
+            _ => return Ok(()),
+        };
+
+        let source =
+            contract_path.strip_prefix(&self.project.paths.sources).unwrap_or(contract_path);
+        let path = out_dir.join("ast").join(format!("{}.json", source.display()));
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| ZkCompilationError::io("Could not create AST directory", parent, e))?;
+        }
+        let ast = serde_json::to_string_pretty(ast)
+            .map_err(|e| ZkCompilationError::json("Could not serialize AST", e))?;
+        fs::write(&path, ast)
+            .map_err(|e| ZkCompilationError::io("Could not write AST file", path, e))
+    }
+
+    /// Handles the errors and warnings present in the output JSON from the compiler.
+    ///
+    /// # Arguments
+    ///
+    /// * `output_json` - A reference to the output JSON from the compiler, represented as a `Value` from
+    ///   the `serde_json` crate.
+    /// * `displayed_warnings` - A mutable reference to a `HashSet` that tracks displayed warnings to
+    ///   avoid duplicates.
+    /// * `diagnostics` - A mutable reference to a `Vec` that collects the errors and the warnings
+    ///   which were not displayed before.
+    ///
+    /// # Behavior
+    ///
+    /// This function iterates over the `errors` array in the output JSON and processes each error or
+    /// warning individually. For each error or warning, it extracts the severity and formatted message
+    /// from the JSON. If the severity is "warning", it checks if the same warning message has been
+    /// displayed before to avoid duplicates. If the warning message has not been displayed before, it
+    /// adds the message to the `displayed_warnings` set, prints the warning in yellow, and sets the
+    /// `has_warning` flag to true. If the severity is not "warning", it prints the error in red and
+    /// sets the `has_error` flag to true. Diagnostics are printed with their source snippet, see
+    /// `print_diagnostic`.
+    ///
+    /// If any errors are encountered, the function returns an error so that the source is reported as
+    /// failed. If only warnings are encountered, it prints a message indicating that the compiler run
+    /// completed with warnings. With `suppress_warnings`, the warnings are collected but never printed.
+    fn handle_output_errors(
+        &self,
+        output_json: &Value,
+        displayed_warnings: &mut HashSet<String>,
+        diagnostics: &mut Vec<ZkSolcDiagnostic>,
+    ) -> Result<()> {
+        let errors = output_json.get("errors").and_then(|v| v.as_array()).ok_or_else(|| {
+            ZkCompilationError::InvalidOutput(
+                "Could not find 'errors' array in the output JSON".to_string(),
+            )
+        })?;
+
+        let mut has_error = false;
+        let mut has_warning = false;
+        let print_warnings = !self.opts.silent && !self.opts.suppress_warnings;
+
+        for error in errors {
+            if is_ignored_warning(error, &self.opts.ignored_error_codes) {
+                continue;
+            }
 
-        for error in errors {
-            let severity = error.get("severity").and_then(|v| v.as_str()).unwrap_or("Unknown");
             let formatted_message =
                 error.get("formattedMessage").and_then(|v| v.as_str()).unwrap_or("");
 
-            let is_warning = severity.eq_ignore_ascii_case("warning");
-            if is_warning {
+            let diagnostic = parse_diagnostic(error);
+
+            if diagnostic.is_warning() {
                 let main_message = formatted_message.lines().next().unwrap_or("").to_string();
                 if !displayed_warnings.contains(&main_message) {
                     displayed_warnings.insert(main_message);
-                    println!("{}", Yellow.paint(formatted_message));
+                    if print_warnings {
+                        self.print_diagnostic(&diagnostic, formatted_message);
+                    }
+                    diagnostics.push(diagnostic);
                     has_warning = true;
                 }
             } else {
-                println!("{}", Red.paint(formatted_message));
+                if !self.opts.silent {
+                    self.print_diagnostic(&diagnostic, formatted_message);
+                }
+                diagnostics.push(diagnostic);
                 has_error = true;
             }
         }
 
         if has_error {
-            exit(1);
-        } else if has_warning {
+            let errors = diagnostics.iter().filter(|d| !d.is_warning()).cloned().collect();
+            return Err(ZkCompilationError::Compilation(errors));
+        } else if has_warning && print_warnings {
             println!("Compiler run completed with warnings");
         }
+        Ok(())
+    }
+
+    /// Prints the `diagnostic` with the source line it refers to, see `ZkSolcDiagnostic::render`.
+    ///
+    /// Diagnostics without a source location are printed with the `formatted_message` of zksolc,
+    /// in yellow for warnings and in red for errors.
+    fn print_diagnostic(&self, diagnostic: &ZkSolcDiagnostic, formatted_message: &str) {
+        if diagnostic.source_file.is_some() || formatted_message.is_empty() {
+            println!("{}", diagnostic.render(&self.project.paths.root));
+        } else if diagnostic.is_warning() {
+            println!("{}", Yellow.paint(formatted_message));
+        } else {
+            println!("{}", Red.paint(formatted_message));
+        }
     }
 
     /// Parses the JSON input for a contract and prepares the necessary configuration for the ZkSolc compiler.
@@ -473,6 +1627,7 @@ impl ZkSolc {
     ///
     /// This function can return an error if any of the following occurs:
     /// - The standard JSON input cannot be generated for the contract.
+    /// - The sources of the standard JSON input import each other in a cycle.
     /// - The artifacts path for the contract cannot be created.
     /// - The JSON input cannot be saved to the artifacts directory.
     ///
@@ -482,6 +1637,7 @@ impl ZkSolc {
     ///
     /// 1. Configure File Output Selection:
     ///    - It configures the file output selection to specify which outputs should be included in the compiler output.
+    ///    - The selectors of `extra_output` and `extra_output_files` are appended to the default ones.
     ///
     /// 2. Configure Solidity Compiler:
     ///    - It modifies the Solidity compiler settings to exclude metadata from the output.
@@ -492,6 +1648,10 @@ impl ZkSolc {
     /// 4. Generate Standard JSON Input:
     ///    - It generates the standard JSON input for the contract using the `standard_json_input` method of the project.
     ///    - The standard JSON input includes the contract's source code, compiler options, and file output selection.
+    ///    - The zksolc specific settings from `ZkSolcOpts::zksync_settings`, like `forceEVMLA` and the optimizer
+    ///      mode, are merged into its `settings` object.
+    ///    - The imports of its sources are checked for cycles, which zksolc may hang on or report with an
+    ///      opaque error.
     ///
     /// 5. Build Artifacts Path:
     ///    - It builds the path for saving the compiler artifacts based on the contract source file.
@@ -517,6 +1677,7 @@ impl ZkSolc {
             vec![
                 "abi".to_string(),
                 "evm.methodIdentifiers".to_string(),
+                "evm.bytecode.sourceMap".to_string(),
                 // "evm.legacyAssembly".to_string(),
             ],
         );
@@ -531,6 +1692,22 @@ impl ZkSolc {
                 // "irOptimized".to_string(),
             ],
         );
+        add_extra_output(
+            &mut file_output_selection,
+            self.opts.extra_output.iter().chain(&self.opts.extra_output_files),
+        );
+        if self.opts.ast {
+            add_extra_output(&mut file_output_selection, &["ast".to_string()]);
+        }
+        if self.opts.storage_layout {
+            add_extra_output(&mut file_output_selection, &["storageLayout".to_string()]);
+        }
+        if self.opts.natspec {
+            add_extra_output(
+                &mut file_output_selection,
+                &["devdoc".to_string(), "userdoc".to_string()],
+            );
+        }
 
         // Step 2: Configure Solidity Compiler
         // zksolc requires metadata to be 'None'
@@ -545,27 +1722,58 @@ impl ZkSolc {
             .insert("*".to_string(), file_output_selection.clone());
 
         // Step 4: Generate Standard JSON Input
-        let standard_json = self
-            .project
-            .standard_json_input(&contract_path)
-            .map_err(|e| Error::msg(format!("Could not get standard json input: {}", e)))
-            .unwrap();
+        let standard_json = self.project.standard_json_input(&contract_path).map_err(|e| {
+            ZkCompilationError::Setup(format!("Could not get standard json input: {}", e))
+        })?;
+        if let Some(cycle) = find_import_cycle(
+            &standard_json.sources,
+            &self.project.paths.root,
+            &self.project.paths.remappings,
+        ) {
+            return Err(ZkCompilationError::CircularImport(cycle));
+        }
+        let mut stdjson = serde_json::to_value(&standard_json)
+            .map_err(|e| ZkCompilationError::json("Could not serialize standard JSON input", e))?;
+        if let Some(settings) = stdjson.get_mut("settings").and_then(Value::as_object_mut) {
+            for (key, value) in self.opts.zksync_settings() {
+                match (settings.get_mut(&key), value) {
+                    (Some(Value::Object(existing)), Value::Object(value)) => existing.extend(value),
+                    (_, value) => {
+                        settings.insert(key, value);
+                    }
+                }
+            }
+
+            // Pass the remappings of the project, so that remapped imports can be resolved
+            let remappings =
+                remappings_settings(&self.project.paths.remappings, &self.project.paths.root);
+            if !remappings.is_empty() {
+                settings.insert("remappings".to_string(), Value::Array(remappings));
+            }
+
+            // Inject the addresses of the deployed libraries, so that zksolc links them
+            let libraries = libraries_settings(
+                &self.project.solc_config.settings.libraries,
+                &self.project.paths.root,
+            );
+            if !libraries.is_empty() {
+                settings.insert("libraries".to_string(), Value::Object(libraries));
+            }
+        }
 
         // Store the generated standard JSON input in the ZkSolc instance
-        self.standard_json = Some(standard_json.to_owned());
+        self.standard_json = Some(stdjson.clone());
 
         // Step 5: Build Artifacts Path
-        let artifact_path = &self
-            .build_artifacts_path(contract_path)
-            .map_err(|e| Error::msg(format!("Could not build_artifacts_path: {}", e)))
-            .unwrap();
+        let artifact_path = &self.build_artifacts_path(contract_path)?;
 
         // Step 6: Save JSON Input
         let json_input_path = artifact_path.join("json_input.json");
-        let stdjson = serde_json::to_value(&standard_json)
-            .map_err(|e| Error::msg(format!("Could not serialize standard JSON input: {}", e)))?;
-        std::fs::write(&json_input_path, serde_json::to_string_pretty(&stdjson).unwrap())
-            .map_err(|e| Error::msg(format!("Could not write JSON input file: {}", e)))?;
+        let json_input = serde_json::to_string_pretty(&stdjson)
+            .map_err(|e| ZkCompilationError::json("Could not serialize standard JSON input", e))?;
+        std::fs::write(&json_input_path, json_input).map_err(|e| {
+            ZkCompilationError::io("Could not write JSON input file", &json_input_path, e)
+        })?;
 
         Ok(())
     }
@@ -615,7 +1823,7 @@ impl ZkSolc {
     ///
     /// # Returns
     ///
-    /// A `Result` containing a `BTreeMap` of the versioned sources on success, or an `anyhow::Error` on failure.
+    /// A `Result` containing a `BTreeMap` of the versioned sources on success, or a `ZkCompilationError` on failure.
     ///
     /// # Errors
     ///
@@ -646,24 +1854,24 @@ impl ZkSolc {
         &mut self,
     ) -> Result<BTreeMap<Solc, (Version, BTreeMap<PathBuf, Source>)>> {
         // Step 1: Retrieve Project Sources
-        let sources = self
-            .project
-            .sources()
-            .map_err(|e| Error::msg(format!("Could not get project sources: {}", e)))?;
+        let sources = self.project.sources().map_err(|e| {
+            ZkCompilationError::Setup(format!("Could not get project sources: {}", e))
+        })?;
 
         // Step 2: Resolve Graph of Sources and Versions
         let graph = Graph::resolve_sources(&self.project.paths, sources)
-            .map_err(|e| Error::msg(format!("Could not create graph: {}", e)))?;
+            .map_err(|e| ZkCompilationError::Setup(format!("Could not create graph: {}", e)))?;
 
         // Step 3: Extract Versions and Edges
-        let (versions, _edges) = graph
-            .into_sources_by_version(self.project.offline)
-            .map_err(|e| Error::msg(format!("Could not get versions & edges: {}", e)))?;
+        let (versions, _edges) =
+            graph.into_sources_by_version(self.project.offline).map_err(|e| {
+                ZkCompilationError::Setup(format!("Could not get versions & edges: {}", e))
+            })?;
 
         // Step 4: Retrieve Solc Version
         let solc_version = versions
             .get(&self.project)
-            .map_err(|e| Error::msg(format!("Could not get solc: {}", e)));
+            .map_err(|e| ZkCompilationError::Setup(format!("Could not get solc: {}", e)));
 
         solc_version
     }
@@ -691,18 +1899,19 @@ impl ZkSolc {
     ///
     /// # Returns
     ///
-    /// A `Result` containing the constructed artifacts path (`PathBuf`) on success, or an `anyhow::Error` on failure.
+    /// A `Result` containing the constructed artifacts path (`PathBuf`) on success, or a `ZkCompilationError` on failure.
     ///
     /// # Errors
     ///
     /// This function can return an error if any of the following occurs:
     /// - The extraction of the filename from the contract source path fails.
     /// - The creation of the artifacts directory fails.
-    fn build_artifacts_path(&self, source: PathBuf) -> Result<PathBuf, anyhow::Error> {
+    fn build_artifacts_path(&self, source: PathBuf) -> Result<PathBuf> {
         let filename = source.file_name().expect("Failed to get Contract filename.");
         let path = self.project.paths.artifacts.join(filename);
-        fs::create_dir_all(&path)
-            .map_err(|e| Error::msg(format!("Could not create artifacts directory: {}", e)))?;
+        fs::create_dir_all(&path).map_err(|e| {
+            ZkCompilationError::io("Could not create artifacts directory", &path, e)
+        })?;
         Ok(path)
     }
 
@@ -725,13 +1934,1204 @@ impl ZkSolc {
     ///
     /// # Returns
     ///
-    /// A `Result` containing the created `File` object on success, or an `anyhow::Error` on failure.
+    /// A `Result` containing the created `File` object on success, or a `ZkCompilationError` on failure.
     ///
     /// # Errors
     ///
     /// This function can return an error if the creation of the artifacts file fails.
     fn build_artifacts_file(&self, source: String) -> Result<File> {
-        File::create(self.project.paths.artifacts.join(source).join("artifacts.json"))
-            .map_err(|e| Error::msg(format!("Could not create artifacts file: {}", e)))
+        let path = self.project.paths.artifacts.join(source).join("artifacts.json");
+        File::create(&path)
+            .map_err(|e| ZkCompilationError::io("Could not create artifacts file", path, e))
+    }
+}
+
+/// Writes the artifact of every compiled contract of `output` to `out_dir`, see
+/// `ZkCompiledContract::to_foundry_artifact`.
+///
+/// Artifacts are written to `<out_dir>/<ContractName>.json`. Contracts whose name is defined in
+/// several sources are written to `<out_dir>/<File.sol>/<ContractName>.json` instead, so that they
+/// don't overwrite each other.
+///
+/// # Errors
+///
+/// This function can return an error if an artifact cannot be written.
+fn write_foundry_artifacts(out_dir: &Path, output: &ZkCompilationOutput) -> Result<()> {
+    let mut names: BTreeMap<&str, usize> = BTreeMap::new();
+    for contract in output.contracts() {
+        *names.entry(contract.contract_name()).or_default() += 1;
+    }
+
+    for contract in output.contracts() {
+        let path = foundry_artifact_path(out_dir, contract, names[contract.contract_name()] > 1);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                ZkCompilationError::io("Could not create artifacts directory", parent, e)
+            })?;
+        }
+        let artifact = serde_json::to_string_pretty(&contract.to_foundry_artifact())
+            .map_err(|e| ZkCompilationError::json("Could not serialize artifact", e))?;
+        fs::write(&path, artifact)
+            .map_err(|e| ZkCompilationError::io("Could not write artifact", path, e))?;
+    }
+    Ok(())
+}
+
+/// Writes the artifact of every compiled contract of `output` to `artifacts_dir`, see
+/// `ZkCompiledContract::to_hardhat_artifact`.
+///
+/// Like Hardhat, artifacts are written to `<artifacts_dir>/<path>/<ContractName>.json`, where
+/// `<path>` is the path of the source of the contract relative to the project root, like
+/// `contracts/Greeter.sol`.
+///
+/// # Errors
+///
+/// This function can return an error if an artifact cannot be written.
+fn write_hardhat_artifacts(artifacts_dir: &Path, output: &ZkCompilationOutput) -> Result<()> {
+    for contract in output.contracts() {
+        let path = hardhat_artifact_path(artifacts_dir, contract);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                ZkCompilationError::io("Could not create Hardhat artifacts directory", parent, e)
+            })?;
+        }
+        let artifact = serde_json::to_string_pretty(&contract.to_hardhat_artifact())
+            .map_err(|e| ZkCompilationError::json("Could not serialize Hardhat artifact", e))?;
+        fs::write(&path, artifact)
+            .map_err(|e| ZkCompilationError::io("Could not write Hardhat artifact", path, e))?;
+    }
+    Ok(())
+}
+
+/// Returns the path of the Hardhat artifact of `contract` in `artifacts_dir`.
+fn hardhat_artifact_path(artifacts_dir: &Path, contract: &ZkCompiledContract) -> PathBuf {
+    let filename = format!("{}.json", contract.contract_name());
+    match contract.name.rsplit_once(':') {
+        Some((source, _)) => artifacts_dir.join(source).join(filename),
+        None => artifacts_dir.join(filename),
+    }
+}
+
+/// Returns the path of the Foundry artifact of `contract` in `out_dir`, nested in a directory
+/// named after its source if the name of the contract is `duplicated` in several sources.
+fn foundry_artifact_path(
+    out_dir: &Path,
+    contract: &ZkCompiledContract,
+    duplicated: bool,
+) -> PathBuf {
+    let filename = format!("{}.json", contract.contract_name());
+    let source =
+        contract.name.rsplit_once(':').and_then(|(source, _)| Path::new(source).file_name());
+    match source {
+        Some(source) if duplicated => out_dir.join(source).join(filename),
+        _ => out_dir.join(filename),
+    }
+}
+
+/// Appends the `selectors` to the output selection of every contract, or of every file for `ast`,
+/// skipping those which are already selected.
+fn add_extra_output<'a>(
+    selection: &mut FileOutputSelection,
+    selectors: impl IntoIterator<Item = &'a String>,
+) {
+    for selector in selectors {
+        let key = if selector == "ast" { "" } else { "*" };
+        let outputs = selection.entry(key.to_string()).or_default();
+        if !outputs.contains(selector) {
+            outputs.push(selector.clone());
+        }
+    }
+}
+
+/// Returns the output of a contract for a dotted `selector`, like `evm.methodIdentifiers`, or
+/// `Value::Null` if it's missing.
+fn select_output<'a>(contract: &'a Value, selector: &str) -> &'a Value {
+    selector.split('.').fold(contract, |output, key| &output[key])
+}
+
+/// Runs `cmd` with `input` written to its stdin and returns its output, or `None` if it was killed
+/// with `Child::kill` because it did not exit within `timeout`.
+///
+/// stdin is written and stdout and stderr are read from background threads while the current
+/// thread monitors the process, so that a process which stops reading its input or fills its
+/// output pipes can't block it past the timeout.
+fn output_with_timeout(
+    cmd: &mut Command,
+    input: Vec<u8>,
+    timeout: Duration,
+) -> std::io::Result<Option<Output>> {
+    let mut child =
+        cmd.stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    let mut stdin = child.stdin.take().expect("Stdin exists.");
+    let mut stdout = child.stdout.take().expect("Stdout exists.");
+    let mut stderr = child.stderr.take().expect("Stderr exists.");
+    // A process which exits without reading all of its input fails the write, its exit status
+    // tells why
+    let writer = thread::spawn(move || stdin.write_all(&input));
+    let stdout_reader = thread::spawn(move || {
+        let mut output = Vec::new();
+        stdout.read_to_end(&mut output).map(|_| output)
+    });
+    let stderr_reader = thread::spawn(move || {
+        let mut output = Vec::new();
+        stderr.read_to_end(&mut output).map(|_| output)
+    });
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            // The process may have exited in the meantime, which fails the kill
+            let _ = child.kill();
+            child.wait()?;
+            // The readers are not joined, the pipes may still be held open by children of the
+            // killed process
+            return Ok(None);
+        }
+        thread::sleep(COMPILER_POLL_INTERVAL);
+    };
+
+    let _ = writer.join();
+    let stdout = stdout_reader.join().expect("The stdout reader panicked")?;
+    let stderr = stderr_reader.join().expect("The stderr reader panicked")?;
+    Ok(Some(Output { status, stdout, stderr }))
+}
+
+/// Returns the entry of the compilation database for the invocation of the zksolc at
+/// `compiler_path` to compile `job`, from the project `root`.
+///
+/// zksolc reads the standard JSON input from stdin, so the `command` redirects the `json_input`
+/// file saved next to the artifacts of the source, to replicate the exact compilation.
+fn compilation_db_entry(
+    compiler_path: &Path,
+    root: &Path,
+    job: &ZkCompilationJob,
+    json_input: &Path,
+) -> Value {
+    let arguments: Vec<String> = [compiler_path, job.contract_path.as_path()]
+        .iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .chain(job.comp_args.iter().cloned())
+        .collect();
+    let command = arguments
+        .iter()
+        .map(|argument| shell_quote(argument))
+        .chain(["<".to_string(), shell_quote(&json_input.to_string_lossy())])
+        .collect::<Vec<_>>()
+        .join(" ");
+    serde_json::json!({
+        "directory": root,
+        "file": job.contract_path,
+        "arguments": arguments,
+        "command": command,
+    })
+}
+
+/// Quotes `argument` for a POSIX shell, unless it only contains characters that don't need to be
+/// quoted.
+fn shell_quote(argument: &str) -> String {
+    let safe = !argument.is_empty() &&
+        argument.chars().all(|c| c.is_ascii_alphanumeric() || "_-./=:,@+".contains(c));
+    if safe {
+        return argument.to_string();
+    }
+    format!("'{}'", argument.replace('\'', "'\\''"))
+}
+
+/// Returns the `--base-path` and `--include-path` arguments of zksolc for `base_path` and
+/// `include_paths`.
+///
+/// solc only accepts include paths together with a base path, so if `base_path` is not set the
+/// project `root` is passed as `--base-path` unless there are no include paths.
+fn include_path_args(
+    root: &Path,
+    base_path: Option<&Path>,
+    include_paths: &[PathBuf],
+) -> Vec<String> {
+    let base_path = match base_path {
+        Some(base_path) => base_path,
+        None if include_paths.is_empty() => return vec![],
+        None => root,
+    };
+    let mut args = vec!["--base-path".to_string(), base_path.to_string_lossy().to_string()];
+    for include_path in include_paths {
+        args.push("--include-path".to_string());
+        args.push(include_path.to_string_lossy().to_string());
+    }
+    args
+}
+
+/// Returns the `--allow-paths` argument of zksolc for `allow_paths`, which solc expects as a single
+/// comma-separated list.
+fn allow_paths_args(allow_paths: &[PathBuf]) -> Vec<String> {
+    if allow_paths.is_empty() {
+        return vec![];
+    }
+    let allow_paths = allow_paths
+        .iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    vec!["--allow-paths".to_string(), allow_paths]
+}
+
+/// Formats the `remappings` of the project as the `settings.remappings` entry of the standard JSON
+/// input, in the `from=to` format of solc.
+///
+/// The remappings of the project are the ones returned by `Config::get_all_remappings`, which
+/// point to absolute paths. They are made relative to the project `root`, like the sources of the
+/// standard JSON input.
+pub(crate) fn remappings_settings(remappings: &[Remapping], root: &Path) -> Vec<Value> {
+    remappings
+        .iter()
+        .map(|remapping| {
+            let remapping = remapping.clone().into_relative(root).to_relative_remapping();
+            Value::String(remapping.to_string())
+        })
+        .collect()
+}
+
+/// Matches the path imported by an `import` statement, in all of its forms: `import "path";`,
+/// `import "path" as name;`, `import * as name from "path";` and `import {a, b} from "path";`.
+static RE_SOL_IMPORT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?m)^\s*import\s+(?:[^;"']*?\s*from\s*)?["']([^"']+)["']"#).unwrap()
+});
+
+/// Formats an import cycle as `A.sol → B.sol → A.sol`.
+fn format_import_cycle(cycle: &[PathBuf]) -> String {
+    cycle.iter().map(|path| path.display().to_string()).collect::<Vec<_>>().join(" → ")
+}
+
+/// Finds a cycle in the imports of the `sources` of a standard JSON input, whose paths are
+/// relative to the project `root`.
+///
+/// The imports of every source are parsed with `RE_SOL_IMPORT` and resolved like solc does:
+/// relative imports from the directory of the importing source, the others with the longest
+/// matching `remappings` or from the project root. Imports which don't resolve to one of the
+/// `sources` are ignored, as solc reports them itself. The import graph is then searched depth
+/// first, in the order of the sources.
+///
+/// # Returns
+/// The sources of the first cycle found, starting and ending with the same source, or `None` if
+/// the imports are acyclic.
+fn find_import_cycle(
+    sources: &BTreeMap<PathBuf, Source>,
+    root: &Path,
+    remappings: &[Remapping],
+) -> Option<Vec<PathBuf>> {
+    let imports: BTreeMap<&PathBuf, Vec<&PathBuf>> = sources
+        .iter()
+        .map(|(path, source)| {
+            let imports = RE_SOL_IMPORT
+                .captures_iter(source.content.as_str())
+                .filter_map(|capture| resolve_import(path, &capture[1], root, remappings))
+                .filter_map(|import| sources.get_key_value(&import).map(|(path, _)| path))
+                .collect();
+            (path, imports)
+        })
+        .collect();
+
+    let mut visited = HashSet::new();
+    for path in imports.keys() {
+        let mut stack = Vec::new();
+        if let Some(cycle) = find_cycle_from(path, &imports, &mut stack, &mut visited) {
+            return Some(cycle);
+        }
+    }
+    None
+}
+
+/// Searches the imports of `path` depth first, with the sources being imported in `stack`.
+///
+/// # Returns
+/// The cycle of the first import of a source in `stack`, or `None` if there is none. The sources
+/// of `visited` are not searched again, as none of their imports leads to a cycle.
+fn find_cycle_from<'a>(
+    path: &'a PathBuf,
+    imports: &BTreeMap<&'a PathBuf, Vec<&'a PathBuf>>,
+    stack: &mut Vec<&'a PathBuf>,
+    visited: &mut HashSet<&'a PathBuf>,
+) -> Option<Vec<PathBuf>> {
+    if let Some(start) = stack.iter().position(|source| *source == path) {
+        let mut cycle: Vec<PathBuf> =
+            stack[start..].iter().map(|path| path.to_path_buf()).collect();
+        cycle.push(path.clone());
+        return Some(cycle);
+    }
+    if visited.contains(path) {
+        return None;
+    }
+
+    stack.push(path);
+    for import in imports.get(path).into_iter().flatten() {
+        if let Some(cycle) = find_cycle_from(import, imports, stack, visited) {
+            return Some(cycle);
+        }
+    }
+    stack.pop();
+    visited.insert(path);
+    None
+}
+
+/// Resolves the path imported by the source at `path` as `import`, relative to the project `root`.
+fn resolve_import(
+    path: &Path,
+    import: &str,
+    root: &Path,
+    remappings: &[Remapping],
+) -> Option<PathBuf> {
+    let resolved = if import.starts_with("./") || import.starts_with("../") {
+        path.parent()?.join(import)
+    } else {
+        let remapping = remappings
+            .iter()
+            .filter(|remapping| import.starts_with(&remapping.name))
+            .max_by_key(|remapping| remapping.name.len());
+        match remapping {
+            Some(remapping) => {
+                let target = Path::new(&remapping.path).join(&import[remapping.name.len()..]);
+                target.strip_prefix(root).map(Path::to_path_buf).unwrap_or(target)
+            }
+            None => PathBuf::from(import),
+        }
+    };
+
+    // normalize the `.` and `..` components, like the paths of the standard JSON input
+    let mut normalized = PathBuf::new();
+    for component in resolved.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    Some(normalized)
+}
+
+/// Formats the deployed `libraries` of the project as the `settings.libraries` entry of the
+/// standard JSON input, in the form `{"path/to/lib.sol": {"LibraryName": "0xADDRESS"}}`.
+///
+/// Library paths are made relative to the project `root`, like the sources of the standard JSON
+/// input, so that zksolc can match them with the sources that define the libraries.
+fn libraries_settings(libraries: &Libraries, root: &Path) -> Map<String, Value> {
+    libraries
+        .libs
+        .iter()
+        .map(|(file, libs)| {
+            let file = file.strip_prefix(root).unwrap_or(file);
+            let libs = libs
+                .iter()
+                .map(|(name, address)| (name.clone(), Value::String(address.clone())))
+                .collect();
+            (file.to_string_lossy().to_string(), Value::Object(libs))
+        })
+        .collect()
+}
+
+/// Returns the hex encoded SHA-256 digest of the compiler binary at `path`.
+fn compiler_digest(path: &Path) -> Result<String> {
+    let binary =
+        fs::read(path).map_err(|e| ZkCompilationError::io("Could not read compiler", path, e))?;
+    Ok(hex::encode(Sha256::digest(binary)))
+}
+
+/// Returns true if `file`, a source path of the compiler output, is the source at `contract_path`.
+///
+/// The paths of the output are relative to the project root. They are compared as paths rather
+/// than as strings, so that `Token.sol` does not match `src/MyToken.sol` or `lib/dep/Token.sol`.
+fn is_output_of(root: &Path, file: &str, contract_path: &Path) -> bool {
+    root.join(file) == contract_path
+}
+
+/// Returns true if `error` is a warning whose code is one of the `ignored_error_codes`.
+///
+/// Only warnings can be suppressed, errors are always reported as they make the compilation fail.
+fn is_ignored_warning(error: &Value, ignored_error_codes: &[u64]) -> bool {
+    let is_warning =
+        error["severity"].as_str().map_or(false, |s| s.eq_ignore_ascii_case("warning"));
+    let code = error["errorCode"].as_str().and_then(|code| code.parse::<u64>().ok());
+    is_warning && code.map_or(false, |code| ignored_error_codes.contains(&code))
+}
+
+/// Parses an error or a warning reported by zksolc, with its `sourceLocation` if it has one.
+///
+/// The message falls back to the `formattedMessage` of zksolc, and the severity is lowercased.
+fn parse_diagnostic(error: &Value) -> ZkSolcDiagnostic {
+    let text = |key: &str| error.get(key).and_then(Value::as_str);
+    let location = error.get("sourceLocation");
+    let offset =
+        |key: &str| location.and_then(|l| l.get(key)).and_then(Value::as_i64).unwrap_or(-1);
+    ZkSolcDiagnostic {
+        severity: text("severity").unwrap_or("Unknown").to_lowercase(),
+        message: text("message").or_else(|| text("formattedMessage")).unwrap_or("").to_string(),
+        source_file: location
+            .and_then(|location| location.get("file"))
+            .and_then(Value::as_str)
+            .map(ToString::to_string),
+        start: offset("start"),
+        end: offset("end"),
+    }
+}
+
+/// This function returns the line of `source` the byte range `start..end` of a diagnostic starts
+/// in, or `None` if `start` is not a valid offset in `source`.
+///
+/// Ranges spanning several lines are underlined to the end of their first line.
+fn source_snippet(source: &str, start: i64, end: i64) -> Option<ZkSourceSnippet<'_>> {
+    let start = usize::try_from(start).ok()?;
+    if start > source.len() || !source.is_char_boundary(start) {
+        return None;
+    }
+    let line_start = source[..start].rfind('\n').map_or(0, |index| index + 1);
+    let line_end = source[start..].find('\n').map_or(source.len(), |index| start + index);
+    let end = usize::try_from(end).unwrap_or(start).clamp(start, line_end);
+    let padding =
+        source[line_start..start].chars().map(|c| if c == '\t' { '\t' } else { ' ' }).collect();
+    let width = source.get(start..end).map_or(0, |range| range.chars().count()).max(1);
+    Some(ZkSourceSnippet {
+        line_number: source[..start].matches('\n').count() + 1,
+        line: source[line_start..line_end].trim_end_matches('\r'),
+        padding,
+        width,
+    })
+}
+
+/// Reads the compiler output written for the contract `source` in the `artifacts` directory.
+///
+/// # Returns
+///
+/// The "contracts" field of the output, which maps every compiled file to its contracts.
+///
+/// # Errors
+///
+/// This function can return an error if the artifacts file cannot be read or parsed, for example
+/// because the source has not been compiled with `zk-build` yet.
+pub fn read_contract_output(artifacts: &Path, source: &Path) -> Result<Value> {
+    let filename =
+        source.file_name().ok_or_else(|| ZkCompilationError::FileNotFound(source.to_path_buf()))?;
+    let output_path = artifacts.join(filename).join("artifacts.json");
+
+    let data = fs::read_to_string(&output_path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => ZkCompilationError::FileNotFound(output_path.clone()),
+        _ => ZkCompilationError::io("Unable to read contract output file", &output_path, e),
+    })?;
+    let output: Value = serde_json::from_str(&data).map_err(|e| {
+        ZkCompilationError::json(
+            format!("Unable to parse JSON contract from {}", output_path.display()),
+            e,
+        )
+    })?;
+    Ok(output["contracts"].clone())
+}
+
+/// Decodes the zkEVM bytecode of a compiled `contract`, as found in the compiler output.
+///
+/// # Errors
+///
+/// This function can return an error if the contract has no bytecode or if it is not valid hex.
+pub fn get_bytecode(contract: &Value) -> Result<Vec<u8>> {
+    let object = contract["evm"]["bytecode"]["object"]
+        .as_str()
+        .ok_or_else(|| ZkCompilationError::InvalidOutput("Missing bytecode object".to_string()))?;
+    hex::decode(object.trim_start_matches("0x"))
+        .map_err(|e| ZkCompilationError::InvalidOutput(format!("Invalid bytecode object: {}", e)))
+}
+
+/// Retrieves the source map of a compiled `contract`, as found in the compiler output.
+///
+/// The source map is returned as emitted by zksolc, in the compressed `s:l:f:j` format of solc.
+/// `None` is returned if the contract has no source map, which is the case for interfaces and
+/// abstract contracts.
+pub fn get_source_map(contract: &Value) -> Option<String> {
+    contract["evm"]["bytecode"]["sourceMap"]
+        .as_str()
+        .filter(|source_map| !source_map.is_empty())
+        .map(str::to_string)
+}
+
+/// Retrieves the hex encoded deployed bytecode of a compiled `contract`, as found in the compiler
+/// output.
+///
+/// zksolc doesn't necessarily emit `evm.deployedBytecode`, as the deployed bytecode is the same as
+/// the bytecode on zkSync, in which case the latter is returned.
+pub fn get_deployed_bytecode(contract: &Value) -> String {
+    let deployed = contract["evm"]["deployedBytecode"]["object"]
+        .as_str()
+        .filter(|object| !object.trim_start_matches("0x").is_empty())
+        .or_else(|| contract["evm"]["bytecode"]["object"].as_str())
+        .unwrap_or_default();
+    format!("0x{}", deployed.trim_start_matches("0x"))
+}
+
+/// Retrieves the bytecode of the factory dependencies of a compiled `contract`.
+///
+/// zksolc lists the factory dependencies of a contract as a map from their bytecode hash to their
+/// identifier in the form `<path>:<contractname>`, which is looked up in the same compiler `output`.
+///
+/// # Errors
+///
+/// This function can return an error if a factory dependency is missing from the `output`.
+pub fn get_factory_dependencies(output: &Value, contract: &Value) -> Result<Vec<Vec<u8>>> {
+    let deps = match contract["factoryDependencies"].as_object() {
+        Some(deps) => deps,
+        None => return Ok(vec![]),
+    };
+
+    deps.values()
+        .map(|dep| {
+            let (path, name) =
+                dep.as_str().and_then(|dep| dep.rsplit_once(':')).ok_or_else(|| {
+                    ZkCompilationError::InvalidOutput(format!("Invalid factory dependency {}", dep))
+                })?;
+            get_bytecode(&output[path][name])
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn circular_imports_are_detected() {
+        let source = |content: &str| Source::new(content);
+        let remappings: Vec<Remapping> = vec!["@lib/=/project/lib/lib/src/".parse().unwrap()];
+        let mut sources = BTreeMap::from([
+            (PathBuf::from("src/A.sol"), source("import {B} from \"./B.sol\";\ncontract A {}")),
+            (
+                PathBuf::from("src/B.sol"),
+                source("import \"@lib/C.sol\";\nimport \"forge-std/Test.sol\";\ncontract B {}"),
+            ),
+            (PathBuf::from("lib/lib/src/C.sol"), source("contract C {}")),
+        ]);
+        assert_eq!(find_import_cycle(&sources, Path::new("/project"), &remappings), None);
+
+        sources.insert(
+            PathBuf::from("lib/lib/src/C.sol"),
+            source("import * as A from \"../../../src/A.sol\";\ncontract C {}"),
+        );
+        let cycle = find_import_cycle(&sources, Path::new("/project"), &remappings).unwrap();
+        assert_eq!(
+            ZkCompilationError::CircularImport(cycle).to_string(),
+            "Circular import detected: src/A.sol → src/B.sol → lib/lib/src/C.sol → src/A.sol"
+        );
+    }
+
+    #[test]
+    fn zksync_settings_include_force_evmla_only_when_set() {
+        let opts = ZkSolcOpts { force_evmla: true, ..Default::default() };
+        assert_eq!(opts.zksync_settings().get("forceEVMLA"), Some(&Value::Bool(true)));
+
+        let opts = ZkSolcOpts::default();
+        assert!(!opts.zksync_settings().contains_key("forceEVMLA"));
+    }
+
+    #[test]
+    fn zksync_settings_include_via_ir_only_when_set() {
+        let opts = ZkSolcOpts { via_ir: true, ..Default::default() };
+        assert_eq!(opts.zksync_settings().get("viaIR"), Some(&Value::Bool(true)));
+
+        let opts = ZkSolcOpts::default();
+        assert!(!opts.zksync_settings().contains_key("viaIR"));
+    }
+
+    #[test]
+    fn zksync_settings_include_optimizer_only_when_set() {
+        let opts = ZkSolcOpts { optimizer_mode: Some('z'), ..Default::default() };
+        assert_eq!(
+            opts.zksync_settings().get("optimizer"),
+            Some(&json!({ "enabled": true, "mode": "z" }))
+        );
+
+        let opts = ZkSolcOpts { optimize_for_size: true, ..Default::default() };
+        assert_eq!(
+            opts.zksync_settings().get("optimizer"),
+            Some(&json!({ "fallbackToOptimizingForSize": true }))
+        );
+
+        let opts = ZkSolcOpts::default();
+        assert!(!opts.zksync_settings().contains_key("optimizer"));
+    }
+
+    #[test]
+    fn zksync_settings_include_metadata_hash_only_when_set() {
+        let opts = ZkSolcOpts { metadata_hash: Some("none".to_string()), ..Default::default() };
+        assert_eq!(
+            opts.zksync_settings().get("metadata"),
+            Some(&json!({ "bytecodeHash": "none" }))
+        );
+
+        let opts = ZkSolcOpts::default();
+        assert!(!opts.zksync_settings().contains_key("metadata"));
+    }
+
+    #[test]
+    fn remappings_are_relative_to_the_project_root() {
+        let remappings = vec![
+            "@openzeppelin/=/project/lib/openzeppelin-contracts/".parse().unwrap(),
+            "ds-test/=lib/ds-test/src/".parse().unwrap(),
+        ];
+
+        let settings = remappings_settings(&remappings, Path::new("/project"));
+        assert_eq!(
+            settings,
+            vec![
+                json!("@openzeppelin/=lib/openzeppelin-contracts/"),
+                json!("ds-test/=lib/ds-test/src/")
+            ]
+        );
+    }
+
+    #[test]
+    fn include_paths_are_passed_with_the_base_path() {
+        let include_paths = vec![PathBuf::from("/project/src"), PathBuf::from("/shared/contracts")];
+        assert_eq!(
+            include_path_args(Path::new("/project"), None, &include_paths),
+            vec![
+                "--base-path",
+                "/project",
+                "--include-path",
+                "/project/src",
+                "--include-path",
+                "/shared/contracts"
+            ]
+        );
+        assert!(include_path_args(Path::new("/project"), None, &[]).is_empty());
+    }
+
+    #[test]
+    fn base_path_is_passed_without_include_paths() {
+        let root = Path::new("/project");
+        assert_eq!(include_path_args(root, Some(root), &[]), vec!["--base-path", "/project"]);
+        assert_eq!(
+            include_path_args(root, Some(Path::new("/base")), &[PathBuf::from("/shared")]),
+            vec!["--base-path", "/base", "--include-path", "/shared"]
+        );
+    }
+
+    #[test]
+    fn allow_paths_are_passed_as_a_list() {
+        let allow_paths = vec![PathBuf::from("/project"), PathBuf::from("/shared/lib")];
+        assert_eq!(allow_paths_args(&allow_paths), vec!["--allow-paths", "/project,/shared/lib"]);
+        assert!(allow_paths_args(&[]).is_empty());
+    }
+
+    #[test]
+    fn libraries_are_relative_to_the_project_root() {
+        let libraries = Libraries::parse(&[
+            "/project/src/Math.sol:Math:0x0000000000000000000000000000000000001234".to_string(),
+            "lib/Strings.sol:Strings:0x0000000000000000000000000000000000005678".to_string(),
+        ])
+        .unwrap();
+
+        let settings = libraries_settings(&libraries, Path::new("/project"));
+        assert_eq!(
+            Value::Object(settings),
+            json!({
+                "src/Math.sol": { "Math": "0x0000000000000000000000000000000000001234" },
+                "lib/Strings.sol": { "Strings": "0x0000000000000000000000000000000000005678" }
+            })
+        );
+        assert!(libraries_settings(&Libraries::default(), Path::new("/project")).is_empty());
+    }
+
+    #[test]
+    fn diagnostics_are_parsed_with_their_source_location() {
+        let error = json!({
+            "severity": "Warning",
+            "message": "Unused local variable.",
+            "sourceLocation": { "file": "src/Greeter.sol", "start": 10, "end": 20 }
+        });
+        let diagnostic = parse_diagnostic(&error);
+        assert_eq!(diagnostic.severity, "warning");
+        assert_eq!(diagnostic.message, "Unused local variable.");
+        assert_eq!(diagnostic.source_file.as_deref(), Some("src/Greeter.sol"));
+        assert_eq!((diagnostic.start, diagnostic.end), (10, 20));
+
+        let diagnostic = parse_diagnostic(&json!({ "severity": "error", "formattedMessage": "x" }));
+        assert_eq!(diagnostic.message, "x");
+        assert!(diagnostic.source_file.is_none());
+        assert_eq!((diagnostic.start, diagnostic.end), (-1, -1));
+    }
+
+    #[test]
+    fn diagnostics_are_rendered_with_their_source_line() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        let source = "contract Greeter {\n\tuint256 x = y;\n}\n";
+        fs::write(dir.path().join("src/Greeter.sol"), source).unwrap();
+        let start = source.find('y').unwrap() as i64;
+        let diagnostic = ZkSolcDiagnostic {
+            severity: "error".to_string(),
+            message: "Undeclared identifier.".to_string(),
+            source_file: Some("src/Greeter.sol".to_string()),
+            start: start - 4,
+            end: start + 1,
+        };
+
+        let rendered = diagnostic.render(dir.path());
+        assert!(rendered.contains("Undeclared identifier."));
+        assert!(rendered.contains(" --> src/Greeter.sol:2:10\n"));
+        assert!(rendered.contains("2 | \tuint256 x = y;\n"));
+        assert!(rendered.contains(&format!("  | \t{}", " ".repeat(8))));
+        assert!(rendered.contains("^^^^^"));
+
+        assert_eq!(diagnostic.to_string(), "error: Undeclared identifier.\n --> src/Greeter.sol");
+
+        let snippet = source_snippet(source, start, -1).unwrap();
+        assert_eq!((snippet.line_number, snippet.width), (2, 1));
+        assert!(source_snippet(source, 1000, 1001).is_none());
+
+        let diagnostic = ZkSolcDiagnostic { source_file: None, ..diagnostic };
+        assert!(diagnostic.render(dir.path()).ends_with(": Undeclared identifier."));
+    }
+
+    #[test]
+    fn factory_dependencies_are_resolved_from_the_output() {
+        let output = json!({
+            "src/Factory.sol": {
+                "Factory": {
+                    "evm": { "bytecode": { "object": "0102" } },
+                    "factoryDependencies": { "0xabcd": "src/Child.sol:Child" }
+                }
+            },
+            "src/Child.sol": {
+                "Child": { "evm": { "bytecode": { "object": "0x0304" } } }
+            }
+        });
+
+        let factory = &output["src/Factory.sol"]["Factory"];
+        assert_eq!(get_bytecode(factory).unwrap(), vec![0x01, 0x02]);
+        assert_eq!(get_factory_dependencies(&output, factory).unwrap(), vec![vec![0x03, 0x04]]);
+
+        let child = &output["src/Child.sol"]["Child"];
+        assert!(get_factory_dependencies(&output, child).unwrap().is_empty());
+    }
+
+    #[test]
+    fn zksolc_can_be_shared_between_compilation_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<ZkSolc>();
+        assert_send_sync::<ZkCompilationJob>();
+    }
+
+    #[test]
+    fn cache_key_depends_on_the_compiler_binary() {
+        let dir = tempfile::tempdir().unwrap();
+        let compiler = dir.path().join("zksolc");
+        fs::write(&compiler, "v1").unwrap();
+        let digest = compiler_digest(&compiler).unwrap();
+        fs::write(&compiler, "v2").unwrap();
+        let rebuilt = compiler_digest(&compiler).unwrap();
+        assert_ne!(digest, rebuilt);
+
+        let args = vec!["--standard-json".to_string()];
+        let input = serde_json::json!({ "language": "Solidity" });
+        assert_eq!(
+            ZkSolc::cache_key(&digest, &args, &input).unwrap(),
+            ZkSolc::cache_key(&digest, &args, &input).unwrap()
+        );
+        assert_ne!(
+            ZkSolc::cache_key(&digest, &args, &input).unwrap(),
+            ZkSolc::cache_key(&rebuilt, &args, &input).unwrap()
+        );
+    }
+
+    #[test]
+    fn output_is_matched_with_the_exact_source_path() {
+        let root = Path::new("/project");
+        let contract_path = Path::new("/project/src/Token.sol");
+        assert!(is_output_of(root, "src/Token.sol", contract_path));
+        assert!(is_output_of(root, "/project/src/Token.sol", contract_path));
+        assert!(!is_output_of(root, "src/MyToken.sol", contract_path));
+        assert!(!is_output_of(root, "lib/dep/src/Token.sol", contract_path));
+    }
+
+    #[test]
+    fn only_warnings_with_ignored_codes_are_suppressed() {
+        let warning = json!({ "severity": "warning", "errorCode": "5574" });
+        assert!(is_ignored_warning(&warning, &[5574]));
+        assert!(!is_ignored_warning(&warning, &[1878]));
+
+        let error = json!({ "severity": "error", "errorCode": "5574" });
+        assert!(!is_ignored_warning(&error, &[5574]));
+        assert!(!is_ignored_warning(&json!({ "severity": "warning" }), &[5574]));
+    }
+
+    #[test]
+    fn missing_artifacts_are_reported_as_not_found() {
+        let err = read_contract_output(Path::new("/nonexistent/zkout"), Path::new("src/A.sol"))
+            .unwrap_err();
+        assert!(matches!(err, ZkCompilationError::FileNotFound(path)
+            if path == Path::new("/nonexistent/zkout/A.sol/artifacts.json")));
+    }
+
+    #[test]
+    fn compiled_contract_size_and_kind() {
+        let contract = ZkCompiledContract {
+            name: "src/Counter.sol:Counter".to_string(),
+            bytecode: "0x000102".to_string(),
+            deployed_bytecode: "0x000102".to_string(),
+            abi: json!([{ "type": "function", "name": "increment", "inputs": [] }]),
+            source_map: None,
+            method_identifiers: json!({ "increment()": "d09de08a" }),
+            metadata: Value::Null,
+            storage_layout: None,
+            devdoc: None,
+            userdoc: None,
+        };
+        assert_eq!(contract.contract_name(), "Counter");
+        assert_eq!(contract.bytecode_size(), 3);
+        assert!(!contract.is_dev_contract());
+
+        let test = ZkCompiledContract {
+            abi: json!([{ "type": "function", "name": "testIncrement", "inputs": [] }]),
+            ..contract
+        };
+        assert!(test.is_dev_contract());
+    }
+
+    #[test]
+    fn source_map_is_read_from_the_bytecode() {
+        let contract = json!({
+            "evm": { "bytecode": { "object": "0102", "sourceMap": "26:85:0:-:0;;;;" } }
+        });
+        assert_eq!(get_source_map(&contract).as_deref(), Some("26:85:0:-:0;;;;"));
+
+        let interface = json!({ "evm": { "bytecode": { "object": "", "sourceMap": "" } } });
+        assert!(get_source_map(&interface).is_none());
+        assert!(get_source_map(&json!({})).is_none());
+    }
+
+    #[test]
+    fn deployed_bytecode_falls_back_to_the_bytecode() {
+        let contract = json!({ "evm": { "bytecode": { "object": "0102" } } });
+        assert_eq!(get_deployed_bytecode(&contract), "0x0102");
+
+        let contract = json!({
+            "evm": { "bytecode": { "object": "0102" }, "deployedBytecode": { "object": "0x03" } }
+        });
+        assert_eq!(get_deployed_bytecode(&contract), "0x03");
+    }
+
+    #[test]
+    fn compilation_output_gives_access_to_contracts_and_diagnostics() {
+        let contract = |name: &str| ZkCompiledContract {
+            name: name.to_string(),
+            bytecode: "0x00".to_string(),
+            deployed_bytecode: "0x00".to_string(),
+            abi: json!([]),
+            source_map: None,
+            method_identifiers: json!({}),
+            metadata: Value::Null,
+            storage_layout: None,
+            devdoc: None,
+            userdoc: None,
+        };
+        let diagnostic = |severity: &str| ZkSolcDiagnostic {
+            severity: severity.to_string(),
+            message: format!("{severity} message"),
+            source_file: None,
+            start: -1,
+            end: -1,
+        };
+        let output = ZkCompilationOutput {
+            sources: vec![
+                ZkSourceOutcome {
+                    contracts: vec![contract("src/A.sol:A"), contract("src/A.sol:Shared")],
+                    diagnostics: vec![diagnostic("warning"), diagnostic("Warning")],
+                    ..Default::default()
+                },
+                ZkSourceOutcome {
+                    contracts: vec![contract("src/B.sol:Shared")],
+                    diagnostics: vec![diagnostic("error")],
+                    ..Default::default()
+                },
+            ],
+        };
+
+        assert_eq!(output.find_contract("A").unwrap().name, "src/A.sol:A");
+        assert_eq!(output.find_contract("src/B.sol:Shared").unwrap().name, "src/B.sol:Shared");
+        // ambiguous or unknown names
+        assert!(output.find_contract("Shared").is_none());
+        assert!(output.find_contract("C").is_none());
+
+        assert_eq!(
+            output.errors().map(|d| d.message.as_str()).collect::<Vec<_>>(),
+            ["error message"]
+        );
+        assert_eq!(
+            output.warnings().map(|d| d.message.as_str()).collect::<Vec<_>>(),
+            ["warning message", "Warning message"]
+        );
+    }
+
+    #[test]
+    fn suppressed_warnings_are_still_collected() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = ethers::solc::ProjectPathsConfig::builder().root(dir.path()).build().unwrap();
+        let project = Project::builder().paths(paths).ephemeral().no_artifacts().build().unwrap();
+        let opts = ZkSolcOpts { suppress_warnings: true, ..Default::default() };
+        let zksolc = ZkSolc::new(opts, project);
+
+        let warning = json!({
+            "severity": "warning",
+            "message": "Unused local variable.",
+            "formattedMessage": "Warning: Unused local variable.",
+            "errorCode": "2072"
+        });
+        let mut diagnostics = Vec::new();
+        let output = json!({ "errors": [warning] });
+        zksolc.handle_output_errors(&output, &mut HashSet::new(), &mut diagnostics).unwrap();
+        assert_eq!(diagnostics.len(), 1);
+
+        // errors are never suppressed
+        let error = json!({
+            "severity": "error",
+            "message": "Undeclared identifier.",
+            "formattedMessage": "DeclarationError: Undeclared identifier."
+        });
+        let output = json!({ "errors": [error] });
+        let err = zksolc.handle_output_errors(&output, &mut HashSet::new(), &mut Vec::new());
+        assert!(matches!(err, Err(ZkCompilationError::Compilation(errors)) if errors.len() == 1));
+    }
+
+    #[test]
+    fn artifacts_are_only_available_after_compiling() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = ethers::solc::ProjectPathsConfig::builder().root(dir.path()).build().unwrap();
+        let project = Project::builder().paths(paths).ephemeral().no_artifacts().build().unwrap();
+        let mut zksolc = ZkSolc::new(ZkSolcOpts::default(), project);
+        assert!(matches!(zksolc.get_artifact("Counter"), Err(ZkCompilationError::NotCompiled)));
+
+        let contract = ZkCompiledContract {
+            name: "src/Counter.sol:Counter".to_string(),
+            bytecode: "0x0102".to_string(),
+            deployed_bytecode: "0x0102".to_string(),
+            abi: json!([]),
+            source_map: Some("1:2:0".to_string()),
+            method_identifiers: json!({ "increment()": "d09de08a" }),
+            metadata: Value::Null,
+            storage_layout: None,
+            devdoc: None,
+            userdoc: None,
+        };
+        zksolc.compiled = Some(vec![contract.clone()]);
+        assert_eq!(zksolc.get_artifact("Counter").unwrap(), Some(contract.to_artifact()));
+        assert_eq!(
+            zksolc.get_artifact("src/Counter.sol:Counter").unwrap(),
+            Some(contract.to_artifact())
+        );
+        assert_eq!(zksolc.get_artifact("Other").unwrap(), None);
+    }
+
+    #[test]
+    fn foundry_artifacts_match_the_foundry_layout() {
+        let contract = ZkCompiledContract {
+            name: "src/Counter.sol:Counter".to_string(),
+            bytecode: "0x0102".to_string(),
+            deployed_bytecode: "0x0102".to_string(),
+            abi: json!([]),
+            source_map: Some("26:85:0:-:0".to_string()),
+            method_identifiers: json!({ "increment()": "d09de08a" }),
+            metadata: json!("{}"),
+            storage_layout: None,
+            devdoc: None,
+            userdoc: None,
+        };
+        assert_eq!(
+            contract.to_foundry_artifact(),
+            json!({
+                "abi": [],
+                "bytecode": { "object": "0x0102", "sourceMap": "26:85:0:-:0", "linkReferences": {} },
+                "deployedBytecode": {
+                    "object": "0x0102",
+                    "sourceMap": "26:85:0:-:0",
+                    "linkReferences": {}
+                },
+                "methodIdentifiers": { "increment()": "d09de08a" },
+                "metadata": "{}",
+            })
+        );
+
+        let storage_layout = json!({ "storage": [], "types": {} });
+        let upgradeable = ZkCompiledContract {
+            storage_layout: Some(serde_json::from_value(storage_layout.clone()).unwrap()),
+            ..contract.clone()
+        };
+        assert_eq!(upgradeable.to_foundry_artifact()["storageLayout"], storage_layout);
+
+        let documented = ZkCompiledContract {
+            devdoc: Some(json!({ "title": "A counter" })),
+            userdoc: Some(json!({ "notice": "Counts" })),
+            ..contract.clone()
+        };
+        assert_eq!(documented.to_foundry_artifact()["devdoc"], json!({ "title": "A counter" }));
+        assert_eq!(documented.to_foundry_artifact()["userdoc"], json!({ "notice": "Counts" }));
+
+        let out_dir = Path::new("/project/out/zksync");
+        assert_eq!(
+            foundry_artifact_path(out_dir, &contract, false),
+            Path::new("/project/out/zksync/Counter.json")
+        );
+        assert_eq!(
+            foundry_artifact_path(out_dir, &contract, true),
+            Path::new("/project/out/zksync/Counter.sol/Counter.json")
+        );
+    }
+
+    #[test]
+    fn hardhat_artifacts_match_the_hardhat_layout() {
+        let contract = ZkCompiledContract {
+            name: "contracts/Greeter.sol:Greeter".to_string(),
+            bytecode: "0102".to_string(),
+            deployed_bytecode: "0x0102".to_string(),
+            abi: json!([]),
+            source_map: None,
+            method_identifiers: json!({}),
+            metadata: json!("{}"),
+            storage_layout: None,
+            devdoc: None,
+            userdoc: None,
+        };
+        assert_eq!(
+            contract.to_hardhat_artifact(),
+            json!({
+                "_format": "hh-sol-artifact-1",
+                "contractName": "Greeter",
+                "sourceName": "contracts/Greeter.sol",
+                "abi": [],
+                "bytecode": "0x0102",
+                "deployedBytecode": "0x0102",
+                "linkReferences": {},
+                "deployedLinkReferences": {},
+            })
+        );
+        assert_eq!(
+            hardhat_artifact_path(Path::new("/project/artifacts"), &contract),
+            Path::new("/project/artifacts/contracts/Greeter.sol/Greeter.json")
+        );
+    }
+
+    #[test]
+    fn compilation_db_entries_replicate_the_zksolc_invocation() {
+        let job = ZkCompilationJob {
+            contract_path: PathBuf::from("/my project/src/Counter.sol"),
+            filename: "Counter.sol".to_string(),
+            standard_json: json!({}),
+            comp_args: vec![
+                "--standard-json".to_string(),
+                "--solc".to_string(),
+                "/bin/solc".to_string(),
+            ],
+            cache_key: String::new(),
+        };
+        let entry = compilation_db_entry(
+            Path::new("/bin/zksolc"),
+            Path::new("/my project"),
+            &job,
+            Path::new("/my project/zkout/Counter.sol/json_input.json"),
+        );
+        assert_eq!(
+            entry,
+            json!({
+                "directory": "/my project",
+                "file": "/my project/src/Counter.sol",
+                "arguments": [
+                    "/bin/zksolc",
+                    "/my project/src/Counter.sol",
+                    "--standard-json",
+                    "--solc",
+                    "/bin/solc"
+                ],
+                "command": "/bin/zksolc '/my project/src/Counter.sol' --standard-json --solc /bin/solc \
+                    < '/my project/zkout/Counter.sol/json_input.json'",
+            })
+        );
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+    }
+
+    #[test]
+    fn extra_output_is_appended_to_the_selection() {
+        let mut selection: FileOutputSelection = BTreeMap::default();
+        selection.insert("*".to_string(), vec!["abi".to_string()]);
+        let selectors =
+            ["storageLayout", "abi", "ast", "evm.deployedBytecode"].map(ToString::to_string);
+        add_extra_output(&mut selection, &selectors);
+
+        assert_eq!(selection["*"], vec!["abi", "storageLayout", "evm.deployedBytecode"]);
+        assert_eq!(selection[""], vec!["ast"]);
+    }
+
+    #[test]
+    fn select_output_follows_dotted_selectors() {
+        let contract = json!({
+            "abi": [],
+            "evm": { "methodIdentifiers": { "increment()": "d09de08a" } }
+        });
+        assert_eq!(
+            select_output(&contract, "evm.methodIdentifiers"),
+            &json!({ "increment()": "d09de08a" })
+        );
+        assert_eq!(select_output(&contract, "abi"), &json!([]));
+        assert!(select_output(&contract, "storageLayout").is_null());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hanging_compilers_are_killed_after_the_timeout() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mock_compiler = |name: &str, script: &str| {
+            let path = dir.path().join(name);
+            fs::write(&path, format!("#!/bin/sh\n{script}\n")).unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+            Command::new(path)
+        };
+
+        let started = Instant::now();
+        let output = output_with_timeout(
+            &mut mock_compiler("sleeping", "exec sleep 30"),
+            b"{}".to_vec(),
+            Duration::from_millis(200),
+        )
+        .unwrap();
+        assert!(output.is_none());
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        // The input is larger than a pipe buffer, so it must be written while stdout is read
+        let input = vec![b'a'; 1 << 20];
+        let output = output_with_timeout(
+            &mut mock_compiler("echoing", "cat"),
+            input.clone(),
+            Duration::from_secs(30),
+        )
+        .unwrap()
+        .unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, input);
+    }
+
+    #[test]
+    fn opts_builder_requires_a_consistent_compiler_setup() {
+        let mut builder = ZkSolcOptsBuilder::default();
+        builder.with_via_ir(true).with_jobs(Some(2));
+        let err = builder.build().unwrap_err();
+        assert_eq!(err.to_string(), "The path of the zksolc binary is not set");
+
+        let opts = builder.with_compiler_path("/bin/zksolc").build().unwrap();
+        assert_eq!(opts.compiler_path, PathBuf::from("/bin/zksolc"));
+        assert!(opts.via_ir);
+        assert_eq!(opts.jobs, Some(2));
+        assert!(!opts.force_evmla);
+
+        let err = builder.with_force_evmla(true).build().unwrap_err();
+        assert!(err.to_string().contains("mutually exclusive"), "{err}");
     }
 }
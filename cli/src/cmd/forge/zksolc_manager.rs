@@ -4,13 +4,14 @@
 ///
 /// This module consists of several key components:
 ///
-/// * `ZkSolcVersion`: This enum represents the available versions of the zksolc compiler. The `parse_version` function
-///   accepts a string version and returns the corresponding `ZkSolcVersion` variant.
+/// * `ZkSolcVersion`: This structure represents a supported version of the zksolc compiler. It is parsed from a
+///   string in the `v1.3.x` or `1.3.x` form with `FromStr`, and displayed in the `v1.3.x` form.
 ///
 /// * `ZkSolcOS`: This enum represents the supported operating systems for the zksolc compiler.
 ///   `get_operating_system` function determines the current operating system and returns the corresponding `ZkSolcOS` variant.
 ///
-/// * `ZkSolcManagerOpts`: This structure holds options for creating a `ZkSolcManagerBuilder`, which includes the version of the compiler to be used.
+/// * `ZkSolcManagerOpts`: This structure holds options for creating a `ZkSolcManagerBuilder`, which includes the version of the compiler to be used,
+///   either as an exact version or as a `ZkSolcVersionReq` requirement resolved against the versions published upstream.
 ///
 /// * `ZkSolcManagerBuilder`: This structure is used to construct a `ZkSolcManager`. It holds options like compilers path, version, compiler name,
 ///   and download URL. It has a `build` function which constructs a `ZkSolcManager` instance.
@@ -21,80 +22,195 @@
 /// This module abstracts the details of managing the zksolc compiler, making it easier for developers to use
 /// different versions of the compiler without dealing with the details of downloading, setting up, and switching
 /// between versions. It is part of a larger framework for managing and interacting with zkSync contracts.
-use anyhow::{anyhow, Context, Error, Result};
+use anyhow::{anyhow, Error, Result};
+use atty::{self, Stream};
 use dirs;
-use reqwest::blocking::Client;
-use serde::Serialize;
+use fd_lock::{RwLock, RwLockWriteGuard};
+use indicatif::{ProgressBar, ProgressStyle};
+use reqwest::{blocking::Client, NoProxy, Proxy};
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::fs::File;
-use std::io::copy;
-use std::{fmt, fs, os::unix::prelude::PermissionsExt, path::PathBuf};
+use std::io::{self, copy};
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    os::unix::prelude::PermissionsExt,
+    path::{Path, PathBuf},
+    str::FromStr,
+    thread,
+    time::{Duration, Instant},
+};
 use url::Url;
 
 const ZKSOLC_DOWNLOAD_BASE_URL: &str = "https://github.com/matter-labs/zksolc-bin/raw/main";
 
-/// `ZkSolcVersion` is an enumeration of the supported versions of the `zksolc` compiler.
-///
-/// Each variant in this enum represents a specific version of the `zksolc` compiler:
-/// * `V138` corresponds to version 1.3.8 of the `zksolc` compiler.
-/// * `V139` corresponds to version 1.3.9 of the `zksolc` compiler.
-///
-/// The `get_version` method is provided to get a string representation of the version associated with each enum variant.
+const ZKSOLC_RELEASES_URL: &str =
+    "https://api.github.com/repos/matter-labs/zksolc-bin/releases?per_page=100";
+
+/// Default timeout of the download of the `zksolc` binary.
+const ZKSOLC_DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Default duration to wait for a download of the `zksolc` binary by another process to complete.
+const ZKSOLC_DOWNLOAD_LOCK_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Interval at which the download lock is polled while another process holds it.
+const ZKSOLC_DOWNLOAD_LOCK_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Name of the file in the compilers directory which is locked for the duration of a download, so
+/// that concurrent processes don't download the same binary at the same time.
+const ZKSOLC_DOWNLOAD_LOCK_FILE: &str = ".download.lock";
+
+/// Environment variables configuring the proxy of the requests to the upstream releases, in the
+/// order of precedence. Both the upper and lower case spellings are supported.
+const PROXY_ENV_VARS: &[&str] = &["HTTPS_PROXY", "HTTP_PROXY", "ALL_PROXY"];
+
+/// Name of the file in the compilers directory caching the versions from the last successful
+/// query of the upstream releases.
+const ZKSOLC_VERSIONS_CACHE_FILE: &str = "zksolc-versions.json";
+
+/// Name of the file in the compilers directory caching the version resolved for `latest`.
+const ZKSOLC_LATEST_VERSION_CACHE_FILE: &str = ".zksolc_latest_version";
+
+/// How long the version cached for `latest` is used before the releases are queried again.
+const ZKSOLC_LATEST_VERSION_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Expected SHA-256 digests of the published `zksolc` binaries, keyed by version (e.g. `v1.3.9`)
+/// like the digests of `ZkSolcManagerBuilder::with_expected_hash`. Every platform has its own
+/// binary, so every version lists the digest of each platform, keyed by its compiler prefix (e.g.
+/// `zksolc-linux-amd64-musl-`), see `expected_hash`.
 ///
-/// This enumeration is used in the `ZkSolcManager` to specify the `zksolc` compiler version to be used for contract compilation.
-#[derive(Debug, Clone, Serialize)]
-pub enum ZkSolcVersion {
-    V135,
-    V136,
-    V137,
-    V138,
-    V139,
-    V1310,
-    V1311,
-}
+/// Digests have to be taken from the upstream release. Binaries without an entry in this table
+/// are only verified if an expected hash is provided via
+/// `ZkSolcManagerBuilder::with_expected_hash`, otherwise a warning is printed once they are
+/// downloaded.
+const ZKSOLC_EXPECTED_HASHES: &[(&str, &[(&str, &str)])] = &[];
+
+/// The versions of the `zksolc` compiler supported by foundry-zksync.
+const SUPPORTED_ZKSOLC_VERSIONS: [&str; 7] =
+    ["1.3.5", "1.3.6", "1.3.7", "1.3.8", "1.3.9", "1.3.10", "1.3.11"];
 
 pub const DEFAULT_ZKSOLC_VERSION: &str = "v1.3.11";
 
-/// `parse_version` parses a string representation of a `zksolc` compiler version
-/// and returns the `ZkSolcVersion` enum variant if it matches a supported version.
-///
-/// # Arguments
+/// `ZkSolcVersion` is a supported version of the `zksolc` compiler.
 ///
-/// * `version`: A string slice of the `zksolc` version to parse.
+/// It is parsed from both the `v1.3.9` and `1.3.9` forms, and always displayed in the `v1.3.9` form
+/// of the names of the zksolc releases. Versions are ordered like the semver versions they wrap.
 ///
-/// # Returns
-///
-/// A `Result` with the `ZkSolcVersion` variant for the parsed version, or an `Err`
-/// if the version isn't supported.
-fn parse_version(version: &str) -> Result<ZkSolcVersion> {
-    match version {
-        "v1.3.5" => Ok(ZkSolcVersion::V135),
-        "v1.3.6" => Ok(ZkSolcVersion::V136),
-        "v1.3.7" => Ok(ZkSolcVersion::V137),
-        "v1.3.8" => Ok(ZkSolcVersion::V138),
-        "v1.3.9" => Ok(ZkSolcVersion::V139),
-        "v1.3.10" => Ok(ZkSolcVersion::V1310),
-        "v1.3.11" => Ok(ZkSolcVersion::V1311),
-        _ => Err(Error::msg(
-            "ZkSolc compiler version not supported. Proper version format: 'v1.3.x'",
-        )),
-    }
+/// This structure is used in the `ZkSolcManager` to specify the `zksolc` compiler version to be used for contract
+/// compilation.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ZkSolcVersion(Version);
+
+/// The error returned when parsing an invalid or unsupported `ZkSolcVersion`.
+#[derive(Debug, thiserror::Error)]
+pub enum ZkSolcVersionError {
+    /// The version is not a valid semver version.
+    #[error("Invalid zksolc version `{version}`: {error}. Proper version format: 'v1.3.x'")]
+    Invalid {
+        version: String,
+        #[source]
+        error: semver::Error,
+    },
+    /// The version is valid, but not supported by foundry-zksync.
+    #[error("ZkSolc compiler version {0} not supported. Proper version format: 'v1.3.x'")]
+    Unsupported(String),
 }
 
 impl ZkSolcVersion {
-    /// `get_version` returns a string slice representing the `ZkSolcVersion` variant.
+    /// Returns the semver version of the `zksolc` compiler.
+    pub fn version(&self) -> &Version {
+        &self.0
+    }
+
+    /// Returns whether the `version` of the `zksolc` compiler is supported by foundry-zksync.
+    fn is_supported(version: &Version) -> bool {
+        SUPPORTED_ZKSOLC_VERSIONS.iter().any(|supported| *supported == version.to_string())
+    }
+}
+
+impl Default for ZkSolcVersion {
+    /// Returns the `DEFAULT_ZKSOLC_VERSION`.
+    fn default() -> Self {
+        DEFAULT_ZKSOLC_VERSION.parse().expect("the default zksolc version is supported")
+    }
+}
+
+impl FromStr for ZkSolcVersion {
+    type Err = ZkSolcVersionError;
+
+    fn from_str(version: &str) -> Result<Self, Self::Err> {
+        let parsed = Version::parse(version.trim().trim_start_matches('v'))
+            .map_err(|error| ZkSolcVersionError::Invalid { version: version.to_string(), error })?;
+        if !Self::is_supported(&parsed) {
+            return Err(ZkSolcVersionError::Unsupported(format!("v{parsed}")));
+        }
+        Ok(Self(parsed))
+    }
+}
+
+impl fmt::Display for ZkSolcVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "v{}", self.0)
+    }
+}
+
+impl Serialize for ZkSolcVersion {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// `ZkSolcVersionSelection` is the `zksolc` version selected with `--use-zksolc` or `use_zksolc` in
+/// the `[zksync]` section of the config: either a supported version, or `latest` for the newest
+/// stable release, which is resolved with `ZkSolcManager::get_latest_version`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ZkSolcVersionSelection {
+    /// The newest stable release supported by foundry-zksync.
+    Latest,
+    /// A specific version.
+    Version(ZkSolcVersion),
+}
+
+impl ZkSolcVersionSelection {
+    /// Returns the selected version, querying the releases if `latest` is selected.
     ///
-    /// # Returns
+    /// # Errors
     ///
-    /// A string slice representing the `ZkSolcVersion` variant.
-    fn get_version(&self) -> &str {
+    /// This function can return an `Err` if `latest` is selected and the releases cannot be queried,
+    /// see `ZkSolcManager::get_latest_version`.
+    pub fn resolve(self) -> Result<ZkSolcVersion> {
         match self {
-            ZkSolcVersion::V135 => "v1.3.5",
-            ZkSolcVersion::V136 => "v1.3.6",
-            ZkSolcVersion::V137 => "v1.3.7",
-            ZkSolcVersion::V138 => "v1.3.8",
-            ZkSolcVersion::V139 => "v1.3.9",
-            ZkSolcVersion::V1310 => "v1.3.10",
-            ZkSolcVersion::V1311 => "v1.3.11",
+            ZkSolcVersionSelection::Latest => ZkSolcManager::get_latest_version(),
+            ZkSolcVersionSelection::Version(version) => Ok(version),
+        }
+    }
+}
+
+impl Default for ZkSolcVersionSelection {
+    /// Returns the `DEFAULT_ZKSOLC_VERSION`.
+    fn default() -> Self {
+        ZkSolcVersionSelection::Version(ZkSolcVersion::default())
+    }
+}
+
+impl FromStr for ZkSolcVersionSelection {
+    type Err = ZkSolcVersionError;
+
+    fn from_str(version: &str) -> Result<Self, Self::Err> {
+        if version.trim().eq_ignore_ascii_case("latest") {
+            return Ok(ZkSolcVersionSelection::Latest);
+        }
+        version.parse().map(ZkSolcVersionSelection::Version)
+    }
+}
+
+impl fmt::Display for ZkSolcVersionSelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ZkSolcVersionSelection::Latest => f.write_str("latest"),
+            ZkSolcVersionSelection::Version(version) => version.fmt(f),
         }
     }
 }
@@ -162,14 +278,101 @@ impl ZkSolcOS {
     }
 }
 
+/// A requirement on the version of the `zksolc` compiler, like `>=1.3.5, <1.4.0`.
+///
+/// The highest supported version published upstream which satisfies the requirement is selected
+/// when building the `ZkSolcManager`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZkSolcVersionReq(VersionReq);
+
+/// The error returned by `ZkSolcVersionReq::parse` for an invalid version requirement.
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid zksolc version requirement `{req}`: {error}")]
+pub struct ZkSolcVersionReqError {
+    req: String,
+    #[source]
+    error: semver::Error,
+}
+
+/// The error returned when `ZkSolcManagerBuilder::build` is called with invalid options.
+///
+/// Each variant is a setting to fix before anything is compiled, rather than an error in the
+/// middle of the build.
+#[derive(Debug, thiserror::Error)]
+pub enum ZkSolcManagerBuildError {
+    /// The version set with `ZkSolcManagerBuilder::with_version` is invalid or not supported.
+    #[error(transparent)]
+    InvalidVersion(#[from] ZkSolcVersionError),
+    /// The published versions could not be listed to resolve the version requirement.
+    #[error("Failed to list the published zksolc versions: {0}")]
+    VersionsUnavailable(#[source] Error),
+    /// None of the published versions satisfies the version requirement.
+    #[error("No supported zksolc version satisfies the requirement `{0}`")]
+    NoMatchingVersion(ZkSolcVersionReq),
+    /// The home directory, in which the compilers directory is created by default, is not known.
+    #[error("Could not build SolcManager - homedir not found")]
+    HomeDirNotFound,
+    /// The compilers directory cannot be written to, so the compiler cannot be downloaded to it.
+    #[error("The compilers directory {} is not writable: {error}", path.display())]
+    CompilersDirNotWritable {
+        path: PathBuf,
+        #[source]
+        error: io::Error,
+    },
+    /// The download URL has a scheme the compiler cannot be downloaded with.
+    #[error("Unsupported scheme of the zksolc download URL {0}, use an http or https URL")]
+    UnsupportedUrlScheme(Url),
+    /// The current operating system has no `zksolc` binary.
+    #[error("Failed to determine OS for compiler: {0}")]
+    UnsupportedOs(#[source] Error),
+}
+
+impl ZkSolcVersionReq {
+    /// Parses a version requirement in the semver syntax, like `>=1.3.5, <1.4.0` or `^1.3`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ZkSolcVersionReqError` if `req` is not a valid semver requirement.
+    pub fn parse(req: &str) -> Result<Self, ZkSolcVersionReqError> {
+        VersionReq::parse(req)
+            .map(Self)
+            .map_err(|error| ZkSolcVersionReqError { req: req.to_string(), error })
+    }
+
+    /// Returns the highest of the `available` versions which satisfies the requirement and is
+    /// supported by `ZkSolcVersion`.
+    fn select(&self, available: &[Version]) -> Option<ZkSolcVersion> {
+        available
+            .iter()
+            .filter(|version| self.0.matches(version))
+            .filter(|version| ZkSolcVersion::is_supported(version))
+            .max()
+            .map(|version| ZkSolcVersion(version.clone()))
+    }
+}
+
+impl fmt::Display for ZkSolcVersionReq {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct ZkSolcManagerOpts {
-    version: String,
+    version: ZkSolcVersion,
+    #[serde(skip)]
+    version_req: Option<ZkSolcVersionReq>,
 }
 
 impl ZkSolcManagerOpts {
-    pub fn new(version: String) -> Self {
-        Self { version }
+    pub fn new(version: ZkSolcVersion) -> Self {
+        Self { version, version_req: None }
+    }
+
+    /// Creates the options for the highest published `zksolc` version satisfying `version_req`,
+    /// rather than for an exact version.
+    pub fn with_version_req(version_req: ZkSolcVersionReq) -> Self {
+        Self { version: ZkSolcVersion::default(), version_req: Some(version_req) }
     }
 }
 
@@ -181,6 +384,10 @@ impl ZkSolcManagerOpts {
 /// * `version`: A string that represents the version of the zkSync compiler to be used.
 /// * `compiler`: An optional string that describes the compiler name.
 /// * `download_url`: The base URL from where the zkSync compiler binary is to be downloaded.
+/// * `timeout`: The maximum duration of the download of the compiler binary, 120 seconds by default.
+/// * `offline`: Whether the compiler binary must already be present, rather than being downloaded.
+/// * `lock_timeout`: The maximum duration to wait for a concurrent download of the compiler binary, 300 seconds
+///   by default.
 ///
 /// The builder provides a `new` function to initialize the structure with `ZkSolcManagerOpts`, and a `build` function
 /// which constructs and returns a `ZkSolcManager` instance.
@@ -199,16 +406,22 @@ impl ZkSolcManagerOpts {
 /// ```
 #[derive(Debug, Clone)]
 pub struct ZkSolcManagerBuilder {
-    _compilers_path: Option<PathBuf>,
-    version: String,
+    compilers_path: Option<PathBuf>,
+    version: ZkSolcVersion,
+    requested_version: Option<String>,
+    version_req: Option<ZkSolcVersionReq>,
     _compiler: Option<String>,
     download_url: Url,
+    expected_hashes: BTreeMap<String, String>,
+    timeout: Duration,
+    offline: bool,
+    lock_timeout: Duration,
 }
 
 impl ZkSolcManagerBuilder {
     /// Constructs a new instance of `ZkSolcManagerBuilder` using the provided `ZkSolcManagerOpts`.
     ///
-    /// This function takes a `ZkSolcManagerOpts` instance as a parameter, which contains the zkSync compiler version as a string.
+    /// This function takes a `ZkSolcManagerOpts` instance as a parameter, which contains the zkSync compiler version.
     /// The builder's `version` field is initialized with this version. The `compilers_path` and `compiler` fields are initialized
     /// as `None` since they are optional and can be set later.
     ///
@@ -226,13 +439,137 @@ impl ZkSolcManagerBuilder {
     /// Returns a new `ZkSolcManagerBuilder` instance.
     pub fn new(opts: ZkSolcManagerOpts) -> Self {
         Self {
-            _compilers_path: None,
+            compilers_path: None,
             version: opts.version,
+            requested_version: None,
+            version_req: opts.version_req,
             _compiler: None,
             download_url: Url::parse(ZKSOLC_DOWNLOAD_BASE_URL).unwrap(),
+            expected_hashes: BTreeMap::new(),
+            timeout: ZKSOLC_DOWNLOAD_TIMEOUT,
+            offline: false,
+            lock_timeout: ZKSOLC_DOWNLOAD_LOCK_TIMEOUT,
         }
     }
 
+    /// Sets the version of the `zksolc` compiler, overriding the one of the `ZkSolcManagerOpts`.
+    ///
+    /// The version is only parsed by `build`, which fails with `ZkSolcManagerBuildError::InvalidVersion` if it
+    /// is not a supported `ZkSolcVersion`.
+    ///
+    /// # Parameters
+    ///
+    /// * `version`: The version of the `zksolc` compiler, in the format `v1.3.x` or `1.3.x`.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `ZkSolcManagerBuilder` with the version set.
+    pub fn with_version(mut self, version: impl Into<String>) -> Self {
+        self.requested_version = Some(version.into());
+        self
+    }
+
+    /// Sets the directory the `zksolc` binaries are stored in, instead of `~/.zksync`.
+    ///
+    /// # Parameters
+    ///
+    /// * `compilers_path`: The path of the compilers directory, which is created if it doesn't exist.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `ZkSolcManagerBuilder` with the compilers directory set.
+    pub fn with_compilers_path(mut self, compilers_path: impl Into<PathBuf>) -> Self {
+        self.compilers_path = Some(compilers_path.into());
+        self
+    }
+
+    /// Sets the base URL the `zksolc` binary is downloaded from, instead of `ZKSOLC_DOWNLOAD_BASE_URL`.
+    ///
+    /// # Parameters
+    ///
+    /// * `download_url`: The base URL, which must have the `http` or `https` scheme.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `ZkSolcManagerBuilder` with the download URL set.
+    pub fn with_download_url(mut self, download_url: Url) -> Self {
+        self.download_url = download_url;
+        self
+    }
+
+    /// Sets the timeout of the download of the `zksolc` binary.
+    ///
+    /// The timeout applies to the whole download, from connecting to the server until the body of
+    /// the response has been read. On slow networks it can be raised above the default of 120
+    /// seconds, rather than letting the download hang indefinitely.
+    ///
+    /// # Parameters
+    ///
+    /// * `timeout`: The maximum duration of the download.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `ZkSolcManagerBuilder` with the timeout set.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Enables the offline mode, in which the network is never accessed.
+    ///
+    /// In offline mode the `zksolc` binary is expected to be provisioned in the compilers directory
+    /// beforehand: instead of downloading a missing binary, `ZkSolcManager::download` returns an
+    /// error.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `ZkSolcManagerBuilder` with the offline mode enabled.
+    pub fn offline(mut self) -> Self {
+        self.offline = true;
+        self
+    }
+
+    /// Sets how long to wait for another process downloading the `zksolc` binary.
+    ///
+    /// Downloads are serialized with a lock in the compilers directory. If the lock is held by
+    /// another process, the download waits up to `lock_timeout` for it to be released and then
+    /// re-checks whether the binary has been downloaded in the meantime.
+    ///
+    /// # Parameters
+    ///
+    /// * `lock_timeout`: The maximum duration to wait for the lock.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `ZkSolcManagerBuilder` with the lock timeout set.
+    pub fn with_lock_timeout(mut self, lock_timeout: Duration) -> Self {
+        self.lock_timeout = lock_timeout;
+        self
+    }
+
+    /// Sets the expected SHA-256 digest of the `zksolc` binary for the given version.
+    ///
+    /// The downloaded binary of that version is verified against this digest instead of the one
+    /// in the bundled hash table. This allows verifying versions which are not part of the bundled
+    /// table, or binaries downloaded from a different `download_url`.
+    ///
+    /// # Parameters
+    ///
+    /// * `version`: The version of the `zksolc` compiler, in the format `v1.3.x`.
+    /// * `hash`: The hex encoded SHA-256 digest of the binary for the current operating system.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `ZkSolcManagerBuilder` with the expected hash registered.
+    pub fn with_expected_hash(
+        mut self,
+        version: impl Into<String>,
+        hash: impl Into<String>,
+    ) -> Self {
+        self.expected_hashes.insert(version.into(), hash.into());
+        self
+    }
+
     /// Returns the appropriate compiler string based on the current operating system.
     ///
     /// This function determines the current operating system using `get_operating_system`, and returns the corresponding
@@ -246,10 +583,10 @@ impl ZkSolcManagerBuilder {
     /// # Errors
     ///
     /// This function can return an `Err` if the operating system cannot be determined using `get_operating_system`.
-    fn get_compiler(self) -> Result<String> {
+    fn get_compiler(&self) -> Result<String, ZkSolcManagerBuildError> {
         get_operating_system()
-            .with_context(|| "Failed to determine OS for compiler")
             .map(|it| it.get_compiler().to_string())
+            .map_err(ZkSolcManagerBuildError::UnsupportedOs)
     }
 
     /// `build` constructs and returns a `ZkSolcManager` instance based on the provided configuration options.
@@ -258,33 +595,64 @@ impl ZkSolcManagerBuilder {
     /// options, resolves the necessary details, and creates the manager instance.
     ///
     /// The function performs the following steps:
-    /// 1. Obtains the home directory path and appends the `.zksync` directory to it, which represents the compilers directory.
-    /// 2. Parses the provided version string and verifies if it matches one of the supported `ZkSolcVersion` variants.
-    /// 3. Determines the appropriate compiler string based on the current operating system using the `get_compiler` function.
-    /// 4. Constructs a `ZkSolcManager` instance with the resolved compilers directory, version, compiler, and download URL.
+    /// 1. Checks that the download URL has the `http` or `https` scheme.
+    /// 2. Uses the compilers directory set with `with_compilers_path`, or the `.zksync` directory of the home directory,
+    ///    and checks that it can be written to.
+    /// 3. Parses the version set with `with_version`, or uses the provided `ZkSolcVersion`.
+    ///    If a version requirement was provided instead, the highest supported version satisfying it is selected from
+    ///    the versions published upstream.
+    /// 4. Determines the appropriate compiler string based on the current operating system using the `get_compiler` function.
+    /// 5. Constructs a `ZkSolcManager` instance with the resolved compilers directory, version, compiler, and download URL.
     ///
     /// # Returns
     ///
-    /// A `Result` containing the constructed `ZkSolcManager` instance if the build process is successful, or an `Err` if any errors occur.
+    /// A `Result` containing the constructed `ZkSolcManager` instance if the build process is successful, or a
+    /// `ZkSolcManagerBuildError` if any of the options is invalid.
     ///
     /// # Errors
     ///
     /// The function can return an `Err` in the following cases:
-    /// * If the home directory path cannot be determined.
-    /// * If the provided version string cannot be parsed into a valid `ZkSolcVersion` variant.
+    /// * If the download URL has an unsupported scheme.
+    /// * If the home directory path cannot be determined, or the compilers directory is not writable.
+    /// * If the version set with `with_version` is invalid or not supported.
+    /// * If the published versions cannot be listed, or none of them satisfies the version requirement.
     /// * If the current operating system is not supported or cannot be determined.
-    pub fn build(self) -> Result<ZkSolcManager> {
-        // TODO: try catching & returning errors quickly (rather than doing 'long' if and return else at the end)
-        let mut home_path =
-            dirs::home_dir().ok_or(anyhow!("Could not build SolcManager - homedir not found"))?;
-        home_path.push(".zksync");
-        let version = self.version.to_string();
+    pub fn build(self) -> Result<ZkSolcManager, ZkSolcManagerBuildError> {
         let download_url = self.download_url.to_owned();
+        if !matches!(download_url.scheme(), "http" | "https") {
+            return Err(ZkSolcManagerBuildError::UnsupportedUrlScheme(download_url));
+        }
+
+        let compilers_path = match &self.compilers_path {
+            Some(compilers_path) => compilers_path.clone(),
+            None => {
+                default_compilers_path().map_err(|_| ZkSolcManagerBuildError::HomeDirNotFound)?
+            }
+        };
+        check_writable(&compilers_path).map_err(|error| {
+            ZkSolcManagerBuildError::CompilersDirNotWritable { path: compilers_path.clone(), error }
+        })?;
+
+        let version = match (&self.requested_version, &self.version_req) {
+            (Some(version), _) => version.parse::<ZkSolcVersion>()?,
+            (None, Some(version_req)) => {
+                let available = ZkSolcManager::available_versions()
+                    .map_err(ZkSolcManagerBuildError::VersionsUnavailable)?;
+                version_req.select(&available).ok_or_else(|| {
+                    ZkSolcManagerBuildError::NoMatchingVersion(version_req.clone())
+                })?
+            }
+            (None, None) => self.version.clone(),
+        };
         let compiler = self.get_compiler()?;
-        let compilers_path = home_path.to_owned();
 
-        let solc_version = parse_version(&version)?;
-        return Ok(ZkSolcManager::new(compilers_path, solc_version, compiler, download_url));
+        let expected_hash =
+            expected_hash(ZKSOLC_EXPECTED_HASHES, &self.expected_hashes, &version, &compiler);
+        Ok(ZkSolcManager::new(compilers_path, version, compiler, download_url)
+            .with_expected_hash(expected_hash)
+            .with_timeout(self.timeout)
+            .with_offline(self.offline)
+            .with_lock_timeout(self.lock_timeout))
     }
 }
 
@@ -301,12 +669,16 @@ impl ZkSolcManagerBuilder {
 /// * `version`: A `ZkSolcVersion` representing the specific version of the `zksolc` compiler managed by this instance.
 /// * `compiler`: A `String` representing the compiler name.
 /// * `download_url`: A `Url` representing the base URL from which the `zksolc` compiler binary is downloaded.
+/// * `expected_hash`: An optional hex encoded SHA-256 digest the downloaded binary is verified against.
+/// * `timeout`: The maximum duration of the download of the compiler binary.
+/// * `offline`: Whether a missing compiler binary is reported as an error instead of being downloaded.
+/// * `lock_timeout`: The maximum duration to wait for a concurrent download of the compiler binary.
 ///
 /// # Example
 ///
 /// ```
 /// let compilers_path = PathBuf::from("/path/to/compilers");
-/// let version = ZkSolcVersion::V139;
+/// let version: ZkSolcVersion = "v1.3.9".parse().unwrap();
 /// let compiler = "zksolc-linux-amd64-musl-v1.3.9".to_string();
 /// let download_url = Url::parse("https://github.com/matter-labs/zksolc-bin/raw/main").unwrap();
 ///
@@ -337,6 +709,10 @@ pub struct ZkSolcManager {
     version: ZkSolcVersion,
     compiler: String,
     download_url: Url,
+    expected_hash: Option<String>,
+    timeout: Duration,
+    offline: bool,
+    lock_timeout: Duration,
 }
 
 impl fmt::Display for ZkSolcManager {
@@ -353,7 +729,7 @@ impl fmt::Display for ZkSolcManager {
                 exists: {}
             )",
             self.compilers_path.display(),
-            self.version.get_version(),
+            self.version,
             self.compiler,
             self.download_url,
             self.get_full_compiler(),
@@ -363,7 +739,266 @@ impl fmt::Display for ZkSolcManager {
     }
 }
 
+/// A release of the `zksolc` compiler as returned by the GitHub releases API.
+#[derive(Debug, Clone, Deserialize)]
+struct ZkSolcRelease {
+    tag_name: String,
+    #[serde(default)]
+    prerelease: bool,
+}
+
+/// Checks that files can be created in the directory `path`, or in its closest existing ancestor if
+/// it doesn't exist yet, by creating and removing a temporary file.
+///
+/// # Errors
+///
+/// Returns the `io::Error` of the creation of the temporary file.
+fn check_writable(path: &Path) -> io::Result<()> {
+    let existing = match path.ancestors().find(|ancestor| ancestor.exists()) {
+        Some(existing) => existing,
+        None => {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "no existing parent directory"))
+        }
+    };
+    tempfile::tempfile_in(existing)?;
+    Ok(())
+}
+
+/// Returns the default directory where the compiler binaries are stored: `~/.zksync`.
+///
+/// # Errors
+///
+/// Returns an `Err` if the home directory path cannot be determined.
+fn default_compilers_path() -> Result<PathBuf> {
+    let mut home_path =
+        dirs::home_dir().ok_or(anyhow!("Could not build SolcManager - homedir not found"))?;
+    home_path.push(".zksync");
+    Ok(home_path)
+}
+
+/// Returns the proxies configured with the `HTTPS_PROXY`, `HTTP_PROXY` and `ALL_PROXY` environment
+/// variables, as looked up by `var`.
+///
+/// `HTTPS_PROXY` and `HTTP_PROXY` only apply to requests of their scheme, `ALL_PROXY` applies to
+/// every request which isn't covered by one of them. Hosts listed in `NO_PROXY` are always
+/// requested directly.
+///
+/// # Errors
+///
+/// Returns an `Err` if one of the variables is set to an invalid proxy URL.
+fn proxies_from_env(var: impl Fn(&str) -> Option<String>) -> Result<Vec<Proxy>> {
+    let lookup = |name: &str| {
+        var(name).or_else(|| var(&name.to_lowercase())).filter(|value| !value.trim().is_empty())
+    };
+    let no_proxy = lookup("NO_PROXY").and_then(|no_proxy| NoProxy::from_string(&no_proxy));
+
+    let mut proxies = Vec::new();
+    for name in PROXY_ENV_VARS {
+        let url = match lookup(name) {
+            Some(url) => url,
+            None => continue,
+        };
+        let proxy = match *name {
+            "HTTPS_PROXY" => Proxy::https(&url),
+            "HTTP_PROXY" => Proxy::http(&url),
+            _ => Proxy::all(&url),
+        }
+        .map_err(|e| anyhow!("Invalid proxy URL in {}: {}", name, e))?;
+        proxies.push(proxy.no_proxy(no_proxy.clone()));
+    }
+    Ok(proxies)
+}
+
+/// Builds the HTTP client used to query the upstream releases and to download the binaries.
+///
+/// The client routes its requests through the proxies configured in the environment, see
+/// `proxies_from_env`.
+///
+/// # Errors
+///
+/// Returns an `Err` if the proxy configuration is invalid or the client cannot be built.
+fn http_client(timeout: Duration) -> Result<Client> {
+    let mut builder = Client::builder().timeout(timeout);
+    for proxy in proxies_from_env(|name| std::env::var(name).ok())? {
+        builder = builder.proxy(proxy);
+    }
+    builder.build().map_err(|e| anyhow!("Failed to build HTTP client: {}", e))
+}
+
+/// Parses a release tag like `v1.3.9` into a `Version`.
+///
+/// Returns `None` for tags that are not valid semver versions, so that malformed tags published
+/// upstream are skipped rather than failing the whole listing.
+fn parse_release_tag(tag: &str) -> Option<Version> {
+    Version::parse(tag.trim().trim_start_matches('v')).ok()
+}
+
+/// Returns the newest of the `releases` supported by foundry-zksync, skipping the releases marked
+/// as pre-releases and the versions with a pre-release suffix like `-rc.1`.
+fn latest_stable_version(releases: &[ZkSolcRelease]) -> Option<ZkSolcVersion> {
+    releases
+        .iter()
+        .filter(|release| !release.prerelease)
+        .filter_map(|release| parse_release_tag(&release.tag_name))
+        .filter(|version| version.pre.is_empty() && ZkSolcVersion::is_supported(version))
+        .max()
+        .map(ZkSolcVersion)
+}
+
+/// Returns the version cached in `cache_file` for `latest`, or `None` if there is none, if it is
+/// not a supported version, or if the file was written more than `max_age` ago.
+fn read_latest_version_cache(cache_file: &Path, max_age: Duration) -> Option<ZkSolcVersion> {
+    let modified = fs::metadata(cache_file).and_then(|metadata| metadata.modified()).ok()?;
+    if modified.elapsed().map_or(true, |age| age > max_age) {
+        return None;
+    }
+    fs::read_to_string(cache_file).ok()?.parse().ok()
+}
+
 impl ZkSolcManager {
+    /// Returns the sorted list of `zksolc` versions published upstream.
+    ///
+    /// The versions are retrieved from the GitHub releases of the `zksolc-bin` repository. On success the
+    /// list is cached in the compilers directory, so that if the releases cannot be queried later on (e.g.
+    /// because there is no network) the list from the last successful query is returned instead.
+    ///
+    /// Release tags which are not valid semver versions are ignored.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result<Vec<Version>>` with the published versions sorted in ascending order.
+    ///
+    /// # Errors
+    ///
+    /// This function can return an `Err` if the releases cannot be queried and there is no cached list of
+    /// versions to fall back to.
+    pub fn available_versions() -> Result<Vec<Version>> {
+        let cache_file = default_compilers_path()?.join(ZKSOLC_VERSIONS_CACHE_FILE);
+
+        match Self::fetch_available_versions() {
+            Ok(versions) => {
+                // caching is best effort, failing to write the cache must not fail the listing
+                if let Some(parent) = cache_file.parent() {
+                    let _ = fs::create_dir_all(parent);
+                }
+                let cached: Vec<String> = versions.iter().map(|v| format!("v{v}")).collect();
+                if let Ok(cached) = serde_json::to_string_pretty(&cached) {
+                    let _ = fs::write(&cache_file, cached);
+                }
+                Ok(versions)
+            }
+            Err(err) => {
+                let cached = fs::read_to_string(&cache_file).map_err(|_| {
+                    anyhow!("Failed to fetch zksolc releases and no cached versions found: {}", err)
+                })?;
+                let cached: Vec<String> = serde_json::from_str(&cached)
+                    .map_err(|e| anyhow!("Failed to parse cached zksolc versions: {}", e))?;
+                let mut versions: Vec<Version> =
+                    cached.iter().filter_map(|tag| parse_release_tag(tag)).collect();
+                versions.sort();
+                Ok(versions)
+            }
+        }
+    }
+
+    /// Returns the sorted list of `zksolc` versions installed in the compilers directory for the
+    /// current operating system.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result<Vec<Version>>` with the installed versions sorted in ascending order, empty if the
+    /// compilers directory doesn't exist yet.
+    ///
+    /// # Errors
+    ///
+    /// This function can return an `Err` if the operating system is not supported, or if the compilers
+    /// directory cannot be read.
+    pub fn installed_versions() -> Result<Vec<Version>> {
+        let compilers_path = default_compilers_path()?;
+        let compiler = get_operating_system()?.get_compiler().to_string();
+        if !compilers_path.exists() {
+            return Ok(vec![]);
+        }
+
+        let entries = fs::read_dir(&compilers_path)
+            .map_err(|e| anyhow!("Failed to read {}: {}", compilers_path.display(), e))?;
+        let mut versions: Vec<Version> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_file())
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                name.strip_prefix(&compiler).and_then(parse_release_tag)
+            })
+            .collect();
+        versions.sort();
+        Ok(versions)
+    }
+
+    /// Queries the GitHub releases API for the published `zksolc` versions.
+    fn fetch_available_versions() -> Result<Vec<Version>> {
+        let releases: Vec<ZkSolcRelease> = http_client(ZKSOLC_DOWNLOAD_TIMEOUT)?
+            .get(ZKSOLC_RELEASES_URL)
+            .header("User-Agent", "foundry-zksync")
+            .send()
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| anyhow!("Failed to query zksolc releases: {}", e))?
+            .json()
+            .map_err(|e| anyhow!("Failed to parse zksolc releases: {}", e))?;
+
+        let mut versions: Vec<Version> =
+            releases.iter().filter_map(|release| parse_release_tag(&release.tag_name)).collect();
+        versions.sort();
+        versions.dedup();
+        Ok(versions)
+    }
+
+    /// Returns the newest stable release of `zksolc` supported by foundry-zksync, for
+    /// `--use-zksolc latest`.
+    ///
+    /// The releases are queried from the GitHub releases API, skipping pre-releases. The version
+    /// found is cached in the `.zksolc_latest_version` file of the compilers directory and reused
+    /// for 24 hours, so that builds don't query the releases every time.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result<ZkSolcVersion>` with the newest stable supported version.
+    ///
+    /// # Errors
+    ///
+    /// This function can return an `Err` if the releases cannot be queried and no version was
+    /// cached before, or if no stable release is supported.
+    pub fn get_latest_version() -> Result<ZkSolcVersion> {
+        let cache_file = default_compilers_path()?.join(ZKSOLC_LATEST_VERSION_CACHE_FILE);
+        if let Some(version) = read_latest_version_cache(&cache_file, ZKSOLC_LATEST_VERSION_MAX_AGE)
+        {
+            return Ok(version);
+        }
+
+        let releases = http_client(ZKSOLC_DOWNLOAD_TIMEOUT)?
+            .get(ZKSOLC_RELEASES_URL)
+            .header("User-Agent", "foundry-zksync")
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|response| response.json::<Vec<ZkSolcRelease>>());
+        let releases = match releases {
+            Ok(releases) => releases,
+            // a stale version is better than failing the build without network
+            Err(err) => {
+                return read_latest_version_cache(&cache_file, Duration::MAX)
+                    .ok_or_else(|| anyhow!("Failed to query the latest zksolc release: {}", err))
+            }
+        };
+
+        let version = latest_stable_version(&releases)
+            .ok_or_else(|| anyhow!("No stable zksolc release is supported"))?;
+        // caching is best effort, failing to write the cache must not fail the build
+        if let Some(parent) = cache_file.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        let _ = fs::write(&cache_file, version.to_string());
+        Ok(version)
+    }
+
     /// Constructs a new instance of `ZkSolcManager` with the specified configuration options.
     ///
     /// This function creates a new `ZkSolcManager` instance with the provided parameters. It initializes
@@ -386,7 +1021,137 @@ impl ZkSolcManager {
         compiler: String,
         download_url: Url,
     ) -> Self {
-        Self { compilers_path, version, compiler, download_url }
+        Self {
+            compilers_path,
+            version,
+            compiler,
+            download_url,
+            expected_hash: None,
+            timeout: ZKSOLC_DOWNLOAD_TIMEOUT,
+            offline: false,
+            lock_timeout: ZKSOLC_DOWNLOAD_LOCK_TIMEOUT,
+        }
+    }
+
+    /// Sets the expected SHA-256 digest of the `zksolc` compiler binary.
+    ///
+    /// If set, the binary is verified against this digest after it has been downloaded.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `ZkSolcManager` with the expected hash set.
+    pub fn with_expected_hash(mut self, expected_hash: Option<String>) -> Self {
+        self.expected_hash = expected_hash;
+        self
+    }
+
+    /// Sets the timeout of the download of the `zksolc` compiler binary.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `ZkSolcManager` with the timeout set.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets whether the manager is in offline mode, in which a missing compiler binary is reported as
+    /// an error instead of being downloaded.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `ZkSolcManager` with the offline mode set.
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Returns whether the manager is in offline mode.
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    /// Sets how long to wait for another process downloading the `zksolc` compiler binary.
+    ///
+    /// # Returns
+    ///
+    /// Returns the `ZkSolcManager` with the lock timeout set.
+    pub fn with_lock_timeout(mut self, lock_timeout: Duration) -> Self {
+        self.lock_timeout = lock_timeout;
+        self
+    }
+
+    /// Acquires the exclusive download lock of the compilers directory.
+    ///
+    /// If another process holds the lock, it is polled until it's released or `lock_timeout` has
+    /// elapsed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Err` if the lock cannot be acquired within `lock_timeout`.
+    fn lock_download<'a>(&self, lock: &'a mut RwLock<File>) -> Result<RwLockWriteGuard<'a, File>> {
+        let deadline = Instant::now() + self.lock_timeout;
+        loop {
+            match lock.try_write() {
+                Ok(_) => break,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    if Instant::now() >= deadline {
+                        return Err(anyhow!(
+                            "Timed out after {}s waiting for another zksolc download to complete; remove {} if no download is running",
+                            self.lock_timeout.as_secs_f64(),
+                            self.compilers_path.join(ZKSOLC_DOWNLOAD_LOCK_FILE).display()
+                        ));
+                    }
+                    thread::sleep(ZKSOLC_DOWNLOAD_LOCK_POLL_INTERVAL);
+                }
+                Err(e) => return Err(anyhow!("Failed to lock the zksolc download: {}", e)),
+            }
+        }
+        // the guard of `try_write` can't be returned from within the loop, the lock is free now so
+        // this only blocks if another process took it in between
+        lock.write().map_err(|e| anyhow!("Failed to lock the zksolc download: {}", e))
+    }
+
+    /// Returns the progress bar of a download of `total` bytes, if known.
+    ///
+    /// The bar shows the received and total bytes, the download speed and the ETA. It is hidden if
+    /// stdout is not a terminal, in which case the `Downloading ...` line printed by the caller is the
+    /// only feedback, rather than a bar redrawn into a log file.
+    fn download_progress(total: Option<u64>) -> ProgressBar {
+        if !atty::is(Stream::Stdout) {
+            return ProgressBar::hidden();
+        }
+        match total {
+            Some(total) => {
+                let pb = ProgressBar::new(total);
+                pb.set_style(
+                    ProgressStyle::with_template(
+                        "{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})",
+                    )
+                    .unwrap()
+                    .progress_chars("#>-"),
+                );
+                pb
+            }
+            None => {
+                let pb = ProgressBar::new_spinner();
+                pb.set_style(
+                    ProgressStyle::with_template(
+                        "{spinner:.green} [{elapsed_precise}] {bytes} ({bytes_per_sec})",
+                    )
+                    .unwrap(),
+                );
+                pb
+            }
+        }
+    }
+
+    /// Returns the error reported when the download doesn't complete within the timeout.
+    fn download_timed_out(&self) -> Error {
+        anyhow!(
+            "Download timed out after {}s; retry or use --compiler-path to specify a local binary",
+            self.timeout.as_secs_f64()
+        )
     }
 
     /// Returns the full name of the `zksolc` compiler, including the version.
@@ -400,7 +1165,12 @@ impl ZkSolcManager {
     /// Returns a `String` representing the full name of the `zksolc` compiler, including the version.
     ///
     pub fn get_full_compiler(&self) -> String {
-        return format!("{}{}", self.compiler, self.version.get_version());
+        return format!("{}{}", self.compiler, self.version);
+    }
+
+    /// Returns the version of the `zksolc` compiler managed by this instance.
+    pub fn get_version(&self) -> &ZkSolcVersion {
+        &self.version
     }
 
     /// Returns the full download URL for the `zksolc` compiler binary based on the current operating system.
@@ -471,15 +1241,50 @@ impl ZkSolcManager {
         Ok(())
     }
 
+    /// Makes sure the `zksolc` compiler of this manager is ready to use, and returns its path.
+    ///
+    /// This combines the setup of the compilers directory with `check_setup_compilers_dir` and the
+    /// download of the compiler with `download` if it doesn't exist yet. The download holds the lock
+    /// of the compilers directory, so concurrent builds don't download the same binary twice.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result<PathBuf>` with the full path to the `zksolc` binary.
+    ///
+    /// # Errors
+    ///
+    /// This function can return an `Err` if the compilers directory cannot be created, or if the
+    /// compiler doesn't exist and cannot be downloaded, for example in offline mode.
+    pub fn ensure_version(&self) -> Result<PathBuf> {
+        self.check_setup_compilers_dir()
+            .map_err(|e| anyhow!("Failed to setup compilers directory: {}", e))?;
+        self.download().map_err(|e| {
+            if self.offline {
+                e
+            } else {
+                anyhow!("Failed to download the file: {}", e)
+            }
+        })?;
+        Ok(self.get_full_compiler_path())
+    }
+
     /// Downloads the `zksolc` compiler binary if it doesn't already exist in the compilers directory.
     ///
     /// This function downloads the `zksolc` compiler binary from the specified download URL if it doesn't already exist
     /// in the compilers directory. It performs the following steps:
     /// 1. Checks if the compiler binary already exists in the compilers directory using the `exists` function.
-    /// 2. If the binary exists, the function returns early without performing any download.
-    /// 3. If the binary doesn't exist, it sends a HTTP GET request to the download URL to retrieve the binary.
-    /// 4. If the download is successful, it creates the output file in the compilers directory and writes the binary data to it.
-    /// 5. Finally, it sets the appropriate permissions for the downloaded compiler binary.
+    /// 2. If the binary exists, the function returns early without performing any download. In offline
+    ///    mode, a missing binary is reported as an error without accessing the network.
+    /// 3. Acquires the download lock of the compilers directory, waiting for a concurrent download by another
+    ///    process to complete. If the binary was downloaded in the meantime, the function returns early.
+    /// 4. If the binary doesn't exist, it sends a HTTP GET request to the download URL to retrieve the binary,
+    ///    which has to complete within the configured timeout. The request goes through the proxy configured
+    ///    with the `HTTPS_PROXY`, `HTTP_PROXY` or `ALL_PROXY` environment variables, if any.
+    /// 5. If the download is successful, it creates the output file in the compilers directory and writes the binary data to it,
+    ///    showing the progress of the download if stdout is a terminal.
+    /// 6. If an expected hash is known for the binary, it verifies the SHA-256 digest of the written file and
+    ///    deletes the file if it doesn't match.
+    /// 7. Finally, it sets the appropriate permissions for the downloaded compiler binary, and releases the lock.
     ///
     /// # Returns
     ///
@@ -488,9 +1293,14 @@ impl ZkSolcManager {
     /// # Errors
     ///
     /// This function can return an `Err` if any errors occur during the download or setup process, including:
+    /// * If the binary doesn't exist and the manager is in offline mode.
+    /// * If the download lock cannot be acquired within the lock timeout.
     /// * If the download URL cannot be obtained using `get_full_download_url`.
+    /// * If the proxy configured in the environment is invalid.
     /// * If the HTTP GET request to the download URL fails.
+    /// * If the download doesn't complete within the timeout.
     /// * If the output file cannot be created or written to.
+    /// * If the SHA-256 digest of the downloaded binary doesn't match the expected hash.
     /// * If the permissions for the downloaded compiler binary cannot be set.
     pub fn download(&self) -> Result<()> {
         if self.exists() {
@@ -498,24 +1308,68 @@ impl ZkSolcManager {
             return Ok(());
         }
 
+        if self.offline {
+            return Err(anyhow!(
+                "offline mode: zksolc {} not found at {}; provision it before running.",
+                self.version,
+                self.get_full_compiler_path().display()
+            ));
+        }
+
+        self.check_setup_compilers_dir()?;
+        let lock_file = File::options()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(self.compilers_path.join(ZKSOLC_DOWNLOAD_LOCK_FILE))
+            .map_err(|e| Error::msg(format!("Failed to open the download lock file: {}", e)))?;
+        let mut lock = RwLock::new(lock_file);
+        let _guard = self.lock_download(&mut lock)?;
+
+        // another process may have completed the download while waiting for the lock
+        if self.exists() {
+            return Ok(());
+        }
+
         let url = self
             .get_full_download_url()
             .map_err(|e| Error::msg(format!("Could not get full download url: {}", e)))?;
 
-        let client = Client::new();
-        let mut response = client
-            .get(url)
-            .send()
-            .map_err(|e| Error::msg(format!("Failed to download file: {}", e)))?;
+        let client = http_client(self.timeout)?;
+        let mut response = client.get(url).send().map_err(|e| {
+            if e.is_timeout() {
+                self.download_timed_out()
+            } else {
+                Error::msg(format!("Failed to download file: {}", e))
+            }
+        })?;
 
         if response.status().is_success() {
             let mut output_file = File::create(self.get_full_compiler_path())
                 .map_err(|e| Error::msg(format!("Failed to create output file: {}", e)))?;
 
-            copy(&mut response, &mut output_file)
-                .map_err(|e| Error::msg(format!("Failed to write the downloaded file: {}", e)))?;
+            let pb = Self::download_progress(response.content_length());
+            let copied = copy(&mut pb.wrap_read(&mut response), &mut output_file);
+            pb.finish_and_clear();
+            if let Err(e) = copied {
+                // don't leave a partial binary behind for a later `exists` check
+                let _ = fs::remove_file(self.get_full_compiler_path());
+                if e.kind() == io::ErrorKind::TimedOut {
+                    return Err(self.download_timed_out());
+                }
+                return Err(Error::msg(format!("Failed to write the downloaded file: {}", e)));
+            }
 
             let compiler_path = self.compilers_path.join(self.get_full_compiler());
+            match &self.expected_hash {
+                Some(expected_hash) => verify_checksum(&compiler_path, expected_hash)?,
+                None => eprintln!(
+                    "{}: no checksum is known for {}, the downloaded binary was not verified.",
+                    yansi::Paint::yellow("warning").bold(),
+                    self.get_full_compiler()
+                ),
+            }
+
             fs::set_permissions(compiler_path, PermissionsExt::from_mode(0o755)).map_err(|e| {
                 Error::msg(format!("Failed to set zksync compiler permissions: {e}"))
             })?;
@@ -528,3 +1382,371 @@ impl ZkSolcManager {
         Ok(())
     }
 }
+
+/// Returns the expected SHA-256 digest of the `compiler` binary of `version`.
+///
+/// The digest set for the version with `ZkSolcManagerBuilder::with_expected_hash` is used if there
+/// is one, the digest of the `compiler` platform in the bundled `table` otherwise. `None` is
+/// returned if neither knows the binary, in which case it is not verified.
+fn expected_hash(
+    table: &[(&str, &[(&str, &str)])],
+    overrides: &BTreeMap<String, String>,
+    version: &ZkSolcVersion,
+    compiler: &str,
+) -> Option<String> {
+    let version = version.to_string();
+    if let Some(hash) = overrides.get(&version) {
+        return Some(hash.clone());
+    }
+    let (_, hashes) = table.iter().find(|(table_version, _)| *table_version == version)?;
+    hashes.iter().find(|(platform, _)| *platform == compiler).map(|(_, hash)| hash.to_string())
+}
+
+/// Verifies that the SHA-256 digest of the file at `path` matches the `expected` hex encoded digest.
+///
+/// If the digests don't match, the file is deleted so that a corrupted or substituted binary is
+/// never picked up by a later `exists` check.
+///
+/// # Errors
+///
+/// Returns an `Err` if the file cannot be read, or if its digest doesn't match the expected one.
+fn verify_checksum(path: &Path, expected: &str) -> Result<()> {
+    let bytes = fs::read(path)
+        .map_err(|e| Error::msg(format!("Failed to read the downloaded file: {}", e)))?;
+    let digest = hex::encode(Sha256::digest(&bytes));
+    let expected = expected.trim_start_matches("0x");
+
+    if !digest.eq_ignore_ascii_case(expected) {
+        let _ = fs::remove_file(path);
+        return Err(Error::msg(format!(
+            "Checksum mismatch for {}: expected SHA-256 {}, got {}. The downloaded file has been deleted",
+            path.display(),
+            expected,
+            digest
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expected_hashes_are_looked_up_by_version_and_platform() {
+        let table: &[(&str, &[(&str, &str)])] = &[(
+            "v1.3.9",
+            &[("zksolc-linux-amd64-musl-", "aa11"), ("zksolc-macosx-arm64-", "bb22")],
+        )];
+        let v1_3_9: ZkSolcVersion = "1.3.9".parse().unwrap();
+        let mut overrides = BTreeMap::new();
+
+        let linux = ZkSolcOS::Linux.get_compiler();
+        assert_eq!(expected_hash(table, &overrides, &v1_3_9, linux), Some("aa11".to_string()));
+        let mac_arm = ZkSolcOS::MacARM.get_compiler();
+        assert_eq!(expected_hash(table, &overrides, &v1_3_9, mac_arm), Some("bb22".to_string()));
+        let mac_amd = ZkSolcOS::MacAMD.get_compiler();
+        assert_eq!(expected_hash(table, &overrides, &v1_3_9, mac_amd), None);
+        let v1_3_10: ZkSolcVersion = "1.3.10".parse().unwrap();
+        assert_eq!(expected_hash(table, &overrides, &v1_3_10, linux), None);
+
+        overrides.insert("v1.3.9".to_string(), "cc33".to_string());
+        assert_eq!(expected_hash(table, &overrides, &v1_3_9, linux), Some("cc33".to_string()));
+    }
+
+    #[test]
+    fn latest_is_the_newest_stable_supported_release() {
+        assert_eq!(
+            "latest".parse::<ZkSolcVersionSelection>().unwrap(),
+            ZkSolcVersionSelection::Latest
+        );
+        assert_eq!(
+            "v1.3.9".parse::<ZkSolcVersionSelection>().unwrap().to_string(),
+            "v1.3.9".to_string()
+        );
+        assert!("newest".parse::<ZkSolcVersionSelection>().is_err());
+
+        let release =
+            |tag: &str, prerelease: bool| ZkSolcRelease { tag_name: tag.to_string(), prerelease };
+        let releases = vec![
+            release("v1.3.9", false),
+            release("v1.3.10", false),
+            release("v1.3.11", true),
+            release("v1.3.11-rc.1", false),
+            release("v9.9.9", false),
+        ];
+        assert_eq!(latest_stable_version(&releases), Some("v1.3.10".parse().unwrap()));
+        assert_eq!(latest_stable_version(&releases[2..]), None);
+
+        let dir = tempfile::tempdir().unwrap();
+        let cache_file = dir.path().join(ZKSOLC_LATEST_VERSION_CACHE_FILE);
+        assert_eq!(read_latest_version_cache(&cache_file, ZKSOLC_LATEST_VERSION_MAX_AGE), None);
+        fs::write(&cache_file, "v1.3.10").unwrap();
+        assert_eq!(
+            read_latest_version_cache(&cache_file, ZKSOLC_LATEST_VERSION_MAX_AGE),
+            Some("v1.3.10".parse().unwrap())
+        );
+        assert_eq!(read_latest_version_cache(&cache_file, Duration::ZERO), None);
+    }
+
+    // SHA-256 of `hello`
+    const HELLO_SHA256: &str = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+
+    #[test]
+    fn parse_release_tag_rejects_malformed_tags() {
+        assert_eq!(parse_release_tag("v1.3.9"), Some(Version::new(1, 3, 9)));
+        assert_eq!(parse_release_tag("1.3.11"), Some(Version::new(1, 3, 11)));
+        assert_eq!(parse_release_tag("v1.3.x"), None);
+        assert_eq!(parse_release_tag("nightly"), None);
+        assert_eq!(parse_release_tag(""), None);
+    }
+
+    #[test]
+    fn download_times_out_on_slow_server() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            // accept the connection but never respond within the timeout
+            let (_stream, _) = listener.accept().unwrap();
+            std::thread::sleep(Duration::from_secs(5));
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let manager = ZkSolcManager::new(
+            dir.path().to_path_buf(),
+            ZkSolcVersion::default(),
+            get_operating_system().unwrap().get_compiler().to_string(),
+            Url::parse(&format!("http://{addr}")).unwrap(),
+        )
+        .with_timeout(Duration::from_millis(200));
+
+        let err = manager.download().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Download timed out after 0.2s; retry or use --compiler-path to specify a local binary"
+        );
+        assert!(!manager.exists());
+    }
+
+    #[test]
+    fn ensure_version_sets_up_the_directory_and_returns_the_binary() {
+        let dir = tempfile::tempdir().unwrap();
+        let compilers_path = dir.path().join("compilers");
+        let manager = ZkSolcManager::new(
+            compilers_path.clone(),
+            ZkSolcVersion::default(),
+            get_operating_system().unwrap().get_compiler().to_string(),
+            Url::parse("http://localhost").unwrap(),
+        )
+        .with_offline(true);
+
+        let err = manager.ensure_version().unwrap_err();
+        assert!(err.to_string().starts_with("offline mode: zksolc v1.3.11 not found"), "{err}");
+        assert!(compilers_path.is_dir());
+
+        let binary = manager.get_full_compiler_path();
+        fs::write(&binary, "").unwrap();
+        fs::set_permissions(&binary, fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(manager.ensure_version().unwrap(), binary);
+    }
+
+    #[test]
+    fn versions_are_parsed_displayed_and_ordered() {
+        let version: ZkSolcVersion = "v1.3.9".parse().unwrap();
+        assert_eq!(version, "1.3.9".parse().unwrap());
+        assert_eq!(version.to_string(), "v1.3.9");
+        assert!(version < "v1.3.10".parse().unwrap());
+        assert_eq!(ZkSolcVersion::default().to_string(), DEFAULT_ZKSOLC_VERSION);
+
+        assert!(matches!("v1.3".parse::<ZkSolcVersion>(), Err(ZkSolcVersionError::Invalid { .. })));
+        let err = "1.4.0".parse::<ZkSolcVersion>().unwrap_err();
+        assert!(err.to_string().starts_with("ZkSolc compiler version v1.4.0 not supported"));
+    }
+
+    #[test]
+    fn build_validates_the_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let builder = ZkSolcManagerBuilder::new(ZkSolcManagerOpts::new(ZkSolcVersion::default()))
+            .with_compilers_path(dir.path().join("compilers"));
+
+        let manager = builder.clone().with_version("1.3.9").build().unwrap();
+        assert_eq!(manager.get_version().to_string(), "v1.3.9");
+        assert!(matches!(
+            builder.clone().with_version("v2.0.0").build(),
+            Err(ZkSolcManagerBuildError::InvalidVersion(ZkSolcVersionError::Unsupported(_)))
+        ));
+        assert!(matches!(
+            builder.clone().with_download_url(Url::parse("ftp://zksolc.invalid").unwrap()).build(),
+            Err(ZkSolcManagerBuildError::UnsupportedUrlScheme(_))
+        ));
+
+        let readonly = dir.path().join("readonly");
+        fs::create_dir(&readonly).unwrap();
+        fs::set_permissions(&readonly, fs::Permissions::from_mode(0o555)).unwrap();
+        // permissions are not enforced for root
+        if tempfile::tempfile_in(&readonly).is_err() {
+            assert!(matches!(
+                builder.with_compilers_path(readonly.join("compilers")).build(),
+                Err(ZkSolcManagerBuildError::CompilersDirNotWritable { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn version_req_selects_highest_supported_match() {
+        let available: Vec<Version> = ["v1.3.5", "v1.3.9", "v1.3.11", "v1.3.13", "v1.4.0"]
+            .iter()
+            .filter_map(|tag| parse_release_tag(tag))
+            .collect();
+
+        let req = ZkSolcVersionReq::parse(">=1.3.5, <1.3.10").unwrap();
+        assert_eq!(req.select(&available), Some("v1.3.9".parse().unwrap()));
+        // v1.3.13 and v1.4.0 are published but not supported
+        let req = ZkSolcVersionReq::parse(">=1.3.5").unwrap();
+        assert_eq!(req.select(&available), Some("v1.3.11".parse().unwrap()));
+        let req = ZkSolcVersionReq::parse("^1.4").unwrap();
+        assert_eq!(req.select(&available), None);
+    }
+
+    #[test]
+    fn version_req_rejects_invalid_requirements() {
+        let err = ZkSolcVersionReq::parse(">=1.3.x, <").unwrap_err();
+        assert!(err.to_string().starts_with("Invalid zksolc version requirement `>=1.3.x, <`"));
+    }
+
+    #[test]
+    fn offline_download_fails_if_binary_is_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = ZkSolcManager::new(
+            dir.path().to_path_buf(),
+            ZkSolcVersion::default(),
+            "zksolc-linux-amd64-musl-".to_string(),
+            // never reached in offline mode
+            Url::parse("http://127.0.0.1:1").unwrap(),
+        )
+        .with_offline(true);
+
+        let err = manager.download().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "offline mode: zksolc v1.3.11 not found at {}; provision it before running.",
+                dir.path().join("zksolc-linux-amd64-musl-v1.3.11").display()
+            )
+        );
+
+        fs::write(manager.get_full_compiler_path(), "zksolc").unwrap();
+        fs::set_permissions(manager.get_full_compiler_path(), PermissionsExt::from_mode(0o755))
+            .unwrap();
+        manager.download().unwrap();
+    }
+
+    #[test]
+    fn download_waits_for_concurrent_download() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = ZkSolcManager::new(
+            dir.path().to_path_buf(),
+            ZkSolcVersion::default(),
+            "zksolc-linux-amd64-musl-".to_string(),
+            // never reached since the binary is downloaded concurrently
+            Url::parse("http://127.0.0.1:1").unwrap(),
+        )
+        .with_lock_timeout(Duration::from_secs(5));
+
+        let lock_file = File::create(dir.path().join(ZKSOLC_DOWNLOAD_LOCK_FILE)).unwrap();
+        let compiler_path = manager.get_full_compiler_path();
+        let (locked_tx, locked_rx) = std::sync::mpsc::channel();
+        let handle = thread::spawn(move || {
+            let mut lock = RwLock::new(lock_file);
+            let _guard = lock.write().unwrap();
+            locked_tx.send(()).unwrap();
+            thread::sleep(Duration::from_millis(300));
+            fs::write(&compiler_path, "zksolc").unwrap();
+            fs::set_permissions(&compiler_path, PermissionsExt::from_mode(0o755)).unwrap();
+        });
+        locked_rx.recv().unwrap();
+
+        manager.download().unwrap();
+        handle.join().unwrap();
+        assert!(manager.exists());
+    }
+
+    #[test]
+    fn download_lock_times_out() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = ZkSolcManager::new(
+            dir.path().to_path_buf(),
+            ZkSolcVersion::default(),
+            "zksolc-linux-amd64-musl-".to_string(),
+            Url::parse("http://127.0.0.1:1").unwrap(),
+        )
+        .with_lock_timeout(Duration::from_millis(200));
+
+        let mut lock =
+            RwLock::new(File::create(dir.path().join(ZKSOLC_DOWNLOAD_LOCK_FILE)).unwrap());
+        let _guard = lock.write().unwrap();
+
+        let err = manager.download().unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Timed out after 0.2s waiting for another zksolc download"));
+    }
+
+    #[test]
+    fn proxies_from_env_rejects_invalid_urls() {
+        let err =
+            proxies_from_env(|name| (name == "https_proxy").then(|| "::".to_string())).unwrap_err();
+        assert!(err.to_string().contains("Invalid proxy URL in HTTPS_PROXY"));
+        assert!(proxies_from_env(|_| None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn requests_are_routed_through_proxy() {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let proxy = format!("http://{}", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 1024];
+            let read = stream.read(&mut request).unwrap();
+            stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n").unwrap();
+            String::from_utf8_lossy(&request[..read]).to_string()
+        });
+
+        let proxies =
+            proxies_from_env(|name| (name == "ALL_PROXY").then(|| proxy.clone())).unwrap();
+        let mut builder = Client::builder().timeout(Duration::from_secs(5));
+        for proxy in proxies {
+            builder = builder.proxy(proxy);
+        }
+        let response = builder.build().unwrap().get("http://zksolc.invalid/zksolc").send().unwrap();
+        assert_eq!(response.status(), 404);
+
+        // a proxied request carries the absolute URL in its request line
+        let request = handle.join().unwrap();
+        assert!(request.starts_with("GET http://zksolc.invalid/zksolc HTTP/1.1"), "{request}");
+    }
+
+    #[test]
+    fn verify_checksum_accepts_matching_digest() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("zksolc");
+        fs::write(&path, "hello").unwrap();
+
+        verify_checksum(&path, HELLO_SHA256).unwrap();
+        verify_checksum(&path, &format!("0x{}", HELLO_SHA256.to_uppercase())).unwrap();
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn verify_checksum_deletes_file_on_mismatch() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("zksolc");
+        fs::write(&path, "hello, world").unwrap();
+
+        let err = verify_checksum(&path, HELLO_SHA256).unwrap_err();
+        assert!(err.to_string().contains("Checksum mismatch"));
+        assert!(!path.exists());
+    }
+}
@@ -2,7 +2,10 @@ use clap::{CommandFactory, Parser};
 use clap_complete::generate;
 use foundry_cli::{
     cmd::{
-        forge::{cache::CacheSubcommands, watch},
+        forge::{
+            cache::CacheSubcommands, watch, zk_cache::ZkCacheSubcommands,
+            zk_compiler::ZkCompilerSubcommands,
+        },
         Cmd,
     },
     handler,
@@ -119,12 +122,75 @@ fn main() -> eyre::Result<()> {
         Subcommands::Doc(cmd) => {
             cmd.run()?;
         }
-        Subcommands::ZkBuild(cmd) => {
+        Subcommands::ZkAbi(cmd) => {
             cmd.run()?;
         }
+        Subcommands::ZkBuild(cmd) => {
+            if cmd.is_watch() {
+                utils::block_on(watch::watch_zk_build(cmd))?;
+            } else {
+                cmd.run()?;
+            }
+        }
+        Subcommands::ZkCache(cmd) => match cmd.sub {
+            ZkCacheSubcommands::Clean(cmd) => {
+                cmd.run()?;
+            }
+        },
         Subcommands::ZkCreate(cmd) => {
             utils::block_on(cmd.run())?;
         }
+        Subcommands::ZkDeploy(cmd) => {
+            utils::block_on(cmd.run())?;
+        }
+        Subcommands::ZkCompiler(cmd) => match cmd.sub {
+            ZkCompilerSubcommands::Versions(cmd) => {
+                cmd.run()?;
+            }
+            ZkCompilerSubcommands::Use(cmd) => {
+                cmd.run()?;
+            }
+            ZkCompilerSubcommands::Current(cmd) => {
+                cmd.run()?;
+            }
+            ZkCompilerSubcommands::List(cmd) => {
+                cmd.run()?;
+            }
+        },
+        Subcommands::ZkDoc(cmd) => {
+            cmd.run()?;
+        }
+        Subcommands::ZkFlatten(cmd) => {
+            cmd.run()?;
+        }
+        Subcommands::ZkInspect(cmd) => {
+            cmd.run()?;
+        }
+        Subcommands::ZkRemappings(cmd) => {
+            cmd.run()?;
+        }
+        Subcommands::ZkSelectors(cmd) => {
+            cmd.run()?;
+        }
+        Subcommands::ZkSnapshot(cmd) => {
+            utils::block_on(cmd.run())?;
+        }
+        Subcommands::ZkStorageLayout(cmd) => {
+            cmd.run()?;
+        }
+        Subcommands::ZkTest(cmd) => {
+            if cmd.build.is_watch() {
+                utils::block_on(watch::watch_zk_test(cmd))?;
+            } else {
+                utils::block_on(cmd.run())?;
+            }
+        }
+        Subcommands::ZkUpdate(cmd) => {
+            cmd.run()?;
+        }
+        Subcommands::ZkVerify(cmd) => {
+            utils::block_on(cmd.run())?;
+        }
     }
 
     Ok(())
@@ -18,8 +18,22 @@ use crate::cmd::forge::{
     script::ScriptArgs,
     snapshot, test, tree, update,
     verify::{VerifyArgs, VerifyCheckArgs},
+    zk_abi::ZkAbiArgs,
     zk_build::ZkBuildArgs,
+    zk_cache::ZkCacheArgs,
+    zk_compiler::ZkCompilerArgs,
     zk_create::ZkCreateArgs,
+    zk_deploy::ZkDeployArgs,
+    zk_doc::ZkDocArgs,
+    zk_flatten::ZkFlattenArgs,
+    zk_inspect::ZkInspectArgs,
+    zk_remappings::ZkRemappingsArgs,
+    zk_selectors::ZkSelectorsArgs,
+    zk_snapshot::ZkSnapshotArgs,
+    zk_storage_layout::ZkStorageLayoutArgs,
+    zk_test::ZkTestArgs,
+    zk_update::ZkUpdateArgs,
+    zk_verify::ZkVerifyArgs,
 };
 use clap::{Parser, Subcommand, ValueHint};
 use ethers::solc::{artifacts::output_selection::ContractOutputSelection, EvmVersion};
@@ -156,11 +170,53 @@ pub enum Subcommands {
     #[clap(about = "Generate documentation for the project.")]
     Doc(DocArgs),
 
+    #[clap(visible_alias = "zkabi", about = "Print the ABI of a contract compiled with zk-build.")]
+    ZkAbi(ZkAbiArgs),
+
     #[clap(visible_aliases = ["zkb", "zkbuild", "zk-compile"], about = "Build the project's smart contracts for zksync.")]
     ZkBuild(ZkBuildArgs),
 
+    #[clap(visible_alias = "zkcache", about = "Manage the zksync compilation artifacts and caches.")]
+    ZkCache(ZkCacheArgs),
+
     #[clap(visible_aliases = ["zkc", "zkcreate", "zk-deploy"], about = "Deploy smart contracts to zksync.")]
     ZkCreate(ZkCreateArgs),
+
+    #[clap(name = "zk-deploy-artifact", visible_alias = "zkda", about = "Deploy a contract compiled with zk-build to zksync.")]
+    ZkDeploy(ZkDeployArgs),
+
+    #[clap(visible_alias = "zkcompiler", about = "Manage the zksolc compiler versions.")]
+    ZkCompiler(ZkCompilerArgs),
+
+    #[clap(visible_alias = "zkdoc", about = "Render the NatSpec documentation of a contract compiled with zk-build --doc.")]
+    ZkDoc(ZkDocArgs),
+
+    #[clap(visible_alias = "zkf", about = "Flatten a source file and its imports for zksync verification.")]
+    ZkFlatten(ZkFlattenArgs),
+
+    #[clap(visible_alias = "zki", about = "Print a field of the artifact of a contract compiled with zk-build.")]
+    ZkInspect(ZkInspectArgs),
+
+    #[clap(visible_alias = "zkr", about = "Print the remappings zksolc resolves the imports with.")]
+    ZkRemappings(ZkRemappingsArgs),
+
+    #[clap(visible_alias = "zksel", about = "Print the selectors of a contract compiled with zk-build.")]
+    ZkSelectors(ZkSelectorsArgs),
+
+    #[clap(visible_alias = "zks", about = "Create a snapshot of the ergs used by each test on zksync.")]
+    ZkSnapshot(ZkSnapshotArgs),
+
+    #[clap(visible_alias = "zksl", about = "Print the storage layout of a contract compiled with zk-build.")]
+    ZkStorageLayout(ZkStorageLayoutArgs),
+
+    #[clap(visible_aliases = ["zkt", "zktest"], about = "Run the project's tests on zksync.")]
+    ZkTest(ZkTestArgs),
+
+    #[clap(visible_aliases = ["zku", "zkupdate"], about = "Upgrade the zksolc of the project to the latest stable version.")]
+    ZkUpdate(ZkUpdateArgs),
+
+    #[clap(visible_aliases = ["zkv", "zkverify"], about = "Verify a contract on the zksync block explorer.")]
+    ZkVerify(ZkVerifyArgs),
 }
 
 // A set of solc compiler settings that can be set via command line arguments, which are intended
@@ -1403,6 +1403,113 @@ forgetest_init!(can_use_absolute_imports, |prj: TestProject, mut cmd: TestComman
     assert!(stdout.contains("Compiler run successful"));
 });
 
+forgetest_init!(can_zk_build_with_remapped_imports, |prj: TestProject, mut cmd: TestCommand| {
+    let remapping = prj.paths().libraries[0].join("openzeppelin-contracts");
+    let config = Config {
+        remappings: vec![Remapping::from_str(&format!("@openzeppelin/={}", remapping.display()))
+            .unwrap()
+            .into()],
+        ..Default::default()
+    };
+    prj.write_config(config);
+
+    prj.inner()
+        .add_lib(
+            "openzeppelin-contracts/token/ERC20/IERC20.sol",
+            r#"
+    pragma solidity ^0.8.10;
+
+    interface IERC20 {}
+   "#,
+        )
+        .unwrap();
+
+    prj.inner()
+        .add_source(
+            "Token",
+            r#"
+    pragma solidity ^0.8.10;
+    import "@openzeppelin/token/ERC20/IERC20.sol";
+
+    contract Token {}
+   "#,
+        )
+        .unwrap();
+
+    cmd.arg("zk-build");
+    let stdout = cmd.stdout_lossy();
+    assert!(stdout.contains("Compiled Successfully"));
+});
+
+forgetest_init!(can_zk_build_with_debug_output_dir, |prj: TestProject, mut cmd: TestCommand| {
+    let debug_output_dir = prj.root().join("zkdebug");
+
+    cmd.arg("zk-build").arg("--debug-output-dir").arg(&debug_output_dir);
+    let stdout = cmd.stdout_lossy();
+    assert!(stdout.contains("Compiled Successfully"));
+
+    let has_yul = fs::read_dir(&debug_output_dir)
+        .unwrap()
+        .filter_map(Result::ok)
+        .any(|entry| entry.path().extension().map_or(false, |ext| ext == "yul"));
+    assert!(has_yul);
+});
+
+forgetest_init!(can_zk_build_foundry_artifacts, |prj: TestProject, mut cmd: TestCommand| {
+    cmd.arg("zk-build");
+    let stdout = cmd.stdout_lossy();
+    assert!(stdout.contains("Compiled Successfully"));
+
+    let artifact = fs::read_to_string(prj.paths().artifacts.join("zksync/Counter.json")).unwrap();
+    let artifact: serde_json::Value = serde_json::from_str(&artifact).unwrap();
+    assert!(artifact["bytecode"]["object"].as_str().unwrap().starts_with("0x"));
+    assert!(artifact["methodIdentifiers"]["increment()"].is_string());
+});
+
+forgetest_init!(can_zk_build_ast, |prj: TestProject, mut cmd: TestCommand| {
+    cmd.args(["zk-build", "--ast"]);
+    let stdout = cmd.stdout_lossy();
+    assert!(stdout.contains("Compiled Successfully"));
+
+    let ast = prj.paths().artifacts.join("zksync/ast/Counter.sol.json");
+    let ast: serde_json::Value = serde_json::from_str(&fs::read_to_string(ast).unwrap()).unwrap();
+    assert_eq!(ast["nodeType"], "SourceUnit");
+});
+
+forgetest_init!(can_print_zk_storage_layout, |_prj: TestProject, mut cmd: TestCommand| {
+    cmd.args(["zk-build", "--storage-layout"]);
+    let stdout = cmd.stdout_lossy();
+    assert!(stdout.contains("Compiled Successfully"));
+
+    cmd.forge_fuse().args(["zk-storage-layout", "Counter"]);
+    let stdout = cmd.stdout_lossy();
+    assert!(stdout.contains("number"));
+    assert!(stdout.contains("uint256"));
+});
+
+forgetest_init!(can_zk_build_via_ir, |_prj: TestProject, mut cmd: TestCommand| {
+    cmd.args(["zk-build", "--via-ir"]);
+    let stdout = cmd.stdout_lossy();
+    assert!(stdout.contains("Compiled Successfully"));
+});
+
+forgetest_init!(can_zk_build_hashes, |_prj: TestProject, mut cmd: TestCommand| {
+    cmd.args(["zk-build", "--hashes"]);
+    let stdout = cmd.stdout_lossy();
+    assert!(stdout.contains("increment()"));
+    assert!(stdout.contains("0xd09de08a"));
+});
+
+forgetest_init!(
+    zk_build_rejects_via_ir_with_force_evmla,
+    |_prj: TestProject, mut cmd: TestCommand| {
+        cmd.args(["zk-build", "--via-ir", "--force-evmla"]);
+        cmd.assert_err();
+        let stderr = cmd.stderr_lossy();
+        assert!(stderr.contains("cannot be used with"));
+    }
+);
+
 // <https://github.com/foundry-rs/foundry/issues/3440>
 forgetest_init!(
     can_use_absolute_imports_from_test_and_script,
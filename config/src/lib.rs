@@ -67,6 +67,9 @@ pub use error::SolidityErrorCode;
 pub mod doc;
 pub use doc::DocConfig;
 
+pub mod zksync;
+pub use zksync::ZkConfig;
+
 mod warning;
 pub use warning::*;
 
@@ -341,6 +344,8 @@ pub struct Config {
     pub fmt: FormatterConfig,
     /// Configuration for `forge doc`
     pub doc: DocConfig,
+    /// Configuration for the zkSync commands, like `forge zk-build`
+    pub zksync: ZkConfig,
     /// Configures the permissions of cheat codes that touch the file system.
     ///
     /// This includes what operations can be executed (read, write)
@@ -392,7 +397,7 @@ impl Config {
 
     /// Standalone sections in the config which get integrated into the selected profile
     pub const STANDALONE_SECTIONS: &'static [&'static str] =
-        &["rpc_endpoints", "etherscan", "fmt", "doc", "fuzz", "invariant"];
+        &["rpc_endpoints", "etherscan", "fmt", "doc", "fuzz", "invariant", "zksync"];
 
     /// File name of config toml file
     pub const FILE_NAME: &'static str = "foundry.toml";
@@ -1023,6 +1028,23 @@ impl Config {
         Self::with_root(root).into()
     }
 
+    /// Returns the figment of `figment_with_root` with the sections of `profile` selected instead of
+    /// the ones of `FOUNDRY_PROFILE`, see `Config::into_figment_with_profile`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use foundry_config::Config;
+    ///
+    /// let config = Config::figment_with_root_and_profile(".", "release").extract::<Config>();
+    /// ```
+    pub fn figment_with_root_and_profile(
+        root: impl Into<PathBuf>,
+        profile: impl Into<Profile>,
+    ) -> Figment {
+        Self::with_root(root).into_figment_with_profile(profile.into())
+    }
+
     /// Creates a new Config that adds additional context extracted from the provided root.
     ///
     /// # Example
@@ -1145,6 +1167,21 @@ impl Config {
         })
     }
 
+    /// Sets the `use_zksolc` entry of the `[zksync]` section inside a `foundry.toml` file, creating
+    /// the section and the entry if they don't exist yet
+    ///
+    /// **Note:** nothing is written if the `foundry.toml` file doesn't exist, see [Self::update()]
+    ///
+    /// # Errors
+    ///
+    /// An error if the `foundry.toml` could not be parsed.
+    pub fn update_zksolc_version(&self, version: &str) -> eyre::Result<()> {
+        self.update(|doc| {
+            doc[ZkConfig::SECTION]["use_zksolc"] = toml_edit::value(version);
+            true
+        })
+    }
+
     /// Serialize the config type as a String of TOML.
     ///
     /// This serializes to a table with the name of the profile
@@ -1468,12 +1505,12 @@ impl Config {
         figment = figment.merge(provider);
         figment
     }
-}
 
-impl From<Config> for Figment {
-    fn from(c: Config) -> Figment {
-        let profile = Config::selected_profile();
-        let mut figment = Figment::default().merge(DappHardhatDirProvider(&c.__root.0));
+    /// Returns the figment of the config like `Figment::from` does, but with the
+    /// `[profile.<profile>]` sections of `profile` selected instead of the ones of the
+    /// `FOUNDRY_PROFILE` environment variable, see `Config::selected_profile`.
+    pub fn into_figment_with_profile(self, profile: Profile) -> Figment {
+        let mut figment = Figment::default().merge(DappHardhatDirProvider(&self.__root.0));
 
         // merge global foundry.toml file
         if let Some(global_toml) = Config::foundry_dir_toml().filter(|p| p.exists()) {
@@ -1486,7 +1523,7 @@ impl From<Config> for Figment {
         // merge local foundry.toml file
         figment = Config::merge_toml_provider(
             figment,
-            TomlFileProvider::new(Some("FOUNDRY_CONFIG"), c.__root.0.join(Config::FILE_NAME))
+            TomlFileProvider::new(Some("FOUNDRY_CONFIG"), self.__root.0.join(Config::FILE_NAME))
                 .cached(),
             profile.clone(),
         );
@@ -1532,13 +1569,23 @@ impl From<Config> for Figment {
             lib_paths: figment
                 .extract_inner::<Vec<PathBuf>>("libs")
                 .map(Cow::Owned)
-                .unwrap_or_else(|_| Cow::Borrowed(&c.libs)),
-            root: &c.__root.0,
+                .unwrap_or_else(|_| Cow::Borrowed(&self.libs)),
+            root: &self.__root.0,
             remappings: figment.extract_inner::<Vec<Remapping>>("remappings"),
         };
-        let merge = figment.merge(remappings);
+        let merge = figment.merge(RenameProfileProvider::new(
+            remappings,
+            Config::selected_profile(),
+            profile.clone(),
+        ));
 
-        Figment::from(c).merge(merge).select(profile)
+        Figment::from(self).merge(merge).select(profile)
+    }
+}
+
+impl From<Config> for Figment {
+    fn from(c: Config) -> Figment {
+        c.into_figment_with_profile(Config::selected_profile())
     }
 }
 
@@ -1750,6 +1797,7 @@ impl Default for Config {
             build_info_path: None,
             fmt: Default::default(),
             doc: Default::default(),
+            zksync: Default::default(),
             __non_exhaustive: (),
             __warnings: vec![],
         }
@@ -2121,7 +2169,7 @@ impl Provider for DappEnvCompatProvider {
 /// [to]
 /// key = "value"
 /// ```
-struct RenameProfileProvider<P> {
+pub struct RenameProfileProvider<P> {
     provider: P,
     from: Profile,
     to: Profile,
@@ -2760,6 +2808,34 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_can_update_zksolc_version() {
+        figment::Jail::expect_with(|jail| {
+            jail.create_file(
+                "foundry.toml",
+                r#"
+                # the comments and other settings are kept
+                [profile.default]
+                libs = ["node_modules"]
+
+                [zksync]
+                is_system = true
+            "#,
+            )?;
+
+            let config = Config::load();
+            config.update_zksolc_version("v1.3.9").unwrap();
+
+            let config = Config::load();
+            assert_eq!(config.zksync.use_zksolc, Some("v1.3.9".to_string()));
+            assert!(config.zksync.is_system);
+            assert_eq!(config.libs, vec![PathBuf::from("node_modules")]);
+            let content = fs::read_to_string(jail.directory().join("foundry.toml")).unwrap();
+            assert!(content.contains("# the comments and other settings are kept"));
+            Ok(())
+        });
+    }
+
     #[test]
     fn test_large_gas_limit() {
         figment::Jail::expect_with(|jail| {
@@ -3937,6 +4013,73 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_figment_with_profile() {
+        figment::Jail::expect_with(|jail| {
+            jail.create_file(
+                "foundry.toml",
+                r#"
+                [profile.default]
+                optimizer_runs = 200
+
+                [profile.release]
+                optimizer_runs = 1000
+
+                [profile.release.zksync]
+                is_system = true
+            "#,
+            )?;
+
+            let figment = Config::figment_with_root_and_profile(jail.directory(), "release");
+            let config = Config::try_from(figment).unwrap();
+            assert_eq!(config.profile, Profile::new("release"));
+            assert_eq!(config.optimizer_runs, 1000);
+            assert!(config.zksync.is_system);
+
+            let config = Config::load();
+            assert_eq!(config.optimizer_runs, 200);
+            assert!(!config.zksync.is_system);
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_zksync_config() {
+        figment::Jail::expect_with(|jail| {
+            jail.create_file(
+                "foundry.toml",
+                r#"
+                [zksync]
+                use_zksolc = "v1.3.9"
+                is_system = true
+            "#,
+            )?;
+            let loaded = Config::load().sanitized();
+            assert_eq!(
+                loaded.zksync,
+                ZkConfig {
+                    use_zksolc: Some("v1.3.9".to_string()),
+                    is_system: true,
+                    force_evmla: false,
+                }
+            );
+
+            jail.set_env("FOUNDRY_ZKSYNC_USE_ZKSOLC", "v1.3.11");
+            jail.set_env("FOUNDRY_ZKSYNC_FORCE_EVMLA", "true");
+            let loaded = Config::load().sanitized();
+            assert_eq!(
+                loaded.zksync,
+                ZkConfig {
+                    use_zksolc: Some("v1.3.11".to_string()),
+                    is_system: true,
+                    force_evmla: true,
+                }
+            );
+
+            Ok(())
+        });
+    }
+
     #[test]
     fn test_invariant_config() {
         figment::Jail::expect_with(|jail| {
@@ -0,0 +1,27 @@
+//! Configuration specific to the zkSync commands, like `forge zk-build`
+
+use serde::{Deserialize, Serialize};
+
+/// Contains the config for compiling contracts for zkSync, set in the `[zksync]` section
+///
+/// Every setting can be overridden with a `FOUNDRY_ZKSYNC_` environment variable, like
+/// `FOUNDRY_ZKSYNC_USE_ZKSOLC=v1.3.11`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ZkConfig {
+    /// The zksolc version to use, for example `v1.3.11`.
+    ///
+    /// If not set, the default version of `forge zk-build` is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub use_zksolc: Option<String>,
+    /// Enable the system contract compilation mode.
+    #[serde(default)]
+    pub is_system: bool,
+    /// Forcibly switch to the EVM legacy assembly pipeline.
+    #[serde(default)]
+    pub force_evmla: bool,
+}
+
+impl ZkConfig {
+    /// The name of the section of `foundry.toml` the zkSync config is set in.
+    pub const SECTION: &'static str = "zksync";
+}